@@ -1,5 +1,18 @@
 /* auto-generated by NAPI-RS */
 /* eslint-disable */
 
-export function nativeUVFib(): Promise<number>;
+export function nativeUVFib(n?: number, algorithm?: 'recursive' | 'iterative' | 'matrix' | 'memoized'): Promise<number | bigint>;
+export function nativeFibBatch(ns: number[], algorithm?: 'recursive' | 'iterative' | 'matrix' | 'memoized'): Promise<Array<number | bigint>>;
+export function nativeFibWithProgress(n: number, algorithm?: 'recursive' | 'iterative' | 'matrix' | 'memoized', onProgress?: (progress: number) => void, signal?: AbortSignal): Promise<number | bigint>;
+export interface CacheStats {
+  hits: number;
+  misses: number;
+  evictions: number;
+  entries: number;
+  bytes: number;
+  capacity: number;
+}
+export function cacheStats(): CacheStats;
+export function clearCache(): void;
+export function setCacheCapacity(bytes: number): void;
 
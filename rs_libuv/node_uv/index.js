@@ -329,3 +329,8 @@ if (!nativeBinding) {
 }
 
 module.exports.nativeUVFib = nativeBinding.nativeUVFib
+module.exports.nativeFibBatch = nativeBinding.nativeFibBatch
+module.exports.nativeFibWithProgress = nativeBinding.nativeFibWithProgress
+module.exports.cacheStats = nativeBinding.cacheStats
+module.exports.clearCache = nativeBinding.clearCache
+module.exports.setCacheCapacity = nativeBinding.setCacheCapacity
@@ -1,16 +1,86 @@
 const http = require('http');
-const { nativeUVFib } = require("./index");
+const { nativeUVFib, nativeFibBatch, nativeFibWithProgress, cacheStats, clearCache } = require("./index");
+
+// 同一轮事件循环中到达的请求合并成一次 nativeFibBatch 调用，只占用一个 libuv 任务
+let pending = [];
+
+function fib(n) {
+    return new Promise((resolve, reject) => {
+        if (pending.length === 0) {
+            setImmediate(flush);
+        }
+        pending.push({ n, resolve, reject });
+    });
+}
+
+function flush() {
+    const batch = pending;
+    pending = [];
+    let results;
+    try {
+        results = nativeFibBatch(batch.map(({ n }) => n));
+    } catch {
+        // 有不合法的 n 时整批都会被拒绝，逐个计算，让每个请求得到自己的结果或者错误
+        for (const { n, resolve, reject } of batch) {
+            try {
+                nativeUVFib(n).then(resolve, reject);
+            } catch (err) {
+                reject(err);
+            }
+        }
+        return;
+    }
+    results.then(
+        (values) => batch.forEach(({ resolve }, i) => resolve(values[i])),
+        (err) => batch.forEach(({ reject }) => reject(err)),
+    );
+}
+
+// 单独计算一个请求，超时或者客户端断开连接时停止计算。
+// 两种情况都 abort 同一个 controller，不依赖 Node.js 20.3 才有的 AbortSignal.any
+async function fibWithTimeout(n, timeout, res) {
+    const controller = new AbortController();
+    const abort = () => controller.abort();
+    const timer = setTimeout(abort, timeout);
+    res.on('close', abort);
+    try {
+        return await nativeFibWithProgress(n, undefined, undefined, controller.signal);
+    } finally {
+        clearTimeout(timer);
+        res.off('close', abort);
+    }
+}
 
 const server = http.createServer(async (req, res) => {
-    if (req.url === '/') {
+    const url = new URL(req.url, 'http://localhost');
+    if (url.pathname === '/') {
         res.end("Hello World")
+    } else if (url.pathname === '/cache') {
+        // GET /cache 查看缓存的命中情况，DELETE /cache 清空缓存
+        if (req.method === 'DELETE') {
+            clearCache();
+        }
+        res.setHeader('Content-Type', 'application/json');
+        res.end(JSON.stringify(cacheStats()));
     } else {
-        const result = await nativeUVFib();
-        res.end(`${result}`);
+        // GET /fib?n=30&timeout=100，不传 n 时计算 fib(20)；传了 timeout（毫秒）时不参与合并，超时返回 504
+        const n = url.searchParams.has('n') ? Number(url.searchParams.get('n')) : 20;
+        const timeout = Number(url.searchParams.get('timeout'));
+        try {
+            const result = timeout > 0 ? await fibWithTimeout(n, timeout, res) : await fib(n);
+            res.end(`${result}`);
+        } catch (err) {
+            if (err.name === 'AbortError') {
+                res.statusCode = 504;
+            } else {
+                res.statusCode = err instanceof RangeError || err instanceof TypeError ? 400 : 500;
+            }
+            res.end(err.message);
+        }
     }
 });
 
 const port = 3000;
 server.listen(port, () => {
     console.log(`Server running at http://localhost:${port}/`);
-});
\ No newline at end of file
+});
@@ -0,0 +1,41 @@
+// 只供 rs_napi/crates/bench 使用的导出，和 apisecond 的 arg_count、add、join 一一对应，
+// 用来比较两种实现的调用开销。需要开启 bench feature，默认构建的模块和 index.d.ts 中没有它们
+use napi::{CallContext, JsNumber, JsObject, JsString, Result};
+use napi_derive::js_function;
+
+pub fn register(exports: &mut JsObject) -> Result<()> {
+  exports.create_named_method("argCount", arg_count)?;
+  exports.create_named_method("add", add)?;
+  exports.create_named_method("join", join)?;
+  Ok(())
+}
+
+// 返回调用时实际传入的参数个数，不做任何参数转换
+#[js_function(0)]
+pub fn arg_count(ctx: CallContext) -> Result<JsNumber> {
+  ctx.env.create_uint32(ctx.length as u32)
+}
+
+// 返回两个数的和
+#[js_function(2)]
+pub fn add(ctx: CallContext) -> Result<JsNumber> {
+  let left = ctx.get::<JsNumber>(0)?.get_double()?;
+  let right = ctx.get::<JsNumber>(1)?.get_double()?;
+  ctx.env.create_double(left + right)
+}
+
+// 把一组字符串用分隔符连接起来
+#[js_function(2)]
+pub fn join(ctx: CallContext) -> Result<JsString> {
+  let parts = ctx.get::<JsObject>(0)?;
+  let separator = ctx.get::<JsString>(1)?.into_utf8()?.into_owned()?;
+  let parts = (0..parts.get_array_length()?)
+    .map(|index| {
+      parts
+        .get_element::<JsString>(index)?
+        .into_utf8()?
+        .into_owned()
+    })
+    .collect::<Result<Vec<_>>>()?;
+  ctx.env.create_string(&parts.join(&separator))
+}
@@ -0,0 +1,305 @@
+use std::collections::{BTreeMap, HashMap};
+use std::mem;
+use std::sync::{Arc, LazyLock, Mutex, MutexGuard};
+
+use crate::fib::{Algorithm, BigUint};
+
+// 进程内所有 env 共享同一个缓存：动态库在进程中只加载一次，worker_threads 中加载的模块访问的也是这个 static
+static CACHE: LazyLock<Mutex<Cache>> = LazyLock::new(|| Mutex::new(Cache::new(CAPACITY)));
+
+// 默认的总大小上限（字节），可以通过 set_capacity 修改。fib(100000) 大约占 9KB
+const CAPACITY: usize = 64 * 1024 * 1024;
+
+// 每个条目除了结果本身之外的开销，按 HashMap 和 BTreeMap 中各一项粗略估计
+const ENTRY_OVERHEAD: usize = 64;
+
+// 不同算法的结果虽然相同，但调用者指定算法通常是为了比较它们的耗时，
+// 所以缓存按 (算法, n) 区分，不会用一种算法的结果代替另一种算法的计算
+type Key = (Algorithm, u32);
+
+struct Entry {
+  value: Arc<BigUint>,
+  // 最近一次访问的序号，也是 order 中的 key
+  tick: u64,
+}
+
+// 按总大小限制的 LRU 缓存，超过容量时淘汰最久没有访问的结果
+struct Cache {
+  entries: HashMap<Key, Entry>,
+  // 按访问顺序排列的 key
+  order: BTreeMap<u64, Key>,
+  tick: u64,
+  bytes: usize,
+  capacity: usize,
+  hits: u64,
+  misses: u64,
+  evictions: u64,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Stats {
+  pub hits: u64,
+  pub misses: u64,
+  pub evictions: u64,
+  pub entries: usize,
+  pub bytes: usize,
+  pub capacity: usize,
+}
+
+fn entry_size(value: &BigUint) -> usize {
+  mem::size_of_val(value.words()) + ENTRY_OVERHEAD
+}
+
+impl Cache {
+  fn new(capacity: usize) -> Cache {
+    Cache {
+      entries: HashMap::new(),
+      order: BTreeMap::new(),
+      tick: 0,
+      bytes: 0,
+      capacity,
+      hits: 0,
+      misses: 0,
+      evictions: 0,
+    }
+  }
+
+  fn next_tick(&mut self) -> u64 {
+    self.tick += 1;
+    self.tick
+  }
+
+  fn get(&mut self, key: Key) -> Option<Arc<BigUint>> {
+    let tick = self.next_tick();
+    match self.entries.get_mut(&key) {
+      Some(entry) => {
+        self.order.remove(&entry.tick);
+        self.order.insert(tick, key);
+        entry.tick = tick;
+        self.hits += 1;
+        Some(entry.value.clone())
+      }
+      None => {
+        self.misses += 1;
+        None
+      }
+    }
+  }
+
+  fn insert(&mut self, key: Key, value: Arc<BigUint>) {
+    let size = entry_size(&value);
+    // 比整个缓存还大的结果不缓存，避免清空其他所有条目
+    if size > self.capacity {
+      return;
+    }
+    let tick = self.next_tick();
+    if let Some(old) = self.entries.insert(key, Entry { value, tick }) {
+      // 并发计算同一个 key 时后完成的覆盖先完成的，结果相同
+      self.order.remove(&old.tick);
+      self.bytes -= entry_size(&old.value);
+    }
+    self.order.insert(tick, key);
+    self.bytes += size;
+    self.evict();
+  }
+
+  // 淘汰最久没有访问的结果，直到总大小不超过容量
+  fn evict(&mut self) {
+    while self.bytes > self.capacity {
+      let (_, oldest) = self
+        .order
+        .pop_first()
+        .expect("cache is over capacity but empty");
+      let entry = self.entries.remove(&oldest).unwrap();
+      self.bytes -= entry_size(&entry.value);
+      self.evictions += 1;
+    }
+  }
+}
+
+fn cache() -> MutexGuard<'static, Cache> {
+  // 持有锁时不会 panic，锁不会被污染；即使被污染，缓存的内容也仍然一致
+  CACHE.lock().unwrap_or_else(|err| err.into_inner())
+}
+
+// 读取缓存中用 algorithm 算出的 fib(n)，同时记录命中或者未命中
+pub fn get(algorithm: Algorithm, n: u32) -> Option<Arc<BigUint>> {
+  cache().get((algorithm, n))
+}
+
+pub fn insert(algorithm: Algorithm, n: u32, value: Arc<BigUint>) {
+  cache().insert((algorithm, n), value)
+}
+
+// 优先从缓存中读取 fib(n)，未命中时用 algorithm 计算并放入缓存。计算过程中不持有锁
+pub fn fib(algorithm: Algorithm, n: u32) -> Arc<BigUint> {
+  if let Some(value) = get(algorithm, n) {
+    return value;
+  }
+  let value = Arc::new(algorithm.compute(n));
+  insert(algorithm, n, value.clone());
+  value
+}
+
+pub fn stats() -> Stats {
+  let cache = cache();
+  Stats {
+    hits: cache.hits,
+    misses: cache.misses,
+    evictions: cache.evictions,
+    entries: cache.entries.len(),
+    bytes: cache.bytes,
+    capacity: cache.capacity,
+  }
+}
+
+// 修改缓存的总大小上限，超出新容量的结果立即淘汰。容量为 0 时不缓存任何结果
+pub fn set_capacity(capacity: usize) {
+  let mut cache = cache();
+  cache.capacity = capacity;
+  cache.evict();
+}
+
+// 清空所有条目并把统计数据归零，容量保持不变
+pub fn clear() {
+  let mut cache = cache();
+  *cache = Cache::new(cache.capacity);
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  // 占用 words 个 64 位字的结果：(2^64 - 1)^words
+  fn value(words: usize) -> Arc<BigUint> {
+    let mut value = BigUint::from(u64::MAX);
+    for _ in 1..words {
+      value = value.mul(&BigUint::from(u64::MAX));
+    }
+    assert_eq!(value.words().len(), words);
+    Arc::new(value)
+  }
+
+  fn size(words: usize) -> usize {
+    entry_size(&value(words))
+  }
+
+  // 测试中的条目都用 iterative 算法的 key
+  fn key(n: u32) -> Key {
+    (Algorithm::Iterative, n)
+  }
+
+  fn keys(cache: &Cache) -> Vec<u32> {
+    cache.order.values().map(|&(_, n)| n).collect()
+  }
+
+  fn total(cache: &Cache) -> usize {
+    cache
+      .entries
+      .values()
+      .map(|entry| entry_size(&entry.value))
+      .sum()
+  }
+
+  #[test]
+  fn get_moves_an_entry_to_the_back_of_the_eviction_order() {
+    let mut cache = Cache::new(3 * size(1));
+    for n in 1..=3 {
+      cache.insert(key(n), value(1));
+    }
+    assert!(cache.get(key(1)).is_some());
+    assert_eq!(keys(&cache), [2, 3, 1]);
+
+    // 淘汰最久没有访问的 2，而不是最早插入的 1
+    cache.insert(key(4), value(1));
+    assert_eq!(keys(&cache), [3, 1, 4]);
+    assert!(cache.get(key(2)).is_none());
+    assert_eq!(cache.evictions, 1);
+    assert_eq!((cache.hits, cache.misses), (1, 1));
+  }
+
+  #[test]
+  fn bytes_is_the_sum_of_entry_sizes() {
+    let mut cache = Cache::new(CAPACITY);
+    cache.insert(key(1), value(1));
+    cache.insert(key(2), value(3));
+    assert_eq!(cache.bytes, size(1) + size(3));
+    assert_eq!(cache.bytes, total(&cache));
+
+    // 替换同一个 n 时减去旧条目的大小
+    cache.insert(key(1), value(2));
+    assert_eq!(cache.bytes, size(2) + size(3));
+    assert_eq!(cache.bytes, total(&cache));
+    assert_eq!(keys(&cache), [2, 1]);
+    assert_eq!(cache.entries.len(), 2);
+  }
+
+  #[test]
+  fn entries_larger_than_the_capacity_are_not_cached() {
+    let mut cache = Cache::new(2 * size(1));
+    cache.insert(key(1), value(1));
+    cache.insert(key(2), value(1));
+    assert!(size(16) > cache.capacity);
+    cache.insert(key(3), value(16));
+    assert!(cache.get(key(3)).is_none());
+    // 其他条目没有被淘汰
+    assert_eq!(keys(&cache), [1, 2]);
+    assert_eq!(cache.evictions, 0);
+    assert_eq!(cache.bytes, 2 * size(1));
+  }
+
+  #[test]
+  fn zero_capacity_evicts_and_caches_nothing() {
+    let mut cache = Cache::new(CAPACITY);
+    cache.insert(key(1), value(1));
+    cache.insert(key(2), value(2));
+    cache.capacity = 0;
+    cache.evict();
+    assert!(cache.entries.is_empty() && cache.order.is_empty());
+    assert_eq!((cache.bytes, cache.evictions), (0, 2));
+
+    cache.insert(key(3), BigUint::from(0).into());
+    assert!(cache.get(key(3)).is_none());
+    assert_eq!(cache.bytes, 0);
+  }
+
+  #[test]
+  fn clear_resets_entries_and_stats_but_keeps_the_capacity() {
+    let capacity = 2 * size(1);
+    let mut cache = Cache::new(capacity);
+    for n in 1..=3 {
+      cache.insert(key(n), value(1));
+    }
+    cache.get(key(3));
+    cache.get(key(1));
+    // 和 clear() 中的写法相同
+    cache = Cache::new(cache.capacity);
+    assert!(cache.entries.is_empty() && cache.order.is_empty());
+    assert_eq!(
+      (cache.bytes, cache.hits, cache.misses, cache.evictions),
+      (0, 0, 0, 0)
+    );
+    assert_eq!(cache.capacity, capacity);
+
+    cache.insert(key(1), value(1));
+    assert!(cache.get(key(1)).is_some());
+  }
+
+  #[test]
+  fn results_are_cached_per_algorithm() {
+    let mut cache = Cache::new(CAPACITY);
+    cache.insert((Algorithm::Memoized, 30), value(1));
+    assert!(cache.get((Algorithm::Recursive, 30)).is_none());
+    assert!(cache.get((Algorithm::Memoized, 30)).is_some());
+    assert_eq!((cache.hits, cache.misses), (1, 1));
+  }
+
+  #[test]
+  fn a_cached_result_does_not_short_circuit_another_algorithm() {
+    // 放入一个错误的结果，命中缓存时就能看出来。进程内只有这个测试使用 n = 23
+    let wrong = Arc::new(BigUint::from(0));
+    insert(Algorithm::Memoized, 23, wrong.clone());
+    assert!(Arc::ptr_eq(&fib(Algorithm::Memoized, 23), &wrong));
+    assert_eq!(*fib(Algorithm::Recursive, 23), BigUint::from(28657));
+  }
+}
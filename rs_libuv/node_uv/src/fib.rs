@@ -0,0 +1,493 @@
+use std::collections::HashMap;
+use std::panic;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+// 斐波那契数的几种算法，结果都用 BigUint 表示，不会溢出
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Algorithm {
+  // 指数复杂度的朴素递归，和 node_worker 中的 JS 实现一致，用来做性能对比
+  Recursive,
+  // 自底向上迭代，O(n) 次大整数加法
+  Iterative,
+  // 矩阵快速幂（fast doubling），O(log n) 次大整数乘法
+  Matrix,
+  // 带备忘录的自顶向下递归
+  Memoized,
+}
+
+impl Algorithm {
+  pub fn parse(name: &str) -> Option<Algorithm> {
+    match name {
+      "recursive" => Some(Algorithm::Recursive),
+      "iterative" => Some(Algorithm::Iterative),
+      "matrix" => Some(Algorithm::Matrix),
+      "memoized" => Some(Algorithm::Memoized),
+      _ => None,
+    }
+  }
+
+  pub fn name(self) -> &'static str {
+    match self {
+      Algorithm::Recursive => "recursive",
+      Algorithm::Iterative => "iterative",
+      Algorithm::Matrix => "matrix",
+      Algorithm::Memoized => "memoized",
+    }
+  }
+
+  // 每种算法允许的最大 n：递归再大就无法在合理时间内完成，备忘录递归的深度是 n，受限于 STACK_SIZE，
+  // 其他两种限制结果的大小（fib(100000) 大约有 2 万位十进制数）
+  pub fn max_n(self) -> u32 {
+    match self {
+      Algorithm::Recursive => 40,
+      Algorithm::Memoized => 10_000,
+      Algorithm::Iterative | Algorithm::Matrix => 100_000,
+    }
+  }
+
+  pub fn compute(self, n: u32) -> BigUint {
+    match self {
+      // 递归的每次调用都要记录进度，不需要进度时单独计算，保持和 JS 实现对比时的性能
+      Algorithm::Recursive => BigUint::from(plain_recursive(n)),
+      _ => self
+        .compute_with_progress(n, &mut |_| true)
+        .expect("computation without cancellation never stops early"),
+    }
+  }
+
+  // 计算过程中用 0 到 1 之间的进度调用 report，report 返回 false 时停止计算并返回 None
+  pub fn compute_with_progress(
+    self,
+    n: u32,
+    report: &mut (dyn FnMut(f64) -> bool + Send),
+  ) -> Option<BigUint> {
+    // 各个算法的总步数：递归是函数调用的次数 2 * fib(n + 1) - 1，矩阵快速幂是 n 的二进制位数
+    let total = match self {
+      Algorithm::Recursive => 2 * fib_u64(n + 1) - 1,
+      Algorithm::Iterative => n as u64,
+      Algorithm::Matrix => (u32::BITS - n.leading_zeros()) as u64,
+      Algorithm::Memoized => n.saturating_sub(1) as u64,
+    };
+    let mut progress = Progress::new(total, report);
+    let value = match self {
+      Algorithm::Recursive => BigUint::from(recursive(n, &mut progress)?),
+      Algorithm::Iterative => iterative(n, &mut progress)?,
+      Algorithm::Matrix => matrix(n, &mut progress)?.0,
+      Algorithm::Memoized => on_large_stack(|| memoized(n, &mut HashMap::new(), &mut progress))?,
+    };
+    progress.finish();
+    Some(value)
+  }
+}
+
+// 每完成大约 1% 的步骤调用一次 report，避免频繁地跨线程通知 JS
+struct Progress<'a> {
+  done: u64,
+  total: u64,
+  next: u64,
+  // 上一次报告时的 done
+  reported: u64,
+  report: &'a mut (dyn FnMut(f64) -> bool + Send),
+}
+
+impl<'a> Progress<'a> {
+  fn new(total: u64, report: &'a mut (dyn FnMut(f64) -> bool + Send)) -> Progress<'a> {
+    let total = total.max(1);
+    Progress {
+      done: 0,
+      total,
+      next: Progress::step(total),
+      reported: 0,
+      report,
+    }
+  }
+
+  fn step(total: u64) -> u64 {
+    (total / 100).max(1)
+  }
+
+  // 完成了一个步骤，返回 false 时应该停止计算
+  fn advance(&mut self) -> bool {
+    self.done += 1;
+    if self.done < self.next && self.done < self.total {
+      return true;
+    }
+    self.next = self.done + Progress::step(self.total);
+    self.reported = self.done;
+    (self.report)(self.done.min(self.total) as f64 / self.total as f64)
+  }
+
+  // 计算完成时报告 1，最后一个步骤已经报告过时不再重复
+  fn finish(&mut self) {
+    if self.reported < self.total {
+      (self.report)(1.0);
+    }
+  }
+}
+
+fn fib_u64(n: u32) -> u64 {
+  (0..n).fold((0, 1), |(a, b), _| (b, a + b)).0
+}
+
+// 对一组 n 调用 compute 计算 fib(n)，结果和 ns 的顺序相同。重复的 n 只计算一次，不同的 n 在当前线程和若干个
+// scoped 线程中并行计算：每个线程通过原子计数器领取下一个 n，各个 n 的耗时差别很大时也不会有线程空等
+pub fn compute_batch<T: Clone + Send>(ns: &[u32], compute: impl Fn(u32) -> T + Sync) -> Vec<T> {
+  let mut unique = ns.to_vec();
+  unique.sort_unstable();
+  unique.dedup();
+  let threads = thread::available_parallelism()
+    .map_or(1, usize::from)
+    .min(unique.len());
+  let next = AtomicUsize::new(0);
+  let work = || {
+    let mut done = Vec::new();
+    loop {
+      let index = next.fetch_add(1, Ordering::Relaxed);
+      match unique.get(index) {
+        Some(&n) => done.push((index, compute(n))),
+        None => return done,
+      }
+    }
+  };
+  let mut results = vec![None; unique.len()];
+  thread::scope(|scope| {
+    let handles = (1..threads).map(|_| scope.spawn(work)).collect::<Vec<_>>();
+    let mut done = work();
+    for handle in handles {
+      done.extend(
+        handle
+          .join()
+          .unwrap_or_else(|err| panic::resume_unwind(err)),
+      );
+    }
+    for (index, value) in done {
+      results[index] = Some(value);
+    }
+  });
+  ns.iter()
+    .map(|n| {
+      let index = unique.binary_search(n).unwrap();
+      results[index].clone().unwrap()
+    })
+    .collect()
+}
+
+// 备忘录递归的深度是 n，max_n 时需要的栈比 Rust 线程默认的 2MB 大，也不能依赖调用者（libuv 线程池、
+// compute_batch 的线程）的栈大小，所以总是在单独的线程中计算
+const STACK_SIZE: usize = 16 * 1024 * 1024;
+
+// 在栈大小为 STACK_SIZE 的线程中执行 f 并等待它完成，f 中的 panic 在当前线程中继续
+fn on_large_stack<T: Send>(f: impl FnOnce() -> T + Send) -> T {
+  thread::scope(|scope| {
+    thread::Builder::new()
+      .stack_size(STACK_SIZE)
+      .spawn_scoped(scope, f)
+      .expect("failed to spawn fibonacci thread")
+      .join()
+      .unwrap_or_else(|err| panic::resume_unwind(err))
+  })
+}
+
+fn plain_recursive(n: u32) -> u64 {
+  match n {
+    0 | 1 => n as u64,
+    _ => plain_recursive(n - 1) + plain_recursive(n - 2),
+  }
+}
+
+fn recursive(n: u32, progress: &mut Progress) -> Option<u64> {
+  if !progress.advance() {
+    return None;
+  }
+  match n {
+    0 | 1 => Some(n as u64),
+    _ => Some(recursive(n - 1, progress)? + recursive(n - 2, progress)?),
+  }
+}
+
+fn iterative(n: u32, progress: &mut Progress) -> Option<BigUint> {
+  let (mut a, mut b) = (BigUint::from(0), BigUint::from(1));
+  for _ in 0..n {
+    let next = a.add(&b);
+    a = b;
+    b = next;
+    if !progress.advance() {
+      return None;
+    }
+  }
+  Some(a)
+}
+
+// 返回 (fib(n), fib(n + 1))，由 [[1, 1], [1, 0]]^n 推出：
+// fib(2k) = fib(k) * (2 * fib(k + 1) - fib(k))，fib(2k + 1) = fib(k)^2 + fib(k + 1)^2
+fn matrix(n: u32, progress: &mut Progress) -> Option<(BigUint, BigUint)> {
+  if n == 0 {
+    return Some((BigUint::from(0), BigUint::from(1)));
+  }
+  let (a, b) = matrix(n / 2, progress)?;
+  let c = a.mul(&b.add(&b).sub(&a));
+  let d = a.mul(&a).add(&b.mul(&b));
+  if !progress.advance() {
+    return None;
+  }
+  if n % 2 == 0 {
+    Some((c, d))
+  } else {
+    let next = c.add(&d);
+    Some((d, next))
+  }
+}
+
+fn memoized(n: u32, memo: &mut HashMap<u32, BigUint>, progress: &mut Progress) -> Option<BigUint> {
+  if n < 2 {
+    return Some(BigUint::from(n as u64));
+  }
+  if let Some(value) = memo.get(&n) {
+    return Some(value.clone());
+  }
+  // 先算 n - 1，n - 2 此时已经在备忘录中，递归深度是 n 而不是 2^n
+  let value = memoized(n - 1, memo, progress)?.add(&memoized(n - 2, memo, progress)?);
+  memo.insert(n, value.clone());
+  if !progress.advance() {
+    return None;
+  }
+  Some(value)
+}
+
+// 只支持加减乘的无符号大整数，按 64 位分段、低位在前存储，可以直接作为 BigInt 的 words 交给 JS
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BigUint(Vec<u64>);
+
+impl From<u64> for BigUint {
+  fn from(value: u64) -> BigUint {
+    BigUint(vec![value]).normalize()
+  }
+}
+
+impl BigUint {
+  // 去掉高位的 0，0 表示为空的 Vec
+  fn normalize(mut self) -> BigUint {
+    while self.0.last() == Some(&0) {
+      self.0.pop();
+    }
+    self
+  }
+
+  pub fn words(&self) -> &[u64] {
+    &self.0
+  }
+
+  // 能用 u32 表示时返回对应的值
+  pub fn to_u32(&self) -> Option<u32> {
+    match self.0.as_slice() {
+      [] => Some(0),
+      [word] => u32::try_from(*word).ok(),
+      _ => None,
+    }
+  }
+
+  pub fn add(&self, other: &BigUint) -> BigUint {
+    let len = self.0.len().max(other.0.len());
+    let mut words = Vec::with_capacity(len + 1);
+    let mut carry = 0;
+    for i in 0..len {
+      let sum = self.word(i) as u128 + other.word(i) as u128 + carry;
+      words.push(sum as u64);
+      carry = sum >> 64;
+    }
+    words.push(carry as u64);
+    BigUint(words).normalize()
+  }
+
+  // 调用者保证 self >= other
+  pub fn sub(&self, other: &BigUint) -> BigUint {
+    let mut words = Vec::with_capacity(self.0.len());
+    let mut borrow = false;
+    for i in 0..self.0.len() {
+      let (diff, overflow) = self.word(i).overflowing_sub(other.word(i));
+      let (diff, underflow) = diff.overflowing_sub(borrow as u64);
+      words.push(diff);
+      borrow = overflow || underflow;
+    }
+    debug_assert!(
+      !borrow && other.0.len() <= self.0.len(),
+      "BigUint subtraction underflow"
+    );
+    BigUint(words).normalize()
+  }
+
+  pub fn mul(&self, other: &BigUint) -> BigUint {
+    let mut words = vec![0u64; self.0.len() + other.0.len()];
+    for (i, &a) in self.0.iter().enumerate() {
+      let mut carry = 0u128;
+      for (j, &b) in other.0.iter().enumerate() {
+        let product = a as u128 * b as u128 + words[i + j] as u128 + carry;
+        words[i + j] = product as u64;
+        carry = product >> 64;
+      }
+      words[i + other.0.len()] = carry as u64;
+    }
+    BigUint(words).normalize()
+  }
+
+  fn word(&self, index: usize) -> u64 {
+    self.0.get(index).copied().unwrap_or(0)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const ALGORITHMS: [Algorithm; 4] = [
+    Algorithm::Recursive,
+    Algorithm::Iterative,
+    Algorithm::Matrix,
+    Algorithm::Memoized,
+  ];
+
+  fn from_u128(value: u128) -> BigUint {
+    BigUint(vec![value as u64, (value >> 64) as u64]).normalize()
+  }
+
+  // 每种算法在允许范围内的已知值，覆盖 64 位的边界：fib(93) 是最大的 u64 斐波那契数，fib(94) 需要两个字
+  #[test]
+  fn algorithms_match_known_values() {
+    let known = [
+      (0, BigUint::from(0)),
+      (1, BigUint::from(1)),
+      (2, BigUint::from(1)),
+      (40, BigUint::from(102_334_155)),
+      (93, BigUint::from(12_200_160_415_121_876_738)),
+      (94, from_u128(19_740_274_219_868_223_167)),
+      (
+        200,
+        BigUint(vec![0xf067cb83df17e395, 0x864a5c1caeb07d0e, 0x338]),
+      ),
+    ];
+    for algorithm in ALGORITHMS {
+      for (n, expected) in &known {
+        if *n > algorithm.max_n() {
+          continue;
+        }
+        assert_eq!(
+          &algorithm.compute(*n),
+          expected,
+          "{:?} fib({})",
+          algorithm,
+          n
+        );
+        let with_progress = algorithm.compute_with_progress(*n, &mut |_| true);
+        assert_eq!(
+          with_progress.as_ref(),
+          Some(expected),
+          "{:?} fib({})",
+          algorithm,
+          n
+        );
+      }
+    }
+  }
+
+  #[test]
+  fn algorithms_agree_with_each_other() {
+    for n in 0..=Algorithm::Recursive.max_n() {
+      let expected = Algorithm::Iterative.compute(n);
+      for algorithm in ALGORITHMS {
+        assert_eq!(algorithm.compute(n), expected, "{:?} fib({})", algorithm, n);
+      }
+    }
+    for n in [64, 1_000, 4_096, 10_000] {
+      let expected = Algorithm::Iterative.compute(n);
+      for algorithm in ALGORITHMS {
+        if n <= algorithm.max_n() {
+          assert_eq!(algorithm.compute(n), expected, "{:?} fib({})", algorithm, n);
+        }
+      }
+    }
+  }
+
+  // 备忘录递归在自己的线程中执行，调用者的栈再小也能算到 max_n
+  #[test]
+  fn memoized_does_not_depend_on_the_caller_stack() {
+    let n = Algorithm::Memoized.max_n();
+    let value = thread::Builder::new()
+      .stack_size(256 * 1024)
+      .spawn(move || Algorithm::Memoized.compute(n))
+      .unwrap()
+      .join()
+      .unwrap();
+    assert_eq!(value, Algorithm::Matrix.compute(n));
+  }
+
+  #[test]
+  fn sub_propagates_borrow_across_words() {
+    let one = BigUint::from(1);
+    // 2^64 - 1
+    assert_eq!(BigUint(vec![0, 1]).sub(&one), BigUint::from(u64::MAX));
+    // 2^128 - 1，借位跨过两个字
+    assert_eq!(
+      BigUint(vec![0, 0, 1]).sub(&one),
+      BigUint(vec![u64::MAX, u64::MAX])
+    );
+    // 低位借位之后高位同时减去 other 的高位
+    assert_eq!(
+      BigUint(vec![0, 5]).sub(&BigUint(vec![1, 2])),
+      BigUint(vec![u64::MAX, 2])
+    );
+    // 结果为 0 时去掉所有的高位 0
+    assert_eq!(
+      from_u128(u128::MAX).sub(&from_u128(u128::MAX)),
+      BigUint::from(0)
+    );
+    assert_eq!(BigUint::from(0).words(), &[] as &[u64]);
+  }
+
+  #[test]
+  #[should_panic(expected = "BigUint subtraction underflow")]
+  fn sub_underflow_panics_in_debug() {
+    BigUint::from(1).sub(&BigUint(vec![0, 1]));
+  }
+
+  #[test]
+  fn progress_is_monotonic_and_ends_at_one() {
+    for algorithm in ALGORITHMS {
+      for n in [0, 1, 2, 25, algorithm.max_n()] {
+        let mut reports = Vec::new();
+        algorithm
+          .compute_with_progress(n, &mut |progress| {
+            reports.push(progress);
+            true
+          })
+          .unwrap();
+        assert_eq!(reports.last(), Some(&1.0), "{:?} fib({})", algorithm, n);
+        assert!(
+          reports.windows(2).all(|pair| pair[0] < pair[1]),
+          "{:?} fib({}) reported {:?}",
+          algorithm,
+          n,
+          reports
+        );
+        assert!(reports
+          .iter()
+          .all(|&progress| progress > 0.0 && progress <= 1.0));
+        // 每完成大约 1%（total / 100 向下取整，至少一步）报告一次，不会逐步报告
+        assert!(reports.len() <= 201, "{:?} fib({})", algorithm, n);
+      }
+    }
+  }
+
+  #[test]
+  fn report_returning_false_stops_the_computation() {
+    for algorithm in ALGORITHMS {
+      let mut reports = 0;
+      let value = algorithm.compute_with_progress(algorithm.max_n(), &mut |_| {
+        reports += 1;
+        false
+      });
+      assert_eq!(value, None, "{:?}", algorithm);
+      assert_eq!(reports, 1, "{:?}", algorithm);
+    }
+  }
+}
@@ -1,50 +1,486 @@
-use napi::{CallContext, Env, JsNumber, JsObject, Result, Task};
+use fib::{Algorithm, BigUint};
+use napi::threadsafe_function::{
+  ErrorStrategy, ThreadSafeCallContext, ThreadsafeFunction, ThreadsafeFunctionCallMode,
+};
+use napi::{
+  CallContext, Env, Error, JsFunction, JsNumber, JsObject, JsString, JsUndefined, JsUnknown, Ref,
+  Result, Status, Task, ValueType,
+};
 use napi_derive::{js_function, module_exports};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+#[cfg(feature = "bench")]
+mod bench;
+mod cache;
+mod fib;
 
 struct ComputeFib {
   n: u32,
+  algorithm: Algorithm,
 }
 
-fn fibonacci_native(n: u32) -> u32 {
-  match n {
-    1 | 2 => 1,
-    _ => fibonacci_native(n - 1) + fibonacci_native(n - 2),
+impl ComputeFib {
+  pub fn new(n: u32, algorithm: Algorithm) -> ComputeFib {
+    ComputeFib { n, algorithm }
   }
 }
 
-impl ComputeFib {
-  pub fn new(n: u32) -> ComputeFib {
-    ComputeFib { n }
+impl Task for ComputeFib {
+  type Output = Arc<BigUint>;
+  type JsValue = JsUnknown;
+
+  fn compute(&mut self) -> Result<Self::Output> {
+    Ok(cache::fib(self.algorithm, self.n))
+  }
+
+  fn resolve(&mut self, env: Env, output: Self::Output) -> Result<Self::JsValue> {
+    fib_to_js(&env, &output)
   }
 }
 
-impl Task for ComputeFib {
-  type Output = u32;
-  type JsValue = JsNumber;
+// 一次计算一组 n，只占用 libuv 线程池中的一个线程，计算本身在 fib::compute_batch 的线程中并行执行
+struct ComputeFibBatch {
+  ns: Vec<u32>,
+  algorithm: Algorithm,
+}
+
+impl Task for ComputeFibBatch {
+  type Output = Vec<Arc<BigUint>>;
+  type JsValue = JsObject;
 
   fn compute(&mut self) -> Result<Self::Output> {
-    Ok(fibonacci_native(self.n))
+    let algorithm = self.algorithm;
+    Ok(fib::compute_batch(&self.ns, |n| cache::fib(algorithm, n)))
   }
 
   fn resolve(&mut self, env: Env, output: Self::Output) -> Result<Self::JsValue> {
-    env.create_uint32(output)
+    let mut array = env.create_array_with_length(output.len())?;
+    for (index, value) in output.iter().enumerate() {
+      array.set_element(index as u32, fib_to_js(&env, value)?)?;
+    }
+    Ok(array)
   }
 }
 
-// 在这段代码中，我们定义了一个名为 native_fib 的函数，它被标记为 #[js_function(0)]。这个标记表明该函数是一个 JavaScript 函数，可以从 JavaScript 环境中调用，并且它不接受任何参数（参数数量为 0）。
-#[js_function(0)]
-pub fn native_fib(ctx: CallContext) -> Result<JsObject> {
-  let task = ComputeFib::new(20);
+// 计算过程中通过 threadsafe function 向 JS 报告进度，AbortSignal 触发 abort 之后在下一次报告进度时停止
+struct ComputeFibWithProgress {
+  n: u32,
+  algorithm: Algorithm,
+  // 任务结束时释放，已经排队的进度仍然会交给 onProgress；被取消时 abort，丢弃排队的进度
+  on_progress: Option<ThreadsafeFunction<f64, ErrorStrategy::Fatal>>,
+  // 由 JS 主线程上的 abort 监听器设置
+  aborted: Arc<AtomicBool>,
+  signal: Option<AbortListener>,
+}
+
+// 注册在 AbortSignal 上的 abort 监听器，任务结束时移除
+struct AbortListener {
+  signal: Ref<()>,
+  listener: Ref<()>,
+}
+
+impl AbortListener {
+  fn add(env: &Env, signal: JsObject, aborted: Arc<AtomicBool>) -> Result<AbortListener> {
+    let listener = env.create_function_from_closure("onAbort", move |_| {
+      aborted.store(true, Ordering::Relaxed);
+      Ok(())
+    })?;
+    let listener_ref = env.create_reference(&listener)?;
+    let add_event_listener = signal.get_named_property::<JsFunction>("addEventListener")?;
+    add_event_listener.call(
+      Some(&signal),
+      &[
+        env.create_string("abort")?.into_unknown(),
+        listener.into_unknown(),
+      ],
+    )?;
+    Ok(AbortListener {
+      signal: env.create_reference(signal)?,
+      listener: listener_ref,
+    })
+  }
+
+  fn signal(&self, env: &Env) -> Result<JsObject> {
+    env.get_reference_value(&self.signal)
+  }
+
+  fn remove(mut self, env: &Env) -> Result<()> {
+    let signal = self.signal(env)?;
+    let listener = env.get_reference_value::<JsFunction>(&self.listener)?;
+    let remove_event_listener = signal.get_named_property::<JsFunction>("removeEventListener")?;
+    remove_event_listener.call(
+      Some(&signal),
+      &[
+        env.create_string("abort")?.into_unknown(),
+        listener.into_unknown(),
+      ],
+    )?;
+    self.signal.unref(*env)?;
+    self.listener.unref(*env)?;
+    Ok(())
+  }
+}
+
+// 和 Node.js 内置模块一样，取消时用 AbortError 拒绝，signal.reason 放在 cause 中
+fn abort_error(env: &Env, signal: &JsObject) -> Result<JsObject> {
+  let mut error = env.create_error(Error::new(
+    Status::GenericFailure,
+    "The operation was aborted".to_string(),
+  ))?;
+  error.set_named_property("name", env.create_string("AbortError")?)?;
+  error.set_named_property("code", env.create_string("ABORT_ERR")?)?;
+  error.set_named_property("cause", signal.get_named_property::<JsUnknown>("reason")?)?;
+  Ok(error)
+}
+
+impl Task for ComputeFibWithProgress {
+  type Output = Arc<BigUint>;
+  type JsValue = JsUnknown;
+
+  fn compute(&mut self) -> Result<Self::Output> {
+    let aborted = &self.aborted;
+    let on_progress = self.on_progress.as_ref();
+    let mut report = |fraction| {
+      if aborted.load(Ordering::Relaxed) {
+        return false;
+      }
+      if let Some(on_progress) = on_progress {
+        on_progress.call(fraction, ThreadsafeFunctionCallMode::NonBlocking);
+      }
+      true
+    };
+    // 调用时 signal 已经 abort 的情况也在这里处理，不会开始计算
+    if !report(0.0) {
+      return Err(Error::new(Status::Cancelled, "aborted".to_string()));
+    }
+    // 命中缓存时直接完成；被取消的计算没有结果，不会放入缓存
+    if let Some(value) = cache::get(self.algorithm, self.n) {
+      report(1.0);
+      return Ok(value);
+    }
+    let value = self
+      .algorithm
+      .compute_with_progress(self.n, &mut report)
+      .ok_or_else(|| Error::new(Status::Cancelled, "aborted".to_string()))?;
+    let value = Arc::new(value);
+    cache::insert(self.algorithm, self.n, value.clone());
+    Ok(value)
+  }
+
+  fn resolve(&mut self, env: Env, output: Self::Output) -> Result<Self::JsValue> {
+    fib_to_js(&env, &output)
+  }
+
+  fn reject(&mut self, env: Env, err: Error) -> Result<Self::JsValue> {
+    match &self.signal {
+      Some(signal) if err.status == Status::Cancelled => {
+        let error = abort_error(&env, &signal.signal(&env)?)?;
+        Err(Error::from(error.into_unknown()))
+      }
+      _ => Err(err),
+    }
+  }
+
+  fn finally(&mut self, env: Env) -> Result<()> {
+    if let Some(on_progress) = self.on_progress.take() {
+      if self.aborted.load(Ordering::Relaxed) {
+        on_progress.abort()?;
+      }
+    }
+    match self.signal.take() {
+      Some(signal) => signal.remove(&env),
+      None => Ok(()),
+    }
+  }
+}
+
+// u32 能表示的结果仍然返回 number，更大的结果返回 BigInt，避免溢出或者丢失精度
+fn fib_to_js(env: &Env, value: &BigUint) -> Result<JsUnknown> {
+  match value.to_u32() {
+    Some(value) => Ok(env.create_uint32(value)?.into_unknown()),
+    None => env
+      .create_bigint_from_words(false, value.words().to_vec())?
+      .into_unknown(),
+  }
+}
+
+// 没有传入或者传入 undefined 的参数返回 None
+fn optional_arg(ctx: &CallContext, index: usize) -> Result<Option<JsUnknown>> {
+  let value = ctx.get::<JsUnknown>(index)?;
+  match value.get_type()? {
+    ValueType::Undefined => Ok(None),
+    _ => Ok(Some(value)),
+  }
+}
+
+// 参数不合法时抛出的 JS 异常，错误码和 Node.js 内置模块的一致
+enum InvalidArg {
+  Type(String),
+  Value(String),
+  Range(String),
+}
+
+impl InvalidArg {
+  // 抛出对应的 JS 异常，返回的 PendingException 一路传回 #[js_function]，napi-rs 不会再抛出另一个异常
+  fn throw(self, env: &Env) -> Error {
+    let thrown = match self {
+      InvalidArg::Type(msg) => env.throw_type_error(&msg, Some("ERR_INVALID_ARG_TYPE")),
+      InvalidArg::Value(msg) => env.throw_range_error(&msg, Some("ERR_INVALID_ARG_VALUE")),
+      InvalidArg::Range(msg) => env.throw_range_error(&msg, Some("ERR_OUT_OF_RANGE")),
+    };
+    match thrown {
+      Ok(()) => Error::from_status(Status::PendingException),
+      Err(err) => err,
+    }
+  }
+}
+
+// 按 JS 的格式输出错误信息中的数字
+fn js_number(n: f64) -> String {
+  match n {
+    f64::INFINITY => "Infinity".to_string(),
+    f64::NEG_INFINITY => "-Infinity".to_string(),
+    _ => n.to_string(),
+  }
+}
+
+// 解析可选的 algorithm 参数，默认是 recursive
+fn algorithm_arg(ctx: &CallContext, index: usize) -> Result<Algorithm> {
+  let value = match optional_arg(ctx, index)? {
+    None => return Ok(Algorithm::Recursive),
+    Some(value) => value,
+  };
+  if value.get_type()? != ValueType::String {
+    return Err(
+      InvalidArg::Type("The \"algorithm\" argument must be of type string".to_string())
+        .throw(ctx.env),
+    );
+  }
+  let name = unsafe { value.cast::<JsString>() }
+    .into_utf8()?
+    .into_owned()?;
+  Algorithm::parse(&name).ok_or_else(|| {
+    InvalidArg::Value(format!(
+      "The argument 'algorithm' must be one of 'recursive', 'iterative', 'matrix', 'memoized'. Received '{}'",
+      name
+    ))
+    .throw(ctx.env)
+  })
+}
+
+// 检查 value 是不是 algorithm 支持的 n，name 是错误信息中参数的名字
+fn n_arg(env: &Env, value: JsUnknown, name: &str, algorithm: Algorithm) -> Result<u32> {
+  if value.get_type()? != ValueType::Number {
+    return Err(
+      InvalidArg::Type(format!("The \"{}\" argument must be of type number", name)).throw(env),
+    );
+  }
+  let n = unsafe { value.cast::<JsNumber>() }.get_double()?;
+  let max = algorithm.max_n();
+  // NaN 和无穷大也不满足这个条件
+  if !(n >= 0.0 && n <= max as f64 && n.fract() == 0.0) {
+    return Err(
+      InvalidArg::Range(format!(
+        "The value of \"{}\" is out of range. It must be an integer >= 0 && <= {} for the {} algorithm. Received {}",
+        name,
+        max,
+        algorithm.name(),
+        js_number(n)
+      ))
+      .throw(env),
+    );
+  }
+  Ok(n as u32)
+}
+
+// 解析 nativeUVFib(n?, algorithm?) 的参数，n 默认是 20
+fn fib_args(ctx: &CallContext) -> Result<(u32, Algorithm)> {
+  let algorithm = algorithm_arg(ctx, 1)?;
+  let n = match optional_arg(ctx, 0)? {
+    None => 20,
+    Some(value) => n_arg(ctx.env, value, "n", algorithm)?,
+  };
+  Ok((n, algorithm))
+}
+
+// 解析 nativeFibBatch(ns, algorithm?) 的参数，ns 中的每一项都必须是合法的 n
+fn fib_batch_args(ctx: &CallContext) -> Result<(Vec<u32>, Algorithm)> {
+  let algorithm = algorithm_arg(ctx, 1)?;
+  let ns = ctx.get::<JsUnknown>(0)?;
+  if !ns.is_array()? {
+    return Err(
+      InvalidArg::Type("The \"ns\" argument must be an instance of Array".to_string())
+        .throw(ctx.env),
+    );
+  }
+  let ns = unsafe { ns.cast::<JsObject>() };
+  let mut parsed = Vec::with_capacity(ns.get_array_length()? as usize);
+  for index in 0..ns.get_array_length()? {
+    let value = ns.get_element::<JsUnknown>(index)?;
+    parsed.push(n_arg(ctx.env, value, &format!("ns[{}]", index), algorithm)?);
+  }
+  Ok((parsed, algorithm))
+}
+
+// nativeUVFib(n?, algorithm?) 在 libuv 的线程池中计算 fib(n)，返回一个 Promise。参数不合法时同步抛出异常，不会创建任务
+#[js_function(2)]
+pub fn native_fib(ctx: CallContext) -> Result<JsUnknown> {
+  let (n, algorithm) = fib_args(&ctx)?;
+  let task = ComputeFib::new(n, algorithm);
   // 使用 ctx.env.spawn(task)? 将这个任务提交给 JavaScript 环境的异步运行时。spawn 方法会返回一个 async_promise，它是一个包含异步操作结果的承诺对象。
   let async_promise = ctx.env.spawn(task)?;
   // 函数返回 async_promise.promise_object()，这是一个 JavaScript 的 Promise 对象，表示异步操作的结果。通过这种方式，Rust 代码可以与 JavaScript 代码进行异步交互，并返回一个 Promise，以便在 JavaScript 中处理异步计算的结果。
-  Ok(async_promise.promise_object())
+  Ok(async_promise.promise_object().into_unknown())
 }
 
+// nativeFibBatch(ns, algorithm?) 计算一组 fib(n)，返回的 Promise 兑现为和 ns 顺序相同的数组。
+// 整批只提交一个 libuv 任务，不会因为每个 n 一个任务而占满线程池，让其他异步操作排队
+#[js_function(2)]
+pub fn native_fib_batch(ctx: CallContext) -> Result<JsUnknown> {
+  let (ns, algorithm) = fib_batch_args(&ctx)?;
+  let async_promise = ctx.env.spawn(ComputeFibBatch { ns, algorithm })?;
+  Ok(async_promise.promise_object().into_unknown())
+}
+
+// 解析 nativeFibWithProgress(n, algorithm?, onProgress?, signal?) 的后两个参数
+fn progress_args(ctx: &CallContext) -> Result<(Option<JsFunction>, Option<JsObject>)> {
+  let on_progress = match optional_arg(ctx, 2)? {
+    None => None,
+    Some(value) if value.get_type()? == ValueType::Function => {
+      Some(unsafe { value.cast::<JsFunction>() })
+    }
+    Some(_) => {
+      return Err(
+        InvalidArg::Type("The \"onProgress\" argument must be of type function".to_string())
+          .throw(ctx.env),
+      )
+    }
+  };
+  let signal = match optional_arg(ctx, 3)? {
+    None => None,
+    Some(value) => {
+      // 只要求有 aborted 和 addEventListener，和 Node.js 内置模块的检查一致
+      let is_signal = value.get_type()? == ValueType::Object && {
+        let object = unsafe { value.cast::<JsObject>() };
+        object.has_named_property("aborted")?
+          && object
+            .get_named_property::<JsUnknown>("addEventListener")?
+            .get_type()?
+            == ValueType::Function
+      };
+      if !is_signal {
+        return Err(
+          InvalidArg::Type(
+            "The \"signal\" argument must be an instance of AbortSignal".to_string(),
+          )
+          .throw(ctx.env),
+        );
+      }
+      Some(unsafe { value.cast::<JsObject>() })
+    }
+  };
+  Ok((on_progress, signal))
+}
+
+// nativeFibWithProgress(n, algorithm?, onProgress?, signal?) 和 nativeUVFib 一样计算 fib(n)，
+// 计算过程中用 0 到 1 之间的进度调用 onProgress，signal abort 之后尽快停止计算并用 AbortError 拒绝。
+// 进度每完成大约 1% 报告一次，最后的几次报告可能在 Promise 兑现之后才到达；abort 之后不会再调用 onProgress
+#[js_function(4)]
+pub fn native_fib_with_progress(ctx: CallContext) -> Result<JsUnknown> {
+  let (n, algorithm) = fib_args(&ctx)?;
+  let (on_progress, signal) = progress_args(&ctx)?;
+  let on_progress = match on_progress {
+    Some(on_progress) => Some(
+      on_progress
+        .create_threadsafe_function(0, |ctx: ThreadSafeCallContext<f64>| Ok(vec![ctx.value]))?,
+    ),
+    None => None,
+  };
+  let aborted = Arc::new(AtomicBool::new(false));
+  let signal = match signal {
+    Some(signal) => {
+      aborted.store(
+        signal.get_named_property::<bool>("aborted")?,
+        Ordering::Relaxed,
+      );
+      Some(AbortListener::add(ctx.env, signal, aborted.clone())?)
+    }
+    None => None,
+  };
+  let task = ComputeFibWithProgress {
+    n,
+    algorithm,
+    on_progress,
+    aborted,
+    signal,
+  };
+  let async_promise = ctx.env.spawn(task)?;
+  Ok(async_promise.promise_object().into_unknown())
+}
+
+// cacheStats() 返回进程内 fib 结果缓存的统计数据，所有 worker_threads 共享同一个缓存
+#[js_function(0)]
+pub fn cache_stats(ctx: CallContext) -> Result<JsObject> {
+  let stats = cache::stats();
+  let mut object = ctx.env.create_object()?;
+  object.set_named_property("hits", ctx.env.create_int64(stats.hits as i64)?)?;
+  object.set_named_property("misses", ctx.env.create_int64(stats.misses as i64)?)?;
+  object.set_named_property("evictions", ctx.env.create_int64(stats.evictions as i64)?)?;
+  object.set_named_property("entries", ctx.env.create_int64(stats.entries as i64)?)?;
+  object.set_named_property("bytes", ctx.env.create_int64(stats.bytes as i64)?)?;
+  object.set_named_property("capacity", ctx.env.create_int64(stats.capacity as i64)?)?;
+  Ok(object)
+}
+
+// clearCache() 清空缓存并把统计数据归零，正在计算的任务完成后仍然会写入缓存
+#[js_function(0)]
+pub fn clear_cache(ctx: CallContext) -> Result<JsUndefined> {
+  cache::clear();
+  ctx.env.get_undefined()
+}
+
+// setCacheCapacity(bytes) 修改缓存的总大小上限，0 表示关闭缓存
+#[js_function(1)]
+pub fn set_cache_capacity(ctx: CallContext) -> Result<JsUndefined> {
+  let value = ctx.get::<JsUnknown>(0)?;
+  if value.get_type()? != ValueType::Number {
+    ctx.env.throw_type_error(
+      "The \"bytes\" argument must be of type number",
+      Some("ERR_INVALID_ARG_TYPE"),
+    )?;
+    return ctx.env.get_undefined();
+  }
+  let bytes = unsafe { value.cast::<JsNumber>() }.get_double()?;
+  if !((0.0..=MAX_SAFE_INTEGER).contains(&bytes) && bytes.fract() == 0.0) {
+    ctx.env.throw_range_error(
+      &format!(
+        "The value of \"bytes\" is out of range. It must be an integer >= 0 && <= {}. Received {}",
+        MAX_SAFE_INTEGER,
+        js_number(bytes)
+      ),
+      Some("ERR_OUT_OF_RANGE"),
+    )?;
+    return ctx.env.get_undefined();
+  }
+  cache::set_capacity(bytes as usize);
+  ctx.env.get_undefined()
+}
+
+// Number.MAX_SAFE_INTEGER
+const MAX_SAFE_INTEGER: f64 = 9007199254740991.0;
+
 // 这个标记表明该函数用于导出模块中的方法，使其可以在 JavaScript 环境中使用。
 #[module_exports]
 pub fn register_js(mut exports: JsObject) -> Result<()> {
   // create_named_method 方法将 native_fib 函数绑定到 exports 对象上，使其可以通过 exports.nativeUVFib 在 JavaScript 中调用。
   exports.create_named_method("nativeUVFib", native_fib)?;
+  exports.create_named_method("nativeFibBatch", native_fib_batch)?;
+  exports.create_named_method("nativeFibWithProgress", native_fib_with_progress)?;
+  exports.create_named_method("cacheStats", cache_stats)?;
+  exports.create_named_method("clearCache", clear_cache)?;
+  exports.create_named_method("setCacheCapacity", set_cache_capacity)?;
+  #[cfg(feature = "bench")]
+  bench::register(&mut exports)?;
   Ok(())
 }
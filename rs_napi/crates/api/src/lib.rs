@@ -1,4 +1,5 @@
 use std::ffi::CString;
+use std::panic::{self, AssertUnwindSafe};
 use std::ptr;
 use sys::{napi_callback_info, napi_env, napi_value};
 
@@ -7,61 +8,99 @@ pub fn add(left: f64, right: f64) -> f64 {
 }
 
 unsafe extern "C" fn add_unsafe_code(env: napi_env, callback: napi_callback_info) -> napi_value {
-    // 初始化两个浮点数变量a和b为0.0，这两个变量将用于存储从JavaScript传入的数值。
-    let mut a: f64 = 0.0;
-    let mut b: f64 = 0.0;
+    // panic 不能跨过 FFI 边界展开到 JS 引擎中（这是未定义行为），捕获后转换成 JS Error
+    catch_panic(env, || {
+        // 初始化两个浮点数变量a和b为0.0，这两个变量将用于存储从JavaScript传入的数值。
+        let mut a: f64 = 0.0;
+        let mut b: f64 = 0.0;
 
-    unsafe {
-        // 使用sys::napi_get_cb_info函数从回调中获取参数。
-        // 这个函数需要参数的数量和一个数组来存储参数值的指针。
-        // 这里，args数组被初始化为包含两个空指针，用于接收从JavaScript传入的参数。
-        let mut args = [ptr::null_mut(); 2];
-        sys::napi_get_cb_info(
-            env,
-            callback,
-            &mut 2,
-            args.as_mut_ptr(),
-            ptr::null_mut(),
-            ptr::null_mut(),
-        );
-        // 使用sys::napi_get_value_double函数两次，分别获取args数组中的两个参数，并将它们的值存储在a和b变量中。
-        sys::napi_get_value_double(env, args[0], &mut a);
-        sys::napi_get_value_double(env, args[1], &mut b);
-    };
-    // 调用add函数（未在代码片段中定义）将a和b相加，结果存储在变量v中。
-    let v = add(a, b);
+        unsafe {
+            // 使用sys::napi_get_cb_info函数从回调中获取参数。
+            // 这个函数需要参数的数量和一个数组来存储参数值的指针。
+            // 这里，args数组被初始化为包含两个空指针，用于接收从JavaScript传入的参数。
+            let mut args = [ptr::null_mut(); 2];
+            sys::napi_get_cb_info(
+                env,
+                callback,
+                &mut 2,
+                args.as_mut_ptr(),
+                ptr::null_mut(),
+                ptr::null_mut(),
+            );
+            // 使用sys::napi_get_value_double函数两次，分别获取args数组中的两个参数，并将它们的值存储在a和b变量中。
+            sys::napi_get_value_double(env, args[0], &mut a);
+            sys::napi_get_value_double(env, args[1], &mut b);
+        };
+        // 调用add函数（未在代码片段中定义）将a和b相加，结果存储在变量v中。
+        let v = add(a, b);
 
-    let mut res = ptr::null_mut();
-    unsafe {
-        // 使用sys::napi_create_double函数将加法结果v转换为N-API可以识别的nap i_value类型，以便将结果返回给JavaScript。
-        sys::napi_create_double(env, v, &mut res);
-    };
-    res
+        let mut res = ptr::null_mut();
+        unsafe {
+            // 使用sys::napi_create_double函数将加法结果v转换为N-API可以识别的nap i_value类型，以便将结果返回给JavaScript。
+            sys::napi_create_double(env, v, &mut res);
+        };
+        res
+    })
 }
 
-// unsafe extern "C" fn register_fn(env: napi_env, exports: napi_value) -> napi_value {：定义了一个不安全的外部 "C" 函数 register_fn，这意味着该函数可以从 C 代码中调用。它接收两个参数：env（一个表示 Node.js 环境的 napi_env 类型的变量）和 exports（一个表示模块导出的 napi_value 类型的变量）。函数返回一个 napi_value 类型的值，即模块的导出。
-unsafe extern "C" fn register_fn(env: napi_env, exports: napi_value) -> napi_value {
-    // ：创建一个新的 CString，包含要注册的函数名 "add"。CString 用于与 C 代码交互，因为它保证了字符串的结尾有一个空字符
-    let name = CString::new("add").unwrap();
-    let desc = [sys::napi_property_descriptor {
-        utf8name: name.as_ptr().cast(),
-        name: ptr::null_mut(),
-        getter: None,
-        setter: None,
-        method: Some(add_unsafe_code),
-        attributes: 0,
-        value: ptr::null_mut(),
-        data: ptr::null_mut(),
-    }];
+// 在 extern "C" 函数中执行 f，如果发生 panic，就把 panic 信息作为 Error 抛给 JS 并返回 undefined
+fn catch_panic(env: napi_env, f: impl FnOnce() -> napi_value) -> napi_value {
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(value) => value,
+        Err(payload) => {
+            let message = if let Some(message) = payload.downcast_ref::<&'static str>() {
+                message.to_string()
+            } else if let Some(message) = payload.downcast_ref::<String>() {
+                message.clone()
+            } else {
+                "Box<dyn Any>".to_string()
+            };
+            let message =
+                CString::new(format!("Rust panicked: {}", message).replace('\0', "\\0")).unwrap();
+            unsafe {
+                sys::napi_throw_error(env, ptr::null(), message.as_ptr());
+            };
+            ptr::null_mut()
+        }
+    }
+}
 
-    //将上面定义的属性（函数）添加到模块的导出中。这个函数接收环境变量 env、模块导出 exports、属性描述符数组的长度以及数组的指针
-    sys::napi_define_properties(env, exports, desc.len(), desc.as_ptr());
-    exports
+// pub unsafe extern "C" fn napi_register_module_v1(env: napi_env, exports: napi_value) -> napi_value {：定义了一个不安全的外部 "C" 函数 napi_register_module_v1，这意味着该函数可以从 C 代码中调用。它接收两个参数：env（一个表示 Node.js 环境的 napi_env 类型的变量）和 exports（一个表示模块导出的 napi_value 类型的变量）。函数返回一个 napi_value 类型的值，即模块的导出。
+/// 和 backend 生成的代码一样以这个名字导出符号：动态加载模式下 ctor 不注册模块，由宿主通过 dlsym 找到它并调用
+///
+/// # Safety
+///
+/// 只能由宿主在加载模块时调用，env 和 exports 必须是宿主传入的有效值
+#[no_mangle]
+pub unsafe extern "C" fn napi_register_module_v1(env: napi_env, exports: napi_value) -> napi_value {
+    // panic 不能跨过 FFI 边界展开到 JS 引擎中（这是未定义行为），捕获后转换成 JS Error
+    catch_panic(env, || {
+        // ：创建一个新的 CString，包含要注册的函数名 "add"。CString 用于与 C 代码交互，因为它保证了字符串的结尾有一个空字符
+        let name = CString::new("add").unwrap();
+        let desc = [sys::napi_property_descriptor {
+            utf8name: name.as_ptr().cast(),
+            name: ptr::null_mut(),
+            getter: None,
+            setter: None,
+            method: Some(add_unsafe_code),
+            attributes: sys::napi_property_attributes::napi_default_method,
+            value: ptr::null_mut(),
+            data: ptr::null_mut(),
+        }];
+
+        //将上面定义的属性（函数）添加到模块的导出中。这个函数接收环境变量 env、模块导出 exports、属性描述符数组的长度以及数组的指针
+        sys::napi_define_properties(env, exports, desc.len(), desc.as_ptr());
+        exports
+    })
 }
 
 // 这段Rust代码展示了如何使用ctor库来在Rust中定义一个在加载时自动执行的函数，以及如何使用Rust的FFI（Foreign Function Interface）功能与Node.js的N-API交互，从而注册一个原生模块
 #[ctor::ctor]
 fn export_module() {
+    // 动态加载模式下此时还没有宿主提供 napi_* 符号，不能在这里注册，由宿主调用导出的 napi_register_module_v1
+    if sys::DYNAMIC_LOADING {
+        return;
+    }
     let name = CString::new("api").unwrap();
     let mut modules = sys::napi_module {
         nm_version: 1,
@@ -69,7 +108,7 @@ fn export_module() {
         nm_flags: 0,
         nm_modname: name.as_ptr().cast(),
         nm_priv: ptr::null_mut() as *mut _,
-        nm_register_func: Some(register_fn),
+        nm_register_func: Some(napi_register_module_v1),
         reserved: [ptr::null_mut() as *mut _; 4],
     };
     unsafe {
@@ -1,3 +1,5 @@
 fn main() {
-    println!("cargo:rustc-link-lib=dylib=ace_napi.z");
+    // #[api] 宏在设置了 API_TYPE_DEF_DIR 时会输出 TypeScript 声明，
+    // 这个变量变化时需要重新编译，确保每个函数的声明都被重新写出
+    println!("cargo:rerun-if-env-changed=API_TYPE_DEF_DIR");
 }
@@ -0,0 +1,16 @@
+/* auto-generated by `cargo xtask gen-dts` */
+/* eslint-disable */
+
+/**
+ * 返回两个数的和
+ */
+export function add(left: number, right: number): number;
+/**
+ * 返回 left 减去 right 的差
+ */
+export function minus(left: number, right: number): number;
+
+/** 每个导出函数的文档注释、参数和返回值描述 */
+export const __meta: Record<string, { doc: string; params: { name: string; type: string; optional: boolean; rest: boolean }[]; returns: string }>;
+/** 模块注册的所有导出，按名称排序 */
+export const __registry: { name: string; kind: 'function' | 'async' | 'iterator' | 'value'; arity: number }[];
@@ -0,0 +1,281 @@
+use std::os::raw::c_void;
+use std::ptr;
+use std::sync::atomic::{AtomicBool, AtomicPtr, Ordering};
+use std::sync::Arc;
+use sys::{
+    napi_async_work, napi_async_work__, napi_callback_info, napi_deferred, napi_env, napi_ref,
+    napi_status, napi_value,
+};
+
+use crate::context::CallContext;
+use crate::env::Env;
+use crate::error::Error;
+use crate::js_value::{JsBoolean, JsFunction, JsObject, JsValue};
+use crate::promise;
+use crate::unwind;
+use crate::value::NapiValue;
+
+// #[api(async)] 函数的取消令牌。JS 传入的 AbortSignal 触发 abort 时令牌被取消：
+// 还在排队的任务不会再执行，正在执行的任务需要自己通过 is_cancelled/check 检查并提前返回，
+// 两种情况下 Promise 都会以 AbortError 拒绝
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Release);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Acquire)
+    }
+
+    // 已经被取消时返回 AbortError，长时间的计算可以在循环中通过 token.check()? 提前结束
+    pub fn check(&self) -> Result<(), Error> {
+        if self.is_cancelled() {
+            Err(Error::abort_error())
+        } else {
+            Ok(())
+        }
+    }
+}
+
+// 宏为 CancellationToken 参数读取对应的 JS 实参：undefined 和 null 表示不可取消，
+// 其他值必须是一个 AbortSignal（有 aborted 属性以及 addEventListener/removeEventListener 方法）
+pub(crate) fn abort_signal(value: JsValue<'_>) -> Option<JsObject<'_>> {
+    if value.is_undefined_or_null() {
+        return None;
+    }
+    let signal = JsObject::try_from(value).ok().filter(|signal| {
+        JsBoolean::try_from(signal.get_named_property("aborted")).is_ok()
+            && JsFunction::try_from(signal.get_named_property("addEventListener")).is_ok()
+            && JsFunction::try_from(signal.get_named_property("removeEventListener")).is_ok()
+    });
+    if signal.is_none() {
+        unwind::throw_error(
+            Error::type_error(format!(
+                "expected an AbortSignal, found {}",
+                value.value_type()
+            ))
+            .with_code("ERR_INVALID_ARG_TYPE"),
+        );
+    }
+    signal
+}
+
+// 在 libuv 线程池中执行 execute，返回一个在执行完成后兑现的 Promise。
+// 参数在调用线程上已经转换成了 Rust 值，execute 的结果回到 JS 主线程后再转换成 JS 值
+pub(crate) fn spawn<'a, T, F>(
+    env: Env<'a>,
+    signal: Option<JsObject<'a>>,
+    execute: F,
+) -> JsObject<'a>
+where
+    T: for<'s> NapiValue<'s> + Send + 'static,
+    F: FnOnce(CancellationToken) -> T + Send + 'static,
+{
+    let (deferred, promise) = promise::create_promise(env);
+    // 调用时已经取消的话直接拒绝，不再把任务放进线程池
+    if let Some(signal) = signal {
+        if bool::from_js_value(signal.get_named_property("aborted")) {
+            reject_aborted(env, deferred, Some(signal));
+            return promise;
+        }
+    }
+
+    let token = CancellationToken::new();
+    let handle = Arc::new(AtomicPtr::new(ptr::null_mut()));
+    let work = Box::into_raw(Box::new(Work {
+        execute: Some(Box::new(execute)),
+        result: None,
+        token: token.clone(),
+        raw: ptr::null_mut(),
+        handle: handle.clone(),
+        deferred,
+        subscription: None,
+    }));
+    let name = env.create_string("apisecond:async");
+    let mut raw = ptr::null_mut();
+    unsafe {
+        sys::napi_create_async_work(
+            env.raw(),
+            ptr::null_mut(),
+            name.raw(),
+            Some(execute_work::<T>),
+            Some(complete_work::<T>),
+            work.cast(),
+            &mut raw,
+        );
+        (*work).raw = raw;
+        handle.store(raw, Ordering::Release);
+        (*work).subscription = signal.map(|signal| subscribe(env, signal, token, handle));
+        sys::napi_queue_async_work(env.raw(), raw);
+    };
+    promise
+}
+
+// 一次异步调用的全部状态，spawn 中装箱，complete_work 中释放。
+// execute 和 result 只在线程池中被访问，其他字段只在 JS 主线程上访问
+struct Work<T> {
+    execute: Option<Box<dyn FnOnce(CancellationToken) -> T + Send>>,
+    result: Option<Result<T, Error>>,
+    token: CancellationToken,
+    raw: napi_async_work,
+    // 和 abort 监听器共享，任务结束后置空，之后的 abort 不会再去取消已经释放的 async work
+    handle: Arc<AtomicPtr<napi_async_work__>>,
+    deferred: napi_deferred,
+    subscription: Option<Subscription>,
+}
+
+// 任务完成时要移除 abort 监听器，否则长期存在的 signal 会一直持有它
+struct Subscription {
+    signal: napi_ref,
+    listener: napi_ref,
+}
+
+// abort 监听器绑定的数据，随监听器函数一起被 GC 回收
+struct Listener {
+    token: CancellationToken,
+    handle: Arc<AtomicPtr<napi_async_work__>>,
+}
+
+unsafe extern "C" fn execute_work<T>(_env: napi_env, data: *mut c_void) {
+    let work = &mut *data.cast::<Work<T>>();
+    // 排队期间被取消，但没来得及从队列中移除
+    if work.token.is_cancelled() {
+        return;
+    }
+    if let Some(execute) = work.execute.take() {
+        let token = work.token.clone();
+        work.result = Some(unwind::catch_panic(|| execute(token)));
+    }
+}
+
+unsafe extern "C" fn complete_work<T: for<'s> NapiValue<'s>>(
+    env: napi_env,
+    status: napi_status,
+    data: *mut c_void,
+) {
+    let work = Box::from_raw(data.cast::<Work<T>>());
+    let env = Env::from_raw(env);
+    let deferred = work.deferred;
+    // 这里已经不在任何 JS 调用中，panic 不能展开到 libuv 中，也没有调用者可以接住异常。
+    // settle 只会在兑现 Promise 之前 panic，这时改为以 panic 的信息拒绝，调用者不会一直等下去
+    if let Err(err) = unwind::catch_panic(|| settle(env, status, *work)) {
+        promise::reject(env, deferred, err.into_js_error(env).into());
+    }
+}
+
+fn settle<T: for<'s> NapiValue<'s>>(env: Env<'_>, status: napi_status, work: Work<T>) {
+    work.handle.store(ptr::null_mut(), Ordering::Release);
+    unsafe {
+        sys::napi_delete_async_work(env.raw(), work.raw);
+    };
+    // 先移除监听器再处理结果，之后无论结果如何都不会留下监听器
+    let signal = work
+        .subscription
+        .map(|subscription| unsubscribe(env, subscription));
+    // 在开始执行之前被移出队列（napi_cancelled），或者执行过程中被取消
+    if status == sys::napi_cancelled || work.token.is_cancelled() {
+        return reject_aborted(env, work.deferred, signal);
+    }
+
+    let result = match work.result {
+        Some(result) if status == sys::napi_ok => result,
+        _ => Err(Error::from_reason(format!(
+            "async work failed with status {}",
+            status
+        ))),
+    };
+    promise::settle(env, work.deferred, result);
+}
+
+fn subscribe<'a>(
+    env: Env<'a>,
+    signal: JsObject<'a>,
+    token: CancellationToken,
+    handle: Arc<AtomicPtr<napi_async_work__>>,
+) -> Subscription {
+    let data = Box::into_raw(Box::new(Listener { token, handle }));
+    let listener = env.create_function("onabort", Some(on_abort), data.cast());
+    env.add_finalizer(listener.into(), data.cast(), Some(drop_listener));
+    call_listener_method(env, signal, "addEventListener", listener);
+    Subscription {
+        signal: create_reference(env, signal.into()),
+        listener: create_reference(env, listener.into()),
+    }
+}
+
+// 移除监听器并返回 signal，拒绝 Promise 时需要读取 signal.reason
+fn unsubscribe(env: Env<'_>, subscription: Subscription) -> JsObject<'_> {
+    let signal = JsObject::from_unchecked(take_reference(env, subscription.signal));
+    let listener = JsFunction::from_unchecked(take_reference(env, subscription.listener));
+    call_listener_method(env, signal, "removeEventListener", listener);
+    signal
+}
+
+fn call_listener_method<'a>(
+    env: Env<'a>,
+    signal: JsObject<'a>,
+    method: &str,
+    listener: JsFunction<'a>,
+) {
+    let method = JsFunction::from_js_value(signal.get_named_property(method));
+    method.call(
+        signal,
+        &[env.create_string("abort").into(), listener.into()],
+    );
+}
+
+unsafe extern "C" fn on_abort(env: napi_env, info: napi_callback_info) -> napi_value {
+    unwind::catch(env, |env| unsafe {
+        let ctx = CallContext::new(env, info, 0);
+        let listener = &*ctx.data().cast::<Listener>();
+        listener.token.cancel();
+        // 还在排队的任务直接从线程池的队列中移除，complete 会收到 napi_cancelled；
+        // 已经开始执行的任务无法被打断，只能等它自己检查令牌
+        let raw = listener.handle.load(Ordering::Acquire);
+        if !raw.is_null() {
+            sys::napi_cancel_async_work(env.raw(), raw);
+        }
+        env.get_undefined()
+    })
+}
+
+unsafe extern "C" fn drop_listener(_env: napi_env, data: *mut c_void, _hint: *mut c_void) {
+    drop(Box::from_raw(data.cast::<Listener>()));
+}
+
+// 以 AbortError 拒绝，和 Node.js 内置 API 一样把 signal.reason 作为 err.cause
+fn reject_aborted<'a>(env: Env<'a>, deferred: napi_deferred, signal: Option<JsObject<'a>>) {
+    let error = Error::abort_error().into_js_error(env);
+    if let Some(reason) = signal
+        .map(|signal| signal.get_named_property("reason"))
+        .filter(|reason| !reason.is_undefined_or_null())
+    {
+        error.set_named_property("cause", reason);
+    }
+    promise::reject(env, deferred, error.into());
+}
+
+fn create_reference(env: Env<'_>, value: JsValue<'_>) -> napi_ref {
+    let mut reference = ptr::null_mut();
+    unsafe {
+        sys::napi_create_reference(env.raw(), value.raw(), 1, &mut reference);
+    };
+    reference
+}
+
+// 取出引用的值并删除引用
+fn take_reference(env: Env<'_>, reference: napi_ref) -> JsValue<'_> {
+    let mut value = ptr::null_mut();
+    unsafe {
+        sys::napi_get_reference_value(env.raw(), reference, &mut value);
+        sys::napi_delete_reference(env.raw(), reference);
+        JsValue::from_raw(env, value)
+    }
+}
@@ -0,0 +1,114 @@
+use std::ops::{Deref, DerefMut};
+use std::os::raw::c_void;
+use std::ptr;
+use std::slice;
+use sys::napi_env;
+
+use crate::env::Env;
+use crate::error::Error;
+use crate::js_value::{JsBuffer, JsValue};
+use crate::memory;
+use crate::unwind;
+use crate::value::NapiValue;
+
+// Node.js 的 Buffer。从 JS 传入时复制一份数据；返回给 JS 时不复制，而是把内存直接
+// 交给 Buffer 使用（external buffer），Buffer 被 GC 回收时释放，并把大小报告给 V8
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Buffer(Vec<u8>);
+
+impl Buffer {
+    pub fn into_inner(self) -> Vec<u8> {
+        self.0
+    }
+}
+
+impl From<Vec<u8>> for Buffer {
+    fn from(data: Vec<u8>) -> Self {
+        Buffer(data)
+    }
+}
+
+impl From<Buffer> for Vec<u8> {
+    fn from(buffer: Buffer) -> Self {
+        buffer.0
+    }
+}
+
+impl Deref for Buffer {
+    type Target = Vec<u8>;
+
+    fn deref(&self) -> &Vec<u8> {
+        &self.0
+    }
+}
+
+impl DerefMut for Buffer {
+    fn deref_mut(&mut self) -> &mut Vec<u8> {
+        &mut self.0
+    }
+}
+
+impl<'a> NapiValue<'a> for Buffer {
+    fn from_js_value(value: JsValue<'a>) -> Buffer {
+        Buffer(JsBuffer::from_js_value(value).to_vec())
+    }
+
+    fn into_js_value(self, env: Env<'a>) -> JsValue<'a> {
+        // 转成 Box<[u8]> 去掉多余的容量，释放时只需要知道长度
+        let data = self.0.into_boxed_slice();
+        let len = data.len();
+        let data = Box::into_raw(data).cast::<u8>();
+        match env.create_external_buffer(data, len, Some(finalize), len as *mut c_void) {
+            Ok(buffer) => {
+                memory::track_alloc(env, len);
+                buffer.into()
+            }
+            Err(status) => {
+                // finalize 不会被调用，数据仍然归这里所有
+                drop(unsafe { Box::from_raw(ptr::slice_from_raw_parts_mut(data, len)) });
+                unwind::throw_error(Error::from_reason(format!(
+                    "failed to create an external buffer (napi_status {})",
+                    status
+                )))
+            }
+        }
+    }
+}
+
+// hint 中保存的是数据的长度
+unsafe extern "C" fn finalize(env: napi_env, data: *mut c_void, hint: *mut c_void) {
+    let len = hint as usize;
+    drop(Box::from_raw(ptr::slice_from_raw_parts_mut(
+        data.cast::<u8>(),
+        len,
+    )));
+    memory::track_free(Env::from_raw(env), len);
+}
+
+impl<'a> JsBuffer<'a> {
+    pub fn len(&self) -> usize {
+        self.info().1
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // 复制 Buffer 中的数据，JS 之后对 Buffer 的修改不会影响返回的 Vec
+    pub fn to_vec(&self) -> Vec<u8> {
+        let (data, len) = self.info();
+        if len == 0 {
+            return Vec::new();
+        }
+        unsafe { slice::from_raw_parts(data.cast::<u8>(), len) }.to_vec()
+    }
+
+    fn info(&self) -> (*mut c_void, usize) {
+        let mut data = ptr::null_mut();
+        let mut len = 0;
+        unsafe {
+            sys::napi_get_buffer_info(self.env().raw(), self.raw(), &mut data, &mut len);
+        };
+        (data, len)
+    }
+}
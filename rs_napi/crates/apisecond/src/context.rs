@@ -0,0 +1,127 @@
+use std::os::raw::c_void;
+use std::ptr;
+use sys::napi_callback_info;
+
+use crate::env::Env;
+use crate::js_value::{JsValue, ValueType};
+use crate::value::NapiValue;
+
+// 一次 JS 调用的上下文。#[api] 函数声明一个 CallContext 类型的参数时，宏会把它传进来，
+// 这样函数就可以访问 this、new.target、原始的参数列表以及创建函数时绑定的 data。
+// 其中的 JS 值只在这次调用中有效
+pub struct CallContext<'a> {
+    env: Env<'a>,
+    this: JsValue<'a>,
+    new_target: Option<JsValue<'a>>,
+    argc: usize,
+    argv: Vec<JsValue<'a>>,
+    data: *mut c_void,
+}
+
+impl<'a> CallContext<'a> {
+    // 先查询实际传入的参数个数，再一次性取出所有参数。
+    // min_argc 是函数声明的参数个数，JS 少传的参数会被 N-API 填充为 undefined
+    pub(crate) unsafe fn new(env: Env<'a>, info: napi_callback_info, min_argc: usize) -> Self {
+        let mut argc = 0;
+        sys::napi_get_cb_info(
+            env.raw(),
+            info,
+            &mut argc,
+            ptr::null_mut(),
+            ptr::null_mut(),
+            ptr::null_mut(),
+        );
+
+        let mut argv = vec![ptr::null_mut(); argc.max(min_argc)];
+        let mut len = argv.len();
+        let mut this = ptr::null_mut();
+        let mut data = ptr::null_mut();
+        sys::napi_get_cb_info(
+            env.raw(),
+            info,
+            &mut len,
+            argv.as_mut_ptr(),
+            &mut this,
+            &mut data,
+        );
+
+        // 不是通过 new 调用时，new_target 为 NULL
+        let mut new_target = ptr::null_mut();
+        sys::napi_get_new_target(env.raw(), info, &mut new_target);
+
+        CallContext {
+            env,
+            this: JsValue::from_raw(env, this),
+            new_target: (!new_target.is_null()).then(|| JsValue::from_raw(env, new_target)),
+            argc,
+            argv: argv
+                .into_iter()
+                .map(|value| JsValue::from_raw(env, value))
+                .collect(),
+            data,
+        }
+    }
+
+    // 当前调用所在的 N-API 环境
+    pub fn env(&self) -> Env<'a> {
+        self.env
+    }
+
+    // 调用时的接收者，也就是 JS 中的 this
+    pub fn this(&self) -> JsValue<'a> {
+        self.this
+    }
+
+    // 通过 new 调用时返回 new.target，普通调用时返回 None
+    pub fn new_target(&self) -> Option<JsValue<'a>> {
+        self.new_target
+    }
+
+    // JS 实际传入的参数个数
+    pub fn argc(&self) -> usize {
+        self.argc
+    }
+
+    // JS 实际传入的参数，不包含为缺少的参数填充的 undefined
+    pub fn argv(&self) -> &[JsValue<'a>] {
+        &self.argv[..self.argc]
+    }
+
+    // 创建函数时绑定的 data 指针
+    pub fn data(&self) -> *mut c_void {
+        self.data
+    }
+
+    // 宏生成的代码按声明的位置读取参数，缺少的参数是 undefined
+    pub(crate) fn arg(&self, index: usize) -> JsValue<'a> {
+        self.argv[index]
+    }
+
+    // 第 index 个参数是否传入了（传入 undefined 视为没有传入），用于判断是否使用默认值
+    pub(crate) fn has_arg(&self, index: usize) -> bool {
+        self.argv()
+            .get(index)
+            .is_some_and(|value| value.value_type() != ValueType::Undefined)
+    }
+
+    // #[rest] 参数：把从第 from 个开始的所有实参收集起来
+    pub(crate) fn rest<T: NapiValue<'a>>(&self, from: usize) -> Vec<T> {
+        self.argv()
+            .iter()
+            .skip(from)
+            .map(|value| T::from_js_value(*value))
+            .collect()
+    }
+
+    // 第 index 个参数是否是一个 Promise（或者继承自 Promise 的对象），没有传入时返回 false
+    pub fn is_promise(&self, index: usize) -> bool {
+        self.argv()
+            .get(index)
+            .is_some_and(|value| value.is_promise())
+    }
+
+    // 把第 index 个参数转换为 Rust 类型，JS 没有传入这个参数时返回 None
+    pub fn get<T: NapiValue<'a>>(&self, index: usize) -> Option<T> {
+        self.argv().get(index).map(|value| T::from_js_value(*value))
+    }
+}
@@ -0,0 +1,376 @@
+// 每个特性的演示导出。集成测试和 crates/bench 通过它们在 mock 运行时中验证对应的特性，
+// 默认不编译，需要开启 demo feature
+use backend::{api, JsError};
+use std::fmt;
+use std::mem;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::task::{Context, Poll};
+use std::time::{Duration, SystemTime};
+
+#[cfg(feature = "serde")]
+use crate::Json;
+use crate::{
+    Buffer, CallContext, CancellationToken, Error, EventEmitter, External, ExternalRef, JsObject,
+    JsSymbol, MemoryTracker, Stream,
+};
+
+/// 把一组字符串用分隔符连接起来
+#[api]
+pub fn join(parts: &[String], separator: &str) -> String {
+    parts.join(separator)
+}
+
+/// 计算平面上两点之间的距离
+#[api]
+pub fn distance((x1, y1): (f64, f64), (x2, y2): (f64, f64)) -> f64 {
+    (x2 - x1).hypot(y2 - y1)
+}
+
+/// 返回 { this, newTarget }：调用时的 this，以及通过 new 调用时的 new.target（普通调用时为 undefined）
+#[api]
+pub fn call_site(ctx: CallContext) -> JsObject {
+    let env = ctx.env();
+    let site = env.create_object();
+    site.set_named_property("this", ctx.this());
+    site.set_named_property(
+        "newTarget",
+        ctx.new_target().unwrap_or_else(|| env.get_undefined()),
+    );
+    site
+}
+
+/// 返回 values 中的第 index 个元素。越界时 panic，JS 中得到的是一个 Error，进程不会崩溃
+#[api]
+pub fn nth(values: Vec<f64>, index: f64) -> f64 {
+    values[index as usize]
+}
+
+/// 返回调用时实际传入的参数个数
+#[api]
+pub fn arg_count(ctx: CallContext) -> f64 {
+    ctx.argc() as f64
+}
+
+/// 生成问候语，greeting 缺省时使用 "Hello"
+#[api]
+pub fn greet(name: &str, greeting: Option<String>) -> String {
+    format!("{}, {}!", greeting.as_deref().unwrap_or("Hello"), name)
+}
+
+/// 对任意个数的参数求和
+#[api]
+pub fn sum(#[rest] values: Vec<f64>) -> f64 {
+    values.iter().sum()
+}
+
+/// 按比例缩放一个数，scale 缺省时为 1
+#[api(default(scale = 1.0))]
+pub fn scale(value: f64, scale: f64) -> f64 {
+    value * scale
+}
+
+/// 把数字格式化为保留 digits 位小数的字符串
+#[api]
+pub fn format_number(value: f64, #[default = 2.0] digits: f64) -> String {
+    format!("{:.*}", digits as usize, value)
+}
+
+/// 在时间 time 上加上 days 天
+#[api]
+pub fn add_days(time: SystemTime, days: f64) -> SystemTime {
+    let offset = Duration::from_secs_f64(days.abs() * 24.0 * 60.0 * 60.0);
+    if days < 0.0 {
+        time - offset
+    } else {
+        time + offset
+    }
+}
+
+/// 返回 id 的下一个 id，128 位的 id 以 BigInt 的形式在 JS 和 Rust 之间传递
+#[api]
+pub fn next_id(id: u128) -> u128 {
+    id.wrapping_add(1)
+}
+
+/// 返回两个 128 位整数之差的绝对值
+#[api]
+pub fn abs_diff(a: i128, b: i128) -> u128 {
+    a.abs_diff(b)
+}
+
+/// 创建一个带有描述 description 的 Symbol
+#[api]
+pub fn symbol(ctx: CallContext, description: String) -> JsSymbol {
+    ctx.env().create_symbol(Some(&description))
+}
+
+// sqrt 和 log 可能抛出的错误，JS 中可以通过 err.code 区分
+#[derive(Debug, JsError)]
+pub enum MathError {
+    #[js_error(kind = "RangeError", code = "ERR_NEGATIVE_SQRT")]
+    NegativeSqrt(f64),
+    #[js_error(kind = "RangeError", code = "ERR_INVALID_LOG_BASE")]
+    InvalidBase(f64),
+    // 计算过程中出现的错误会作为 err.cause 一起抛出
+    #[js_error(code = "ERR_LOG")]
+    Log {
+        value: f64,
+        #[js_error(cause)]
+        source: Error,
+    },
+}
+
+impl fmt::Display for MathError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MathError::NegativeSqrt(value) => {
+                write!(f, "cannot take the square root of {}", value)
+            }
+            MathError::InvalidBase(base) => write!(f, "invalid logarithm base {}", base),
+            MathError::Log { value, .. } => write!(f, "cannot take the logarithm of {}", value),
+        }
+    }
+}
+
+/// 返回 value 的平方根，value 为负数时抛出 RangeError
+#[api]
+pub fn sqrt(value: f64) -> Result<f64, MathError> {
+    if value < 0.0 {
+        return Err(MathError::NegativeSqrt(value));
+    }
+    Ok(value.sqrt())
+}
+
+/// 返回以 base 为底 value 的对数
+#[api]
+pub fn log(value: f64, base: f64) -> Result<f64, MathError> {
+    if base <= 0.0 || base == 1.0 {
+        return Err(MathError::InvalidBase(base));
+    }
+    if value <= 0.0 {
+        return Err(MathError::Log {
+            value,
+            source: Error::range_error("logarithm is only defined for positive numbers")
+                .with_code("ERR_OUT_OF_DOMAIN"),
+        });
+    }
+    Ok(value.log(base))
+}
+
+/// 把一组样本保存在 Rust 侧，返回给 JS 一个不透明的句柄
+#[api]
+pub fn create_samples(values: Vec<f64>) -> External<Vec<f64>> {
+    // 除了 Vec 本身，还要报告它在堆上分配的空间
+    let size = mem::size_of::<Vec<f64>>() + values.capacity() * mem::size_of::<f64>();
+    External::with_size(values, size)
+}
+
+/// 返回 create_samples 保存的样本的平均值，没有样本时返回 NaN
+#[api]
+pub fn samples_mean(samples: ExternalRef<Vec<f64>>) -> f64 {
+    samples.iter().sum::<f64>() / samples.len() as f64
+}
+
+/// 创建一个长度为 len、每个字节都是 byte 的 Buffer
+#[api]
+pub fn fill_buffer(byte: f64, len: f64) -> Buffer {
+    vec![byte as u8; len as usize].into()
+}
+
+/// 返回插件当前持有的、已经报告给 V8 的原生内存字节数
+#[api]
+pub fn external_memory() -> f64 {
+    MemoryTracker::external_bytes() as f64
+}
+
+/// 在线程池中计算第 n 个斐波那契数，可以通过 AbortSignal 取消
+#[api(async)]
+pub fn fibonacci(n: f64, token: CancellationToken) -> Result<f64, Error> {
+    // 故意使用指数复杂度的递归，每一层都检查一次是否已经被取消
+    fn fib(n: u32, token: &CancellationToken) -> Result<f64, Error> {
+        token.check()?;
+        match n {
+            0 | 1 => Ok(n as f64),
+            _ => Ok(fib(n - 1, token)? + fib(n - 2, token)?),
+        }
+    }
+    fib(n as u32, &token)
+}
+
+/// 在线程池中计算第 n 个斐波那契数，不可取消。和 rs_libuv/node_uv 的 nativeUVFib 一样是朴素的递归，
+/// crates/bench 用它比较两种实现的异步开销
+#[api(async)]
+pub fn fib(n: f64) -> f64 {
+    fn fib(n: u32) -> f64 {
+        match n {
+            0 | 1 => n as f64,
+            _ => fib(n - 1) + fib(n - 2),
+        }
+    }
+    fib(n as u32)
+}
+
+// spin 一共开始执行过多少次
+static SPINS: AtomicUsize = AtomicUsize::new(0);
+
+/// 在线程池中一直运行，直到通过 AbortSignal 取消
+#[api(async)]
+pub fn spin(token: CancellationToken) -> Result<f64, Error> {
+    SPINS.fetch_add(1, Ordering::SeqCst);
+    loop {
+        token.check()?;
+        std::thread::sleep(Duration::from_millis(1));
+    }
+}
+
+/// 返回 spin 一共开始执行过多少次，还在排队的调用不计算在内
+#[api]
+pub fn spins() -> f64 {
+    SPINS.load(Ordering::SeqCst) as f64
+}
+
+/// 异步地把 text 重复 count 次，Future 在执行器的线程上运行
+#[api]
+pub async fn repeat(text: &str, count: f64) -> String {
+    text.repeat(count as usize)
+}
+
+/// 异步地返回 values 中的第 index 个元素。越界时 panic，Promise 以 Error 拒绝
+#[api]
+pub async fn nth_async(values: Vec<f64>, index: f64) -> f64 {
+    values[index as usize]
+}
+
+/// 按需产生 [start, end) 中的整数，用 for await...of 读取，JS 每取一个才计算下一个
+#[api]
+pub fn range(start: f64, end: f64) -> impl Iterator<Item = f64> {
+    (start as i64..end as i64).map(|n| n as f64)
+}
+
+// scan 返回的所有流一共产生过的元素个数
+static SCANNED: AtomicUsize = AtomicUsize::new(0);
+
+// 逐个产生 [next, count) 中的整数，每次被 poll 时才计算下一个
+struct Scan {
+    next: u32,
+    count: u32,
+    fail_at: Option<u32>,
+}
+
+impl Stream for Scan {
+    type Item = Result<f64, Error>;
+
+    fn poll_next(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let scan = self.get_mut();
+        if scan.next >= scan.count {
+            return Poll::Ready(None);
+        }
+        let index = scan.next;
+        scan.next += 1;
+        SCANNED.fetch_add(1, Ordering::Relaxed);
+        if scan.fail_at == Some(index) {
+            return Poll::Ready(Some(Err(Error::range_error(format!(
+                "failed to scan item {}",
+                index
+            ))
+            .with_code("ERR_SCAN"))));
+        }
+        Poll::Ready(Some(Ok(index as f64)))
+    }
+}
+
+/// 以流的形式按需产生 [0, count) 中的整数，用 for await...of 读取；产生到第 fail_at 个时以 RangeError 结束
+#[api]
+pub fn scan(count: f64, fail_at: Option<f64>) -> impl Stream<Item = Result<f64, Error>> {
+    Scan {
+        next: 0,
+        count: count as u32,
+        fail_at: fail_at.map(|index| index as u32),
+    }
+}
+
+/// 返回 scan 到目前为止一共产生了多少个元素，JS 不调用 next() 时这个数不会增加
+#[api]
+pub fn scanned() -> f64 {
+    SCANNED.load(Ordering::Relaxed) as f64
+}
+
+/// 在后台线程上依次向 events 发送 count 个 "tick" 事件（payload 为序号），全部发送之后再发送 "done"，
+/// 返回 events 本身
+#[api]
+pub fn emit_ticks(events: EventEmitter, count: f64) -> EventEmitter {
+    let done = events.clone();
+    std::thread::spawn(move || {
+        let ticks = done.clone();
+        std::thread::spawn(move || {
+            for i in 0..count as u32 {
+                ticks.emit("tick", i as f64);
+            }
+        })
+        .join()
+        .unwrap();
+        // 所有 tick 都已经在另一个线程上进入了队列，done 一定在它们之后派发
+        done.emit("done", count);
+    });
+    events
+}
+
+/// 平面上的一个点
+#[cfg(feature = "serde")]
+#[derive(Clone, Copy, Debug, serde::Serialize, serde::Deserialize, backend::TypeDef)]
+pub struct Point {
+    pub x: f64,
+    pub y: f64,
+}
+
+/// 返回两个点连线的中点，点以 { x, y } 对象的形式传入和返回
+#[cfg(feature = "serde")]
+#[api]
+pub fn midpoint(a: Json<Point>, b: Json<Point>) -> Json<Point> {
+    Json(Point {
+        x: (a.0.x + b.0.x) / 2.0,
+        y: (a.0.y + b.0.y) / 2.0,
+    })
+}
+
+/// 画布上的一个图形
+#[cfg(feature = "serde")]
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize, backend::TypeDef)]
+pub struct Shape {
+    /// 以 BigInt 的形式传递的 128 位 id
+    pub id: u128,
+    /// 以外部标记表示的图形种类，例如 "Dot"、{ "Circle": 1 } 或者 { "Rect": [2, 3] }
+    pub kind: ShapeKind,
+    pub origin: Point,
+    pub label: Option<String>,
+}
+
+/// 图形的种类，其中的坐标和子图形的位置都相对于所在图形的 origin
+#[cfg(feature = "serde")]
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub enum ShapeKind {
+    Dot,
+    Circle(f64),
+    Rect(f64, f64),
+    Polygon { points: Vec<Point> },
+    Group(Vec<Shape>),
+}
+
+/// 把图形平移 (dx, dy)，kind 中的坐标都是相对的，只需要移动 origin
+#[cfg(feature = "serde")]
+#[api]
+pub fn translate(shape: Json<Shape>, dx: f64, dy: f64) -> Json<Shape> {
+    let mut shape = shape.into_inner();
+    shape.origin.x += dx;
+    shape.origin.y += dy;
+    Json(shape)
+}
+
+/// 模块的版本号
+#[api]
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// scale 允许的缩放倍数范围 [最小值, 最大值]
+#[api]
+pub static SCALE_LIMITS: (f64, f64) = (0.0, 1024.0);
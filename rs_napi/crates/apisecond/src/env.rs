@@ -0,0 +1,213 @@
+use std::ffi::CString;
+use std::marker::PhantomData;
+use std::os::raw::{c_int, c_void};
+use std::ptr;
+use sys::{napi_callback, napi_env, napi_finalize, napi_status, napi_value};
+
+use crate::js_value::{
+    JsArray, JsBigInt, JsBoolean, JsBuffer, JsDate, JsExternal, JsFunction, JsNumber, JsObject,
+    JsString, JsSymbol, JsValue,
+};
+
+// N-API 环境的句柄。生命周期 'a 对应当前的 handle scope（一次 JS 调用或者一次模块初始化），
+// 通过它创建的 JS 值都带有同样的生命周期，编译器会阻止这些值被保存到 scope 之外
+#[derive(Clone, Copy, Debug)]
+pub struct Env<'a> {
+    raw: napi_env,
+    scope: PhantomData<&'a ()>,
+}
+
+impl<'a> Env<'a> {
+    // 调用者需要保证 raw 在 'a 期间一直有效，并且 'a 不会超出当前的 handle scope，
+    // 通常由 unwind::catch 在 extern "C" 函数的入口处创建
+    pub(crate) unsafe fn from_raw(raw: napi_env) -> Env<'a> {
+        Env {
+            raw,
+            scope: PhantomData,
+        }
+    }
+
+    pub fn raw(&self) -> napi_env {
+        self.raw
+    }
+
+    // 把 N-API 的出参包装成 JsValue，create 负责调用具体的 N-API 函数
+    fn create(&self, create: impl FnOnce(*mut napi_value)) -> JsValue<'a> {
+        let mut res = ptr::null_mut();
+        create(&mut res);
+        unsafe { JsValue::from_raw(*self, res) }
+    }
+
+    pub fn get_undefined(&self) -> JsValue<'a> {
+        self.create(|res| unsafe {
+            sys::napi_get_undefined(self.raw, res);
+        })
+    }
+
+    pub fn get_null(&self) -> JsValue<'a> {
+        self.create(|res| unsafe {
+            sys::napi_get_null(self.raw, res);
+        })
+    }
+
+    pub fn get_boolean(&self, value: bool) -> JsBoolean<'a> {
+        JsBoolean::from_unchecked(self.create(|res| unsafe {
+            sys::napi_get_boolean(self.raw, value, res);
+        }))
+    }
+
+    pub fn create_double(&self, value: f64) -> JsNumber<'a> {
+        JsNumber::from_unchecked(self.create(|res| unsafe {
+            sys::napi_create_double(self.raw, value, res);
+        }))
+    }
+
+    pub fn create_string(&self, value: &str) -> JsString<'a> {
+        JsString::from_unchecked(self.create(|res| unsafe {
+            sys::napi_create_string_utf8(self.raw, value.as_ptr().cast(), value.len(), res);
+        }))
+    }
+
+    pub fn create_object(&self) -> JsObject<'a> {
+        JsObject::from_unchecked(self.create(|res| unsafe {
+            sys::napi_create_object(self.raw, res);
+        }))
+    }
+
+    pub fn create_array(&self, len: usize) -> JsArray<'a> {
+        JsArray::from_unchecked(self.create(|res| unsafe {
+            sys::napi_create_array_with_length(self.raw, len, res);
+        }))
+    }
+
+    // JS 中的 globalThis
+    pub fn get_global(&self) -> JsObject<'a> {
+        JsObject::from_unchecked(self.create(|res| unsafe {
+            sys::napi_get_global(self.raw, res);
+        }))
+    }
+
+    // 相当于 JS 中的 Symbol(description)
+    pub fn create_symbol(&self, description: Option<&str>) -> JsSymbol<'a> {
+        let description = description
+            .map(|description| self.create_string(description).raw())
+            .unwrap_or(ptr::null_mut());
+        JsSymbol::from_unchecked(self.create(|res| unsafe {
+            sys::napi_create_symbol(self.raw, description, res);
+        }))
+    }
+
+    // 相当于 JS 中的 new Date(millis)，millis 是距离 Unix 纪元的毫秒数
+    pub fn create_date(&self, millis: f64) -> JsDate<'a> {
+        JsDate::from_unchecked(self.create(|res| unsafe {
+            sys::napi_create_date(self.raw, millis, res);
+        }))
+    }
+
+    // 用符号和绝对值创建 BigInt，words 按小端顺序排列，低位在前
+    pub fn create_bigint_words(&self, negative: bool, words: &[u64]) -> JsBigInt<'a> {
+        JsBigInt::from_unchecked(self.create(|res| unsafe {
+            sys::napi_create_bigint_words(
+                self.raw,
+                negative as c_int,
+                words.len(),
+                words.as_ptr(),
+                res,
+            );
+        }))
+    }
+
+    // 用原生回调创建一个 JS 函数，name 会成为函数的 name 属性，
+    // data 会在每次调用时通过 CallContext::data 传给回调
+    pub(crate) fn create_function(
+        &self,
+        name: &str,
+        cb: napi_callback,
+        data: *mut c_void,
+    ) -> JsFunction<'a> {
+        let name = CString::new(name).unwrap();
+        JsFunction::from_unchecked(self.create(|res| unsafe {
+            sys::napi_create_function(
+                self.raw,
+                name.as_ptr(),
+                name.as_bytes().len(),
+                cb,
+                data,
+                res,
+            );
+        }))
+    }
+
+    // 创建一个指向 data 的 external 值，它被 GC 回收时调用 finalize 释放 data
+    pub(crate) fn create_external(
+        &self,
+        data: *mut c_void,
+        finalize: napi_finalize,
+    ) -> JsExternal<'a> {
+        JsExternal::from_unchecked(self.create(|res| unsafe {
+            sys::napi_create_external(self.raw, data, finalize, ptr::null_mut(), res);
+        }))
+    }
+
+    // 创建一个直接使用 data 作为存储的 Buffer，它被 GC 回收时调用 finalize(data, hint)。
+    // 创建失败时返回 napi_status，finalize 不会被调用，data 仍然归调用者所有
+    pub(crate) fn create_external_buffer(
+        &self,
+        data: *mut u8,
+        len: usize,
+        finalize: napi_finalize,
+        hint: *mut c_void,
+    ) -> Result<JsBuffer<'a>, napi_status> {
+        let mut status = sys::napi_ok;
+        let buffer = self.create(|res| unsafe {
+            status =
+                sys::napi_create_external_buffer(self.raw, len, data.cast(), finalize, hint, res);
+        });
+        match status {
+            sys::napi_ok => Ok(JsBuffer::from_unchecked(buffer)),
+            status => Err(status),
+        }
+    }
+
+    // value 被 GC 回收时调用 finalize(data)，用来释放绑定在 JS 对象上的原生数据
+    pub(crate) fn add_finalizer(
+        &self,
+        value: JsValue<'a>,
+        data: *mut c_void,
+        finalize: napi_finalize,
+    ) {
+        unsafe {
+            sys::napi_add_finalizer(
+                self.raw,
+                value.raw(),
+                data,
+                finalize,
+                ptr::null_mut(),
+                ptr::null_mut(),
+            );
+        };
+    }
+
+    // 相当于 JS 中的 throw error，异常会在当前原生调用返回之后抛出
+    pub fn throw(&self, error: JsValue<'a>) {
+        unsafe {
+            sys::napi_throw(self.raw, error.raw());
+        };
+    }
+
+    // 取出并清除当前未处理的异常，没有异常时返回 undefined
+    pub(crate) fn take_exception(&self) -> JsValue<'a> {
+        self.create(|res| unsafe {
+            sys::napi_get_and_clear_last_exception(self.raw, res);
+        })
+    }
+
+    // 当前是否已经有一个还没有抛出的异常
+    pub fn is_exception_pending(&self) -> bool {
+        let mut res = false;
+        unsafe {
+            sys::napi_is_exception_pending(self.raw, &mut res);
+        };
+        res
+    }
+}
@@ -0,0 +1,179 @@
+use std::fmt;
+use std::ptr;
+
+use crate::env::Env;
+use crate::js_value::{JsObject, JsValue};
+use crate::value::NapiValue;
+
+// 抛给 JS 时使用的错误类，对应 JS 内置的 Error 子类
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ErrorKind {
+    Error,
+    TypeError,
+    RangeError,
+    SyntaxError,
+    // 与 Node.js 内置 API 被 AbortSignal 取消时抛出的错误一致：err.name 为 "AbortError"
+    AbortError,
+}
+
+// #[api] 函数返回 Err 时抛给 JS 的错误。JS 中可以通过 err.code 区分不同的错误，
+// 通过 err.cause 拿到引起这个错误的下层错误
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Error {
+    kind: ErrorKind,
+    code: Option<String>,
+    message: String,
+    cause: Option<Box<Error>>,
+}
+
+impl Error {
+    pub fn new(kind: ErrorKind, message: impl Into<String>) -> Self {
+        Error {
+            kind,
+            code: None,
+            message: message.into(),
+            cause: None,
+        }
+    }
+
+    // 普通的 Error，没有 code
+    pub fn from_reason(message: impl Into<String>) -> Self {
+        Error::new(ErrorKind::Error, message)
+    }
+
+    pub fn type_error(message: impl Into<String>) -> Self {
+        Error::new(ErrorKind::TypeError, message)
+    }
+
+    pub fn range_error(message: impl Into<String>) -> Self {
+        Error::new(ErrorKind::RangeError, message)
+    }
+
+    pub fn syntax_error(message: impl Into<String>) -> Self {
+        Error::new(ErrorKind::SyntaxError, message)
+    }
+
+    // 操作被取消时的错误，code 与 Node.js 内置 API 一样是 "ABORT_ERR"
+    pub fn abort_error() -> Self {
+        Error::new(ErrorKind::AbortError, "The operation was aborted").with_code("ABORT_ERR")
+    }
+
+    // 设置 err.code，例如 "ERR_OUT_OF_RANGE"
+    pub fn with_code(mut self, code: impl Into<String>) -> Self {
+        self.code = Some(code.into());
+        self
+    }
+
+    // 设置 err.cause，cause 同样会被转换成一个 JS Error
+    pub fn with_cause(mut self, cause: impl Into<Error>) -> Self {
+        self.cause = Some(Box::new(cause.into()));
+        self
+    }
+
+    pub fn kind(&self) -> ErrorKind {
+        self.kind
+    }
+
+    pub fn code(&self) -> Option<&str> {
+        self.code.as_deref()
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    pub fn cause(&self) -> Option<&Error> {
+        self.cause.as_deref()
+    }
+
+    // 创建对应的 JS Error 对象，cause 链会被一起转换
+    pub(crate) fn into_js_error(self, env: Env<'_>) -> JsObject<'_> {
+        let create = match self.kind {
+            ErrorKind::Error => sys::napi_create_error,
+            ErrorKind::TypeError => sys::napi_create_type_error,
+            ErrorKind::RangeError => sys::napi_create_range_error,
+            ErrorKind::SyntaxError => sys::node_api_create_syntax_error,
+            // N-API 没有创建 AbortError 的函数，创建普通的 Error 之后再修改 name
+            ErrorKind::AbortError => sys::napi_create_error,
+        };
+        // code 为 NULL 时不会设置 err.code
+        let code = match &self.code {
+            Some(code) => env.create_string(code).raw(),
+            None => ptr::null_mut(),
+        };
+        let message = env.create_string(&self.message);
+        let mut error = ptr::null_mut();
+        unsafe {
+            create(env.raw(), code, message.raw(), &mut error);
+        };
+        let error = unsafe { JsObject::from_unchecked(JsValue::from_raw(env, error)) };
+        if self.kind == ErrorKind::AbortError {
+            error.set_named_property("name", env.create_string("AbortError"));
+        }
+        if let Some(cause) = self.cause {
+            error.set_named_property("cause", cause.into_js_error(env));
+        }
+        error
+    }
+
+    // 把错误抛给 JS。已经有一个未处理的异常时保留原来的异常，N-API 不允许同时抛出两个异常
+    pub(crate) fn throw(self, env: Env<'_>) {
+        if env.is_exception_pending() {
+            return;
+        }
+        env.throw(self.into_js_error(env).into());
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.code {
+            Some(code) => write!(f, "{} [{}]: {}", self.kind, code, self.message),
+            None => write!(f, "{}: {}", self.kind, self.message),
+        }
+    }
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.cause
+            .as_deref()
+            .map(|cause| cause as &(dyn std::error::Error + 'static))
+    }
+}
+
+// 返回 Result<T, String> 的函数抛出普通的 Error
+impl From<String> for Error {
+    fn from(message: String) -> Self {
+        Error::from_reason(message)
+    }
+}
+
+impl From<&str> for Error {
+    fn from(message: &str) -> Self {
+        Error::from_reason(message)
+    }
+}
+
+// Err 会被转换成 JS 异常抛出，函数的返回值此时没有意义，返回 undefined
+impl<'a, T: NapiValue<'a>, E: Into<Error>> NapiValue<'a> for Result<T, E> {
+    fn from_js_value(value: JsValue<'a>) -> Result<T, E> {
+        Ok(T::from_js_value(value))
+    }
+
+    fn into_js_value(self, env: Env<'a>) -> JsValue<'a> {
+        match self {
+            Ok(value) => value.into_js_value(env),
+            Err(err) => {
+                err.into().throw(env);
+                env.get_undefined()
+            }
+        }
+    }
+}
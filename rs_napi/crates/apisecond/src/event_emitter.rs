@@ -0,0 +1,133 @@
+use std::ptr;
+use sys::{napi_env, napi_ref};
+
+use crate::env::Env;
+use crate::error::Error;
+use crate::js_value::{JsFunction, JsObject, JsValue};
+use crate::threadsafe::ThreadsafeFunction;
+use crate::unwind;
+use crate::value::NapiValue;
+
+// 在 JS 主线程上把 payload 转换成 JS 值
+type Payload = Box<dyn for<'s> FnOnce(Env<'s>) -> JsValue<'s> + Send>;
+
+struct Event {
+    name: String,
+    payload: Payload,
+}
+
+// #[api] 函数的 EventEmitter 参数：JS 传入任意一个有 emit 方法的对象（通常是 events.EventEmitter），
+// Rust 可以在任意线程上通过 emit 向它发送事件，事件总是在 JS 主线程上以 target.emit(name, payload) 的形式派发。
+//
+// 顺序保证：
+// - 一个句柄和它的所有克隆共用一个队列，事件按进入队列的顺序派发；
+//   在一个线程上先后调用的 emit，或者通过其他同步手段确定了先后关系的 emit，派发顺序与之相同
+// - 每次从 JS 值转换都会创建一个新的队列：同一个 JS 对象多次传给 #[api] 函数得到的句柄互相独立，
+//   它们发出的事件之间没有确定的顺序。需要保证顺序时应该克隆同一个句柄，而不是再转换一次
+// - 不同线程并发调用的 emit 之间没有确定的顺序，但每个线程自己发出的事件仍然保持顺序
+// - 派发总是异步的：即使在 JS 主线程上调用 emit，监听器也要等到当前 JS 调用返回之后才会执行
+// - 监听器抛出的异常作为未捕获的异常交给 Node.js 处理，不影响之后的事件
+//
+// 只要还有句柄没有 drop，Node.js 的事件循环就不会退出
+#[derive(Clone)]
+pub struct EventEmitter {
+    tsfn: ThreadsafeFunction<Event>,
+    // 和 handler 中的 Target 是同一个引用，只在 JS 主线程上读取
+    reference: napi_ref,
+}
+
+// reference 只在 JS 主线程上使用，并且在 tsfn 被 finalize 之前一直有效
+unsafe impl Send for EventEmitter {}
+unsafe impl Sync for EventEmitter {}
+
+impl EventEmitter {
+    // 把事件放进队列，返回 false 表示环境已经销毁，事件被丢弃
+    pub fn emit<T>(&self, name: impl Into<String>, payload: T) -> bool
+    where
+        T: for<'s> NapiValue<'s> + Send + 'static,
+    {
+        self.tsfn.call(Event {
+            name: name.into(),
+            payload: Box::new(move |env| payload.into_js_value(env)),
+        })
+    }
+}
+
+impl<'a> NapiValue<'a> for EventEmitter {
+    fn from_js_value(value: JsValue<'a>) -> EventEmitter {
+        let env = value.env();
+        let target = JsObject::try_from(value)
+            .ok()
+            .filter(|target| JsFunction::try_from(target.get_named_property("emit")).is_ok());
+        let Some(target) = target else {
+            unwind::throw_error(
+                Error::type_error(format!(
+                    "expected an EventEmitter, found {}",
+                    value.value_type()
+                ))
+                .with_code("ERR_INVALID_ARG_TYPE"),
+            );
+        };
+        let target = Target::new(env, target);
+        let reference = target.reference;
+        let tsfn = ThreadsafeFunction::new(env, "apisecond:event", move |env, event: Event| {
+            target.emit(env, event)
+        });
+        EventEmitter { tsfn, reference }
+    }
+
+    // 返回给 JS 的是创建时传入的那个对象
+    fn into_js_value(self, env: Env<'a>) -> JsValue<'a> {
+        target(env, self.reference).into()
+    }
+}
+
+// 持有目标对象的引用，保证对象在最后一个句柄 drop 之前不会被 GC 回收。
+// 只在 JS 主线程上创建、使用和释放（threadsafe function 的 handler 在 finalize 时释放）
+struct Target {
+    env: napi_env,
+    reference: napi_ref,
+}
+
+impl Target {
+    fn new(env: Env<'_>, target: JsObject<'_>) -> Target {
+        let mut reference = ptr::null_mut();
+        unsafe {
+            sys::napi_create_reference(env.raw(), target.raw(), 1, &mut reference);
+        };
+        Target {
+            env: env.raw(),
+            reference,
+        }
+    }
+
+    fn emit(&self, env: Env<'_>, event: Event) {
+        let target = target(env, self.reference);
+        // 每次都重新读取 emit，和 JS 中调用 target.emit(...) 的行为一致
+        let Ok(emit) = JsFunction::try_from(target.get_named_property("emit")) else {
+            return;
+        };
+        let payload = (event.payload)(env);
+        // Result 类型的 payload 在转换时可能留下异常，此时不再派发
+        if env.is_exception_pending() {
+            return;
+        }
+        emit.call(target, &[env.create_string(&event.name).into(), payload]);
+    }
+}
+
+fn target(env: Env<'_>, reference: napi_ref) -> JsObject<'_> {
+    let mut target = ptr::null_mut();
+    unsafe {
+        sys::napi_get_reference_value(env.raw(), reference, &mut target);
+        JsObject::from_unchecked(JsValue::from_raw(env, target))
+    }
+}
+
+impl Drop for Target {
+    fn drop(&mut self) {
+        unsafe {
+            sys::napi_delete_reference(self.env, self.reference);
+        };
+    }
+}
@@ -0,0 +1,172 @@
+use std::any::{type_name, TypeId};
+use std::mem;
+use std::ops::Deref;
+use std::os::raw::c_void;
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+use sys::{napi_env, napi_type_tag};
+
+use crate::env::Env;
+use crate::error::Error;
+use crate::js_value::{JsExternal, JsValue};
+use crate::memory;
+use crate::unwind;
+use crate::value::NapiValue;
+
+// 本插件创建的 external 都打上这个标签，其他插件创建的 external 里的指针不能按我们的布局读取
+const EXTERNAL_TAG: napi_type_tag = napi_type_tag {
+    lower: 0x8f3a_61d2_4c7e_0b95,
+    upper: 0x2d6c_e0a4_93f1_57b8,
+};
+
+// 放进 external 中的数据。repr(C) 保证 type_id 位于开头且偏移与 T 无关，
+// 这样在不知道 T 的情况下也能先读出 type_id 检查类型
+#[repr(C)]
+struct ExternalData<T> {
+    type_id: TypeId,
+    size: usize,
+    value: T,
+}
+
+// 把一个 Rust 值交给 JS 持有。JS 得到的是一个不透明的 external 值，只能原样传回给
+// #[api] 函数，再通过 ExternalRef<T> 或 JsExternal::get 访问其中的值。
+// 值在 external 被 GC 回收时才会 drop，创建和回收时都会把占用的内存报告给 V8
+pub struct External<T> {
+    value: T,
+    size: usize,
+}
+
+impl<T: 'static> External<T> {
+    // 报告给 V8 的大小是 size_of::<T>()
+    pub fn new(value: T) -> Self {
+        External {
+            value,
+            size: mem::size_of::<T>(),
+        }
+    }
+
+    // T 在堆上持有额外的内存（例如 Vec、String）时，通过 size 报告实际占用的字节数
+    pub fn with_size(value: T, size: usize) -> Self {
+        External { value, size }
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+impl<T> Deref for External<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<'a, T: 'static> NapiValue<'a> for External<T> {
+    // #[api] 会拒绝 External<T> 参数，只有 CallContext::get 这类手动转换会走到这里
+    fn from_js_value(_value: JsValue<'a>) -> Self {
+        unwind::throw_error(
+            Error::type_error(format!(
+                "External<{}> cannot be taken back from JS, use ExternalRef instead",
+                type_name::<T>()
+            ))
+            .with_code("ERR_INVALID_ARG_TYPE"),
+        )
+    }
+
+    fn into_js_value(self, env: Env<'a>) -> JsValue<'a> {
+        let size = self.size;
+        let data = Box::into_raw(Box::new(ExternalData {
+            type_id: TypeId::of::<T>(),
+            size,
+            value: self.value,
+        }));
+        let external = env.create_external(data.cast(), Some(finalize::<T>));
+        unsafe {
+            sys::napi_type_tag_object(env.raw(), external.raw(), &EXTERNAL_TAG);
+        };
+        memory::track_alloc(env, size);
+        external.into()
+    }
+}
+
+unsafe extern "C" fn finalize<T>(env: napi_env, data: *mut c_void, _hint: *mut c_void) {
+    let data = Box::from_raw(data.cast::<ExternalData<T>>());
+    let size = data.size;
+    // finalizer 中没有可以抛出异常的 JS 调用，T 的 drop 发生 panic 时只能忽略
+    let _ = panic::catch_unwind(AssertUnwindSafe(|| drop(data)));
+    memory::track_free(Env::from_raw(env), size);
+}
+
+impl<'a> JsExternal<'a> {
+    // 取回 External<T> 中的值。external 不是本插件创建的，或者其中的值不是 T 时返回 None
+    pub fn get<T: 'static>(&self) -> Option<&'a T> {
+        let env = self.env().raw();
+        let mut tagged = false;
+        unsafe {
+            sys::napi_check_object_type_tag(env, self.raw(), &EXTERNAL_TAG, &mut tagged);
+        };
+        if !tagged {
+            return None;
+        }
+        let mut data = ptr::null_mut();
+        unsafe {
+            sys::napi_get_value_external(env, self.raw(), &mut data);
+        };
+        // 只要 external 还在当前 scope 中被引用，GC 就不会回收它，数据在 'a 期间一直有效
+        unsafe {
+            if *data.cast::<TypeId>() != TypeId::of::<T>() {
+                return None;
+            }
+            Some(&(*data.cast::<ExternalData<T>>()).value)
+        }
+    }
+}
+
+// 借用 JS 传回来的 External<T> 中的值，作为 #[api] 参数时类型不匹配会抛出 TypeError
+pub struct ExternalRef<'a, T> {
+    external: JsExternal<'a>,
+    value: &'a T,
+}
+
+impl<'a, T> Clone for ExternalRef<'a, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, T> Copy for ExternalRef<'a, T> {}
+
+impl<'a, T> Deref for ExternalRef<'a, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.value
+    }
+}
+
+impl<'a, T: 'static> NapiValue<'a> for ExternalRef<'a, T> {
+    fn from_js_value(value: JsValue<'a>) -> Self {
+        let external = JsExternal::from_js_value(value);
+        let value = external.get::<T>().unwrap_or_else(|| {
+            unwind::throw_error(
+                Error::type_error(format!(
+                    "expected an External<{}> created by this addon",
+                    type_name::<T>()
+                ))
+                .with_code("ERR_INVALID_ARG_TYPE"),
+            )
+        });
+        ExternalRef { external, value }
+    }
+
+    // 原样返回传入的 external，不会创建新的值
+    fn into_js_value(self, _env: Env<'a>) -> JsValue<'a> {
+        self.external.into()
+    }
+}
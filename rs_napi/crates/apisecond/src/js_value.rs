@@ -0,0 +1,452 @@
+use std::ffi::CString;
+use std::fmt;
+use std::ops::Deref;
+use std::ptr;
+use sys::{napi_property_attributes, napi_property_descriptor, napi_value};
+
+use crate::env::Env;
+use crate::error::Error;
+use crate::unwind;
+use crate::value::NapiValue;
+
+// JS 值的类型，也就是 typeof 的结果（null 单独作为一种类型）
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ValueType {
+    Undefined,
+    Null,
+    Boolean,
+    Number,
+    String,
+    Symbol,
+    Object,
+    Function,
+    External,
+    BigInt,
+}
+
+impl fmt::Display for ValueType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ValueType::Undefined => "undefined",
+            ValueType::Null => "null",
+            ValueType::Boolean => "boolean",
+            ValueType::Number => "number",
+            ValueType::String => "string",
+            ValueType::Symbol => "symbol",
+            ValueType::Object => "object",
+            ValueType::Function => "function",
+            ValueType::External => "external",
+            ValueType::BigInt => "bigint",
+        };
+        f.write_str(name)
+    }
+}
+
+// 任意一个 JS 值。它和创建它的 Env 有同样的生命周期，不能在 handle scope 之外使用
+#[derive(Clone, Copy, Debug)]
+pub struct JsValue<'a> {
+    env: Env<'a>,
+    raw: napi_value,
+}
+
+impl<'a> JsValue<'a> {
+    // raw 必须是在 env 对应的 handle scope 中创建的值
+    pub(crate) unsafe fn from_raw(env: Env<'a>, raw: napi_value) -> JsValue<'a> {
+        JsValue { env, raw }
+    }
+
+    pub fn env(&self) -> Env<'a> {
+        self.env
+    }
+
+    pub fn raw(&self) -> napi_value {
+        self.raw
+    }
+
+    pub fn value_type(&self) -> ValueType {
+        let mut ty = sys::napi_undefined;
+        unsafe {
+            sys::napi_typeof(self.env.raw(), self.raw, &mut ty);
+        };
+        match ty {
+            sys::napi_null => ValueType::Null,
+            sys::napi_boolean => ValueType::Boolean,
+            sys::napi_number => ValueType::Number,
+            sys::napi_string => ValueType::String,
+            sys::napi_symbol => ValueType::Symbol,
+            sys::napi_object => ValueType::Object,
+            sys::napi_function => ValueType::Function,
+            sys::napi_external => ValueType::External,
+            sys::napi_bigint => ValueType::BigInt,
+            _ => ValueType::Undefined,
+        }
+    }
+
+    pub fn is_undefined_or_null(&self) -> bool {
+        matches!(self.value_type(), ValueType::Undefined | ValueType::Null)
+    }
+
+    pub fn is_array(&self) -> bool {
+        let mut res = false;
+        unsafe {
+            sys::napi_is_array(self.env.raw(), self.raw, &mut res);
+        };
+        res
+    }
+
+    pub fn is_date(&self) -> bool {
+        let mut res = false;
+        unsafe {
+            sys::napi_is_date(self.env.raw(), self.raw, &mut res);
+        };
+        res
+    }
+
+    // 是否是一个 Node.js Buffer（包括其他 Uint8Array）
+    pub fn is_buffer(&self) -> bool {
+        let mut res = false;
+        unsafe {
+            sys::napi_is_buffer(self.env.raw(), self.raw, &mut res);
+        };
+        res
+    }
+
+    // 是否是一个 Promise（或者继承自 Promise 的对象）
+    pub fn is_promise(&self) -> bool {
+        let mut res = false;
+        unsafe {
+            sys::napi_is_promise(self.env.raw(), self.raw, &mut res);
+        };
+        res
+    }
+
+    // 调用一个输出 napi_value 的 N-API 函数，结果和 self 属于同一个 scope
+    fn create(&self, create: impl FnOnce(*mut napi_value)) -> JsValue<'a> {
+        let mut res = ptr::null_mut();
+        create(&mut res);
+        JsValue {
+            env: self.env,
+            raw: res,
+        }
+    }
+}
+
+impl<'a> NapiValue<'a> for JsValue<'a> {
+    fn from_js_value(value: JsValue<'a>) -> JsValue<'a> {
+        value
+    }
+
+    fn into_js_value(self, _env: Env<'a>) -> JsValue<'a> {
+        self
+    }
+}
+
+// 定义具体类型的 JS 值。可以通过 TryFrom<JsValue> 检查类型后转换，
+// 作为 #[api] 参数时类型不匹配会抛出 TypeError
+macro_rules! js_type {
+    // 按 typeof 的结果检查类型
+    ($name:ident, $expected:literal, $($ty:ident)|+) => {
+        js_type!(@define $name, $expected, |value| matches!(
+            value.value_type(),
+            $(ValueType::$ty)|+
+        ));
+    };
+    // typeof 无法区分的类型（数组、Date、Buffer），使用 JsValue 上对应的 is_* 方法检查
+    ($name:ident, $expected:literal, fn $check:ident) => {
+        js_type!(@define $name, $expected, |value| value.$check());
+    };
+    (@define $name:ident, $expected:literal, |$value:ident| $check:expr) => {
+        #[derive(Clone, Copy, Debug)]
+        pub struct $name<'a>(JsValue<'a>);
+
+        impl<'a> $name<'a> {
+            // 不检查类型直接转换，只用于 N-API 保证了类型的场景（例如刚刚创建的值）
+            pub(crate) fn from_unchecked(value: JsValue<'a>) -> Self {
+                $name(value)
+            }
+        }
+
+        impl<'a> Deref for $name<'a> {
+            type Target = JsValue<'a>;
+
+            fn deref(&self) -> &JsValue<'a> {
+                &self.0
+            }
+        }
+
+        impl<'a> From<$name<'a>> for JsValue<'a> {
+            fn from(value: $name<'a>) -> JsValue<'a> {
+                value.0
+            }
+        }
+
+        impl<'a> TryFrom<JsValue<'a>> for $name<'a> {
+            type Error = Error;
+
+            fn try_from($value: JsValue<'a>) -> Result<Self, Error> {
+                if $check {
+                    Ok($name($value))
+                } else {
+                    Err(Error::type_error(format!(
+                        "expected {}, found {}",
+                        $expected,
+                        $value.value_type()
+                    ))
+                    .with_code("ERR_INVALID_ARG_TYPE"))
+                }
+            }
+        }
+
+        impl<'a> NapiValue<'a> for $name<'a> {
+            fn from_js_value(value: JsValue<'a>) -> Self {
+                Self::try_from(value).unwrap_or_else(|err| unwind::throw_error(err))
+            }
+
+            fn into_js_value(self, _env: Env<'a>) -> JsValue<'a> {
+                self.0
+            }
+        }
+    };
+}
+
+js_type!(JsBoolean, "a boolean", Boolean);
+js_type!(JsNumber, "a number", Number);
+js_type!(JsString, "a string", String);
+js_type!(JsSymbol, "a symbol", Symbol);
+js_type!(JsBigInt, "a bigint", BigInt);
+js_type!(JsFunction, "a function", Function);
+// 函数也是对象，同样可以读写属性
+js_type!(JsObject, "an object", Object | Function);
+js_type!(JsArray, "an array", fn is_array);
+js_type!(JsDate, "a Date", fn is_date);
+js_type!(JsExternal, "an external", External);
+js_type!(JsBuffer, "a Buffer", fn is_buffer);
+
+impl<'a> JsBoolean<'a> {
+    pub fn get(&self) -> bool {
+        let mut res = false;
+        unsafe {
+            sys::napi_get_value_bool(self.env().raw(), self.raw(), &mut res);
+        };
+        res
+    }
+}
+
+impl<'a> JsNumber<'a> {
+    pub fn get(&self) -> f64 {
+        let mut res = 0.0;
+        unsafe {
+            sys::napi_get_value_double(self.env().raw(), self.raw(), &mut res);
+        };
+        res
+    }
+}
+
+impl<'a> JsString<'a> {
+    pub fn into_string(self) -> String {
+        let env = self.env().raw();
+        // 第一次调用传入空的缓冲区，只获取字符串的字节长度
+        let mut len = 0;
+        unsafe {
+            sys::napi_get_value_string_utf8(env, self.raw(), ptr::null_mut(), 0, &mut len);
+        };
+        // 缓冲区需要额外留出一个字节给结尾的 null
+        let mut buf = vec![0u8; len + 1];
+        unsafe {
+            sys::napi_get_value_string_utf8(
+                env,
+                self.raw(),
+                buf.as_mut_ptr().cast(),
+                buf.len(),
+                &mut len,
+            );
+        };
+        buf.truncate(len);
+        String::from_utf8(buf).unwrap_or_default()
+    }
+}
+
+impl<'a> JsBigInt<'a> {
+    // 返回符号和按小端顺序排列的 64 位 words
+    pub fn get_words(&self) -> (bool, Vec<u64>) {
+        let env = self.env().raw();
+        // 第一次调用只获取 words 的个数
+        let mut word_count = 0;
+        unsafe {
+            sys::napi_get_value_bigint_words(
+                env,
+                self.raw(),
+                ptr::null_mut(),
+                &mut word_count,
+                ptr::null_mut(),
+            );
+        };
+        let mut sign_bit = 0;
+        let mut words = vec![0u64; word_count];
+        unsafe {
+            sys::napi_get_value_bigint_words(
+                env,
+                self.raw(),
+                &mut sign_bit,
+                &mut word_count,
+                words.as_mut_ptr(),
+            );
+        };
+        (sign_bit != 0, words)
+    }
+}
+
+impl<'a> JsDate<'a> {
+    // 距离 Unix 纪元的毫秒数，Invalid Date 返回 NaN
+    pub fn value_of(&self) -> f64 {
+        let mut res = 0.0;
+        unsafe {
+            sys::napi_get_date_value(self.env().raw(), self.raw(), &mut res);
+        };
+        res
+    }
+}
+
+impl<'a> JsFunction<'a> {
+    // 相当于 JS 中的 func.call(this, ...args)，函数抛出异常时返回的是 undefined，
+    // 异常会留在 env 中，原生调用返回后抛给调用者
+    pub fn call(&self, this: impl Into<JsValue<'a>>, args: &[JsValue<'a>]) -> JsValue<'a> {
+        let args = args.iter().map(|arg| arg.raw()).collect::<Vec<_>>();
+        let this = this.into();
+        let res = self.create(|res| unsafe {
+            sys::napi_call_function(
+                self.env().raw(),
+                this.raw(),
+                self.raw(),
+                args.len(),
+                args.as_ptr(),
+                res,
+            );
+        });
+        // 调用失败时出参没有被写入
+        if res.raw().is_null() {
+            self.env().get_undefined()
+        } else {
+            res
+        }
+    }
+}
+
+impl<'a> JsArray<'a> {
+    pub fn len(&self) -> u32 {
+        let mut res = 0;
+        unsafe {
+            sys::napi_get_array_length(self.env().raw(), self.raw(), &mut res);
+        };
+        res
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn get(&self, index: u32) -> JsValue<'a> {
+        self.create(|res| unsafe {
+            sys::napi_get_element(self.env().raw(), self.raw(), index, res);
+        })
+    }
+
+    pub fn set(&self, index: u32, value: impl Into<JsValue<'a>>) {
+        unsafe {
+            sys::napi_set_element(self.env().raw(), self.raw(), index, value.into().raw());
+        };
+    }
+}
+
+impl<'a> JsObject<'a> {
+    pub fn get_named_property(&self, name: &str) -> JsValue<'a> {
+        self.get_property(self.env().create_string(name))
+    }
+
+    pub fn set_named_property(&self, name: &str, value: impl Into<JsValue<'a>>) {
+        let name = CString::new(name).unwrap();
+        unsafe {
+            sys::napi_set_named_property(
+                self.env().raw(),
+                self.raw(),
+                name.as_ptr(),
+                value.into().raw(),
+            );
+        };
+    }
+
+    pub fn get_property(&self, key: impl Into<JsValue<'a>>) -> JsValue<'a> {
+        let key = key.into();
+        self.create(|res| unsafe {
+            sys::napi_get_property(self.env().raw(), self.raw(), key.raw(), res);
+        })
+    }
+
+    // key 可以是任意值，和 JS 中 object[key] = value 一样会被转换成属性名
+    pub fn set_property(&self, key: impl Into<JsValue<'a>>, value: impl Into<JsValue<'a>>) {
+        unsafe {
+            sys::napi_set_property(
+                self.env().raw(),
+                self.raw(),
+                key.into().raw(),
+                value.into().raw(),
+            );
+        };
+    }
+
+    // 对象自身及原型链上可枚举的字符串 key，和 for...in 的结果一致
+    pub fn property_names(&self) -> JsArray<'a> {
+        JsArray::from_unchecked(self.create(|res| unsafe {
+            sys::napi_get_property_names(self.env().raw(), self.raw(), res);
+        }))
+    }
+
+    // 对象自身可枚举的字符串 key，和 Object.keys 的结果一致
+    pub fn own_property_names(&self) -> JsArray<'a> {
+        JsArray::from_unchecked(self.create(|res| unsafe {
+            sys::napi_get_all_property_names(
+                self.env().raw(),
+                self.raw(),
+                sys::napi_key_own_only,
+                sys::napi_key_enumerable | sys::napi_key_skip_symbols,
+                sys::napi_key_numbers_to_strings,
+                res,
+            );
+        }))
+    }
+
+    // 一次定义多个带有相同特性（可写、可枚举、可配置）的属性
+    pub(crate) fn define_properties(
+        &self,
+        properties: &[(&str, JsValue<'a>)],
+        attributes: napi_property_attributes,
+    ) {
+        // 描述符中的 utf8name 指向这些 CString，要保证它们活到 napi_define_properties 返回
+        let names = properties
+            .iter()
+            .map(|(name, _)| CString::new(*name).unwrap())
+            .collect::<Vec<_>>();
+        let descriptors = properties
+            .iter()
+            .zip(&names)
+            .map(|((_, value), name)| napi_property_descriptor {
+                utf8name: name.as_ptr(),
+                name: ptr::null_mut(),
+                method: None,
+                getter: None,
+                setter: None,
+                value: value.raw(),
+                attributes,
+                data: ptr::null_mut(),
+            })
+            .collect::<Vec<_>>();
+        unsafe {
+            sys::napi_define_properties(
+                self.env().raw(),
+                self.raw(),
+                descriptors.len(),
+                descriptors.as_ptr(),
+            );
+        };
+    }
+}
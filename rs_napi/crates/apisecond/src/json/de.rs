@@ -0,0 +1,286 @@
+use serde::de::{self, DeserializeSeed, IntoDeserializer, Visitor};
+
+use crate::error::Error;
+use crate::js_value::{JsArray, JsBigInt, JsObject, JsValue, ValueType};
+use crate::value::{self, NapiValue};
+
+// 能够被 f64 精确表示的最大整数 2^53 - 1，在这个范围内的整数以整数的形式交给 Visitor
+const MAX_SAFE_INTEGER: f64 = 9007199254740991.0;
+
+// 对象和数组最多嵌套的层数，和 serde_json 的默认限制相同。
+// 有循环引用的对象会一直嵌套下去，超过这个深度时以 TypeError 结束，而不是耗尽栈空间
+const MAX_DEPTH: u32 = 128;
+
+// 直接从 JS 值反序列化出 Rust 值
+#[derive(Clone, Copy)]
+pub struct Deserializer<'a> {
+    value: JsValue<'a>,
+    // value 所在的对象和数组的层数
+    depth: u32,
+}
+
+impl<'a> Deserializer<'a> {
+    pub fn new(value: JsValue<'a>) -> Self {
+        Deserializer { value, depth: 0 }
+    }
+
+    // 类型已经检查过了，直接按对象处理
+    fn object(&self) -> JsObject<'a> {
+        JsObject::from_unchecked(self.value)
+    }
+
+    // 进入当前对象或数组，返回其中元素所在的层数
+    fn enter(&self) -> Result<u32, Error> {
+        if self.depth >= MAX_DEPTH {
+            return Err(<Error as de::Error>::custom(format!(
+                "cannot deserialize a value nested more than {} levels deep, it may contain a cycle",
+                MAX_DEPTH
+            )));
+        }
+        Ok(self.depth + 1)
+    }
+}
+
+// 对象或数组中的一个元素
+fn nested(value: JsValue<'_>, depth: u32) -> Deserializer<'_> {
+    Deserializer { value, depth }
+}
+
+fn unsupported(ty: ValueType) -> Error {
+    <Error as de::Error>::custom(format!("cannot deserialize a JS {}", ty))
+}
+
+impl<'de, 'a> de::Deserializer<'de> for Deserializer<'a> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        let value = self.value;
+        match value.value_type() {
+            ValueType::Undefined | ValueType::Null => visitor.visit_unit(),
+            ValueType::Boolean => visitor.visit_bool(bool::from_js_value(value)),
+            ValueType::Number => {
+                let number = f64::from_js_value(value);
+                // 整数交给 visit_i64/visit_u64，这样 u32、i64 等整数类型的字段也能接受 JS number
+                if number.fract() == 0.0 && number.abs() <= MAX_SAFE_INTEGER {
+                    if number < 0.0 {
+                        visitor.visit_i64(number as i64)
+                    } else {
+                        visitor.visit_u64(number as u64)
+                    }
+                } else {
+                    visitor.visit_f64(number)
+                }
+            }
+            ValueType::String => visitor.visit_string(String::from_js_value(value)),
+            // 非负数按 u128 读取，这样 u128 字段也能接受超过 i128 范围的值；两者都表示不了时抛出 RangeError
+            ValueType::BigInt => {
+                let bigint = JsBigInt::from_unchecked(value);
+                if bigint.get_words().0 {
+                    visitor.visit_i128(value::bigint_to_i128(bigint)?)
+                } else {
+                    visitor.visit_u128(value::bigint_to_u128(bigint)?)
+                }
+            }
+            ValueType::Object if value.is_array() => {
+                let array = JsArray::from_unchecked(value);
+                visitor.visit_seq(SeqAccess {
+                    array,
+                    index: 0,
+                    len: array.len(),
+                    depth: self.enter()?,
+                })
+            }
+            ValueType::Object => {
+                // 只读取对象自身的属性，原型链上的属性不属于这个值
+                let keys = self.object().own_property_names();
+                visitor.visit_map(MapAccess {
+                    object: self.object(),
+                    keys,
+                    index: 0,
+                    len: keys.len(),
+                    key: None,
+                    depth: self.enter()?,
+                })
+            }
+            ty => Err(unsupported(ty)),
+        }
+    }
+
+    // undefined 和 null 都对应 None
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        if self.value.is_undefined_or_null() {
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self)
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    // 单元变体是字符串 "Variant"，其他变体是只有一个 key 的对象 { Variant: value }
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        match self.value.value_type() {
+            ValueType::String => {
+                let variant = String::from_js_value(self.value);
+                visitor.visit_enum(variant.into_deserializer())
+            }
+            ValueType::Object => {
+                let keys = self.object().own_property_names();
+                if keys.len() != 1 {
+                    return Err(<Error as de::Error>::custom(format!(
+                        "expected an object with a single key for an enum variant, found {} keys",
+                        keys.len()
+                    )));
+                }
+                let variant = keys.get(0);
+                visitor.visit_enum(EnumAccess {
+                    variant,
+                    value: nested(self.object().get_property(variant), self.enter()?),
+                })
+            }
+            _ => Err(<Error as de::Error>::custom(
+                "expected a string or an object for an enum",
+            )),
+        }
+    }
+
+    // 未知字段的值直接跳过，不读取其中的内容：即使它引用了自身也不会无限递归
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_unit()
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map struct
+        identifier
+    }
+}
+
+struct SeqAccess<'a> {
+    array: JsArray<'a>,
+    index: u32,
+    len: u32,
+    // 元素所在的层数
+    depth: u32,
+}
+
+impl<'de, 'a> de::SeqAccess<'de> for SeqAccess<'a> {
+    type Error = Error;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, Error> {
+        if self.index >= self.len {
+            return Ok(None);
+        }
+        let element = self.array.get(self.index);
+        self.index += 1;
+        seed.deserialize(nested(element, self.depth)).map(Some)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some((self.len - self.index) as usize)
+    }
+}
+
+struct MapAccess<'a> {
+    object: JsObject<'a>,
+    keys: JsArray<'a>,
+    index: u32,
+    len: u32,
+    // next_key_seed 读到的 key，next_value_seed 用它读取对应的属性值
+    key: Option<JsValue<'a>>,
+    // 属性值所在的层数
+    depth: u32,
+}
+
+impl<'de, 'a> de::MapAccess<'de> for MapAccess<'a> {
+    type Error = Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, Error> {
+        if self.index >= self.len {
+            return Ok(None);
+        }
+        let key = self.keys.get(self.index);
+        self.index += 1;
+        self.key = Some(key);
+        seed.deserialize(Deserializer::new(key)).map(Some)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, Error> {
+        let key = self.key.take().ok_or_else(|| {
+            <Error as de::Error>::custom("next_value_seed called before next_key_seed")
+        })?;
+        seed.deserialize(nested(self.object.get_property(key), self.depth))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some((self.len - self.index) as usize)
+    }
+}
+
+struct EnumAccess<'a> {
+    variant: JsValue<'a>,
+    value: Deserializer<'a>,
+}
+
+impl<'de, 'a> de::EnumAccess<'de> for EnumAccess<'a> {
+    type Error = Error;
+    type Variant = VariantAccess<'a>;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, VariantAccess<'a>), Error> {
+        let variant = seed.deserialize(Deserializer::new(self.variant))?;
+        Ok((
+            variant,
+            VariantAccess {
+                deserializer: self.value,
+            },
+        ))
+    }
+}
+
+struct VariantAccess<'a> {
+    deserializer: Deserializer<'a>,
+}
+
+impl<'de, 'a> de::VariantAccess<'de> for VariantAccess<'a> {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value, Error> {
+        seed.deserialize(self.deserializer)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value, Error> {
+        de::Deserializer::deserialize_seq(self.deserializer, visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        de::Deserializer::deserialize_map(self.deserializer, visitor)
+    }
+}
@@ -0,0 +1,69 @@
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+
+use crate::env::Env;
+use crate::error::Error;
+use crate::js_value::JsValue;
+use crate::unwind;
+use crate::value::NapiValue;
+
+mod de;
+mod ser;
+
+pub use de::Deserializer;
+pub use ser::Serializer;
+
+// 任何实现了 Serialize/Deserialize 的类型都可以包在 Json 中作为 #[api] 的参数或返回值。
+// 转换时直接通过 N-API 遍历 JS 值，不会先序列化成 JSON 字符串：
+// 结构体和 map 对应 JS 对象，序列和元组对应数组，None 和 () 对应 null，
+// 枚举使用 serde 默认的外部标记表示，例如 "Unit" 或者 { "Newtype": value }。
+// 反序列化时只读取对象自身可枚举的属性，对象和数组最多嵌套 128 层，超出时（例如有循环引用）抛出 TypeError
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Json<T>(pub T);
+
+impl<T> Json<T> {
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+// 转换失败时以 TypeError 的形式抛给 JS
+impl<'a, T: Serialize + DeserializeOwned> NapiValue<'a> for Json<T> {
+    fn from_js_value(value: JsValue<'a>) -> Json<T> {
+        T::deserialize(Deserializer::new(value))
+            .map(Json)
+            .unwrap_or_else(|err| unwind::throw_error(err))
+    }
+
+    fn into_js_value(self, env: Env<'a>) -> JsValue<'a> {
+        self.0
+            .serialize(Serializer::new(env))
+            .unwrap_or_else(|err| unwind::throw_error(err))
+    }
+}
+
+impl serde::ser::Error for Error {
+    fn custom<T: Display>(msg: T) -> Self {
+        Error::type_error(msg.to_string()).with_code("ERR_SERIALIZE")
+    }
+}
+
+impl serde::de::Error for Error {
+    fn custom<T: Display>(msg: T) -> Self {
+        Error::type_error(msg.to_string()).with_code("ERR_DESERIALIZE")
+    }
+}
+
+// 反序列化时 Json<T> 本身是透明的，这样 Json<T> 也可以嵌套在其他类型中
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Json<T> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        T::deserialize(deserializer).map(Json)
+    }
+}
+
+impl<T: Serialize> Serialize for Json<T> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.serialize(serializer)
+    }
+}
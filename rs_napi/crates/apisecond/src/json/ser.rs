@@ -0,0 +1,353 @@
+use serde::ser::{self, Serialize};
+
+use crate::env::Env;
+use crate::error::Error;
+use crate::js_value::{JsArray, JsObject, JsValue};
+use crate::value::NapiValue;
+
+// 把 Rust 值直接序列化成 JS 值
+#[derive(Clone, Copy)]
+pub struct Serializer<'a> {
+    env: Env<'a>,
+}
+
+impl<'a> Serializer<'a> {
+    pub fn new(env: Env<'a>) -> Self {
+        Serializer { env }
+    }
+
+    fn number(&self, value: f64) -> JsValue<'a> {
+        value.into_js_value(self.env)
+    }
+
+    fn string(&self, value: &str) -> JsValue<'a> {
+        self.env.create_string(value).into()
+    }
+}
+
+// 外部标记的枚举变体：{ [variant]: value }
+fn wrap_variant<'a>(env: Env<'a>, variant: &str, value: JsValue<'a>) -> JsValue<'a> {
+    let object = env.create_object();
+    object.set_named_property(variant, value);
+    object.into()
+}
+
+impl<'a> ser::Serializer for Serializer<'a> {
+    type Ok = JsValue<'a>;
+    type Error = Error;
+
+    type SerializeSeq = SeqSerializer<'a>;
+    type SerializeTuple = SeqSerializer<'a>;
+    type SerializeTupleStruct = SeqSerializer<'a>;
+    type SerializeTupleVariant = VariantSerializer<'a, SeqSerializer<'a>>;
+    type SerializeMap = MapSerializer<'a>;
+    type SerializeStruct = MapSerializer<'a>;
+    type SerializeStructVariant = VariantSerializer<'a, MapSerializer<'a>>;
+
+    fn serialize_bool(self, v: bool) -> Result<JsValue<'a>, Error> {
+        Ok(v.into_js_value(self.env))
+    }
+
+    // JS 中只有一种 number，超过 2^53 的 64 位整数会丢失精度，需要精确值时请使用 i128/u128
+    fn serialize_i8(self, v: i8) -> Result<JsValue<'a>, Error> {
+        Ok(self.number(v as f64))
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<JsValue<'a>, Error> {
+        Ok(self.number(v as f64))
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<JsValue<'a>, Error> {
+        Ok(self.number(v as f64))
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<JsValue<'a>, Error> {
+        Ok(self.number(v as f64))
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<JsValue<'a>, Error> {
+        Ok(v.into_js_value(self.env))
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<JsValue<'a>, Error> {
+        Ok(self.number(v as f64))
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<JsValue<'a>, Error> {
+        Ok(self.number(v as f64))
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<JsValue<'a>, Error> {
+        Ok(self.number(v as f64))
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<JsValue<'a>, Error> {
+        Ok(self.number(v as f64))
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<JsValue<'a>, Error> {
+        Ok(v.into_js_value(self.env))
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<JsValue<'a>, Error> {
+        Ok(self.number(v as f64))
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<JsValue<'a>, Error> {
+        Ok(self.number(v))
+    }
+
+    fn serialize_char(self, v: char) -> Result<JsValue<'a>, Error> {
+        Ok(self.string(v.encode_utf8(&mut [0; 4])))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<JsValue<'a>, Error> {
+        Ok(self.string(v))
+    }
+
+    // 字节序列转换成由数字组成的数组
+    fn serialize_bytes(self, v: &[u8]) -> Result<JsValue<'a>, Error> {
+        ser::Serializer::collect_seq(self, v)
+    }
+
+    fn serialize_none(self) -> Result<JsValue<'a>, Error> {
+        Ok(self.env.get_null())
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<JsValue<'a>, Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<JsValue<'a>, Error> {
+        Ok(self.env.get_null())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<JsValue<'a>, Error> {
+        Ok(self.env.get_null())
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<JsValue<'a>, Error> {
+        Ok(self.string(variant))
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<JsValue<'a>, Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<JsValue<'a>, Error> {
+        let value = value.serialize(self)?;
+        Ok(wrap_variant(self.env, variant, value))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<SeqSerializer<'a>, Error> {
+        Ok(SeqSerializer {
+            env: self.env,
+            array: self.env.create_array(len.unwrap_or(0)),
+            index: 0,
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<SeqSerializer<'a>, Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<SeqSerializer<'a>, Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<VariantSerializer<'a, SeqSerializer<'a>>, Error> {
+        Ok(VariantSerializer {
+            env: self.env,
+            variant,
+            inner: self.serialize_seq(Some(len))?,
+        })
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<MapSerializer<'a>, Error> {
+        Ok(MapSerializer {
+            env: self.env,
+            object: self.env.create_object(),
+            key: None,
+        })
+    }
+
+    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<MapSerializer<'a>, Error> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<VariantSerializer<'a, MapSerializer<'a>>, Error> {
+        Ok(VariantSerializer {
+            env: self.env,
+            variant,
+            inner: self.serialize_map(Some(len))?,
+        })
+    }
+}
+
+pub struct SeqSerializer<'a> {
+    env: Env<'a>,
+    array: JsArray<'a>,
+    index: u32,
+}
+
+impl<'a> ser::SerializeSeq for SeqSerializer<'a> {
+    type Ok = JsValue<'a>;
+    type Error = Error;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        let value = value.serialize(Serializer::new(self.env))?;
+        self.array.set(self.index, value);
+        self.index += 1;
+        Ok(())
+    }
+
+    fn end(self) -> Result<JsValue<'a>, Error> {
+        Ok(self.array.into())
+    }
+}
+
+impl<'a> ser::SerializeTuple for SeqSerializer<'a> {
+    type Ok = JsValue<'a>;
+    type Error = Error;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<JsValue<'a>, Error> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+impl<'a> ser::SerializeTupleStruct for SeqSerializer<'a> {
+    type Ok = JsValue<'a>;
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<JsValue<'a>, Error> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+pub struct MapSerializer<'a> {
+    env: Env<'a>,
+    object: JsObject<'a>,
+    // serialize_key 和 serialize_value 分两次调用，先把 key 保存下来
+    key: Option<JsValue<'a>>,
+}
+
+impl<'a> ser::SerializeMap for MapSerializer<'a> {
+    type Ok = JsValue<'a>;
+    type Error = Error;
+
+    // key 可以是任意值，作为属性名时会被 JS 转换成字符串，和 JSON 中数字 key 的处理方式一致
+    fn serialize_key<T: ?Sized + Serialize>(&mut self, key: &T) -> Result<(), Error> {
+        self.key = Some(key.serialize(Serializer::new(self.env))?);
+        Ok(())
+    }
+
+    fn serialize_value<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        let key = self.key.take().ok_or_else(|| {
+            <Error as ser::Error>::custom("serialize_value called before serialize_key")
+        })?;
+        let value = value.serialize(Serializer::new(self.env))?;
+        self.object.set_property(key, value);
+        Ok(())
+    }
+
+    fn end(self) -> Result<JsValue<'a>, Error> {
+        Ok(self.object.into())
+    }
+}
+
+impl<'a> ser::SerializeStruct for MapSerializer<'a> {
+    type Ok = JsValue<'a>;
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        let value = value.serialize(Serializer::new(self.env))?;
+        self.object.set_named_property(key, value);
+        Ok(())
+    }
+
+    fn end(self) -> Result<JsValue<'a>, Error> {
+        Ok(self.object.into())
+    }
+}
+
+// 元组变体和结构体变体先序列化内容，结束时再包一层 { [variant]: value }
+pub struct VariantSerializer<'a, S> {
+    env: Env<'a>,
+    variant: &'static str,
+    inner: S,
+}
+
+impl<'a> ser::SerializeTupleVariant for VariantSerializer<'a, SeqSerializer<'a>> {
+    type Ok = JsValue<'a>;
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        ser::SerializeSeq::serialize_element(&mut self.inner, value)
+    }
+
+    fn end(self) -> Result<JsValue<'a>, Error> {
+        let value = ser::SerializeSeq::end(self.inner)?;
+        Ok(wrap_variant(self.env, self.variant, value))
+    }
+}
+
+impl<'a> ser::SerializeStructVariant for VariantSerializer<'a, MapSerializer<'a>> {
+    type Ok = JsValue<'a>;
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        ser::SerializeStruct::serialize_field(&mut self.inner, key, value)
+    }
+
+    fn end(self) -> Result<JsValue<'a>, Error> {
+        let value = ser::SerializeStruct::end(self.inner)?;
+        Ok(wrap_variant(self.env, self.variant, value))
+    }
+}
@@ -1,14 +1,64 @@
+// 没有开启 demo 时，只有 #[api] 生成的代码会调用的运行时支持（异步任务、流、事件等）没有调用者
+#![cfg_attr(not(any(test, feature = "demo")), allow(dead_code))]
+
+mod async_work;
+mod buffer;
+mod context;
+mod env;
+mod error;
+mod event_emitter;
+mod external;
+mod js_value;
+#[cfg(feature = "serde")]
+mod json;
+mod memory;
+mod meta;
+mod promise;
 mod register;
+mod runtime;
+mod stream;
+mod threadsafe;
+mod unwind;
 mod value;
 
+pub use async_work::CancellationToken;
+pub use buffer::Buffer;
+pub use context::CallContext;
+pub use env::Env;
+pub use error::{Error, ErrorKind};
+pub use event_emitter::EventEmitter;
+pub use external::{External, ExternalRef};
+pub use js_value::{
+    JsArray, JsBigInt, JsBoolean, JsBuffer, JsDate, JsExternal, JsFunction, JsNumber, JsObject,
+    JsString, JsSymbol, JsValue, ValueType,
+};
+#[cfg(feature = "serde")]
+pub use json::{Deserializer, Json, Serializer};
+pub use memory::MemoryTracker;
+pub use meta::{ExportKind, FnMeta, ParamMeta};
+pub use register::{register_fn, register_value, registry, Registration};
+#[cfg(feature = "tokio")]
+pub use runtime::TokioExecutor;
+pub use runtime::{set_executor, BoxFuture, Executor, ThreadPool};
+// 返回 impl Stream<Item = T> 的 #[api] 函数需要的 trait
+pub use futures_core::Stream;
+
 use backend::api;
 
+/// 返回两个数的和
 #[api]
 pub fn add(left: f64, right: f64) -> f64 {
     left + right
 }
 
+/// 返回 left 减去 right 的差
 #[api]
 pub fn minus(left: f64, right: f64) -> f64 {
     left - right
 }
+
+// 演示和测试各个特性的导出，不属于模块本身的 API，只在测试或者开启 demo feature 时编译。
+// 第一个展开的 #[api] 会生成模块的注册入口 napi_register_module_v1，所以要放在 add 和 minus 之后，
+// 保证入口留在 crate 的根模块中
+#[cfg(any(test, feature = "demo"))]
+mod demo;
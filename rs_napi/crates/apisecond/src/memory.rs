@@ -0,0 +1,56 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::env::Env;
+
+// 插件当前报告给 V8 的外部内存字节数，以及还没有被回收的 External/Buffer 个数。
+// 计数是整个进程共享的，而 V8 的统计是按 isolate（主线程、每个 worker）分开的
+static EXTERNAL_BYTES: AtomicUsize = AtomicUsize::new(0);
+static EXTERNAL_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+// V8 的 GC 只知道 JS 堆的大小，看不到 Rust 侧分配的内存。一个很小的 JS 对象背后可能持有
+// 很大的原生内存，如果不报告，V8 不会觉得有必要回收它。External 和 Buffer 在创建和回收时
+// 会自动报告，其他长期持有的原生内存可以通过 adjust 手动报告
+pub struct MemoryTracker;
+
+impl MemoryTracker {
+    // 插件当前持有并已经报告给 V8 的字节数
+    pub fn external_bytes() -> usize {
+        EXTERNAL_BYTES.load(Ordering::Relaxed)
+    }
+
+    // 还没有被 GC 回收的 External 和 Buffer 的个数
+    pub fn external_count() -> usize {
+        EXTERNAL_COUNT.load(Ordering::Relaxed)
+    }
+
+    // 报告新分配（change 为正）或者已经释放（change 为负）的原生内存，
+    // 返回 V8 记录的当前 isolate 的外部内存总量（也包括其他插件报告的部分）
+    pub fn adjust(env: Env<'_>, change: i64) -> i64 {
+        let bytes = change.unsigned_abs() as usize;
+        if change < 0 {
+            // 手动报告的释放量可能超过之前报告的分配量，计数不能因此回绕
+            let _ = EXTERNAL_BYTES.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |current| {
+                Some(current.saturating_sub(bytes))
+            });
+        } else {
+            EXTERNAL_BYTES.fetch_add(bytes, Ordering::Relaxed);
+        }
+        let mut adjusted = 0;
+        unsafe {
+            sys::napi_adjust_external_memory(env.raw(), change, &mut adjusted);
+        };
+        adjusted
+    }
+}
+
+// External 或 Buffer 创建时调用
+pub(crate) fn track_alloc(env: Env<'_>, bytes: usize) {
+    EXTERNAL_COUNT.fetch_add(1, Ordering::Relaxed);
+    MemoryTracker::adjust(env, bytes as i64);
+}
+
+// External 或 Buffer 被 GC 回收时在 finalizer 中调用
+pub(crate) fn track_free(env: Env<'_>, bytes: usize) {
+    EXTERNAL_COUNT.fetch_sub(1, Ordering::Relaxed);
+    MemoryTracker::adjust(env, -(bytes as i64));
+}
@@ -0,0 +1,80 @@
+use crate::env::Env;
+use crate::js_value::{JsArray, JsObject};
+
+// 单个参数的描述：参数名、对应的 TypeScript 类型，以及是否可选、是否为剩余参数
+#[derive(Clone, Copy, Debug)]
+pub struct ParamMeta {
+    pub name: &'static str,
+    pub ty: &'static str,
+    pub optional: bool,
+    pub rest: bool,
+}
+
+// 导出的种类，exports.__registry 中的 kind 字段是它的小写形式
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExportKind {
+    // 同步调用的普通函数
+    Function,
+    // #[api(async)] 和 async fn，调用后返回 Promise
+    Async,
+    // 返回 impl Iterator/impl Stream，调用后返回异步迭代器
+    Iterator,
+    // #[api] const/static 导出的值
+    Value,
+}
+
+impl ExportKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ExportKind::Function => "function",
+            ExportKind::Async => "async",
+            ExportKind::Iterator => "iterator",
+            ExportKind::Value => "value",
+        }
+    }
+}
+
+// #[api] 宏为每个导出函数生成的描述信息，会以 exports.__meta 的形式暴露给 JS
+#[derive(Clone, Copy, Debug)]
+pub struct FnMeta {
+    pub kind: ExportKind,
+    pub doc: &'static str,
+    pub params: &'static [ParamMeta],
+    pub ret: &'static str,
+}
+
+// 生成形如 { add: { doc, params: [{ name, type, optional, rest }], returns } } 的对象
+pub(crate) fn create_meta<'a>(env: Env<'a>, fns: &[(&'static str, FnMeta)]) -> JsObject<'a> {
+    let meta = env.create_object();
+    fns.iter().for_each(|(name, fn_meta)| {
+        let desc = env.create_object();
+        desc.set_named_property("doc", env.create_string(fn_meta.doc));
+        desc.set_named_property("params", create_params(env, fn_meta.params));
+        desc.set_named_property("returns", env.create_string(fn_meta.ret));
+        meta.set_named_property(name, desc);
+    });
+    meta
+}
+
+impl FnMeta {
+    // 和 JS 中 Function.prototype.length 的规则一致：第一个可选参数或剩余参数之前的参数个数
+    pub fn arity(&self) -> usize {
+        self.params
+            .iter()
+            .take_while(|param| !param.optional && !param.rest)
+            .count()
+    }
+}
+
+fn create_params<'a>(env: Env<'a>, params: &[ParamMeta]) -> JsArray<'a> {
+    let array = env.create_array(params.len());
+    params.iter().enumerate().for_each(|(index, param)| {
+        let desc = env.create_object();
+        desc.set_named_property("name", env.create_string(param.name));
+        desc.set_named_property("type", env.create_string(param.ty));
+        desc.set_named_property("optional", env.get_boolean(param.optional));
+        desc.set_named_property("rest", env.get_boolean(param.rest));
+        array.set(index as u32, desc);
+    });
+    array
+}
@@ -0,0 +1,47 @@
+use std::ptr;
+use sys::napi_deferred;
+
+use crate::env::Env;
+use crate::error::Error;
+use crate::js_value::{JsObject, JsValue};
+use crate::unwind;
+use crate::value::NapiValue;
+
+// 创建一个待定的 Promise，deferred 必须且只能被 resolve/reject/settle 使用一次，之后就会被释放
+pub(crate) fn create_promise(env: Env<'_>) -> (napi_deferred, JsObject<'_>) {
+    let mut deferred = ptr::null_mut();
+    let mut promise = ptr::null_mut();
+    unsafe {
+        sys::napi_create_promise(env.raw(), &mut deferred, &mut promise);
+        (
+            deferred,
+            JsObject::from_unchecked(JsValue::from_raw(env, promise)),
+        )
+    }
+}
+
+pub(crate) fn resolve(env: Env<'_>, deferred: napi_deferred, value: JsValue<'_>) {
+    unsafe {
+        sys::napi_resolve_deferred(env.raw(), deferred, value.raw());
+    };
+}
+
+pub(crate) fn reject(env: Env<'_>, deferred: napi_deferred, error: JsValue<'_>) {
+    unsafe {
+        sys::napi_reject_deferred(env.raw(), deferred, error.raw());
+    };
+}
+
+// 在 JS 主线程上把异步计算的结果转换成 JS 值，兑现或者拒绝 Promise
+pub(crate) fn settle<'a, T: NapiValue<'a>>(
+    env: Env<'a>,
+    deferred: napi_deferred,
+    result: Result<T, Error>,
+) {
+    match result.and_then(|value| unwind::catch_panic(|| value.into_js_value(env))) {
+        // Result<T, E> 的 Err 在转换时会作为异常留在 env 中
+        Ok(_) if env.is_exception_pending() => reject(env, deferred, env.take_exception()),
+        Ok(value) => resolve(env, deferred, value),
+        Err(err) => reject(env, deferred, err.into_js_error(env).into()),
+    }
+}
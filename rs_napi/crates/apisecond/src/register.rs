@@ -1,33 +1,163 @@
+use crate::env::Env;
+use crate::js_value::{JsArray, JsObject, JsValue};
+use crate::meta::{self, ExportKind, FnMeta};
 use once_cell::sync::Lazy;
+use std::ptr;
 use std::sync::RwLock;
-use sys::{napi_callback, napi_env, napi_value};
+use sys::{napi_callback, napi_property_attributes};
 
 // Lazy：来自 once_cell crate，用于延迟初始化静态变量。Lazy 确保 REGISTER_FN 在首次访问时才会被初始化，并且初始化的结果会被缓存起来，后续访问直接使用缓存的结果。
 // 这行代码定义了一个线程安全的、延迟初始化的静态变量，用于存储一组可能在程序的多个地方注册和使用的回调函数
-pub(crate) static REGISTER_FN: Lazy<RwLock<Vec<(&'static str, napi_callback)>>> =
-    Lazy::new(Default::default);
+pub(crate) static REGISTER_FN: Lazy<RwLock<Vec<RegisteredFn>>> = Lazy::new(Default::default);
+
+// 已注册函数的 JS 名称、回调以及描述信息
+type RegisteredFn = (&'static str, napi_callback, FnMeta);
+
+// #[api] const/static 导出的值，保存名称和在模块初始化时创建 JS 值的函数
+pub(crate) static REGISTER_VALUE: Lazy<RwLock<Vec<RegisteredValue>>> = Lazy::new(Default::default);
+
+type RegisteredValue = (&'static str, CreateValue);
+
+// 在给定的 env 中创建 JS 值，创建出的值和 env 属于同一个 handle scope
+type CreateValue = for<'a> fn(Env<'a>) -> JsValue<'a>;
 
 //它接受两个参数：js_name 和 cb。js_name 是一个静态生命周期的字符串切片，表示要注册的 JavaScript 函数名。
 // cb 是一个类型为 napi_callback 的回调函数，这是一个与 Node.js 的原生 API 接口（N-API）相关的类型，用于定义 JavaScript 调用的原生函数。
-pub fn register_fn(js_name: &'static str, cb: napi_callback) {
-    REGISTER_FN.write().unwrap().push((js_name, cb));
+// meta 是宏记录下来的文档注释和参数信息，最终会出现在 exports.__meta 上。
+// 注册以名称为键，同名的再次注册会替换原来的条目而不是追加，所以重复注册（例如宿主重新加载模块）不会产生重复的导出。
+// 同名但回调不同说明两个导出使用了同一个 JS 名称，后注册的会悄悄覆盖先注册的，所以直接 panic
+pub fn register_fn(js_name: &'static str, cb: napi_callback, meta: FnMeta) {
+    let registered = upsert(
+        &mut REGISTER_FN.write().unwrap(),
+        (js_name, cb, meta),
+        |entry| entry.0,
+        |existing, entry| existing.1.map(|cb| cb as usize) == entry.1.map(|cb| cb as usize),
+    );
+    // 写锁已经释放，panic 不会让注册表中毒
+    assert!(
+        registered,
+        "`{}` is already registered with a different callback",
+        js_name
+    );
+}
+
+// 注册一个导出的常量，create 在每次初始化模块时为当前 env 创建对应的 JS 值
+pub fn register_value(js_name: &'static str, create: CreateValue) {
+    let registered = upsert(
+        &mut REGISTER_VALUE.write().unwrap(),
+        (js_name, create),
+        |entry| entry.0,
+        |existing, entry| existing.1 as usize == entry.1 as usize,
+    );
+    assert!(
+        registered,
+        "`{}` is already registered with a different value",
+        js_name
+    );
+}
+
+// 已有同名条目时原地替换，保持第一次注册时的顺序。同名条目不是同一个导出（same 返回 false）时
+// 不修改注册表，返回 false
+fn upsert<T>(
+    entries: &mut Vec<T>,
+    entry: T,
+    name: impl Fn(&T) -> &'static str,
+    same: impl Fn(&T, &T) -> bool,
+) -> bool {
+    match entries
+        .iter()
+        .position(|existing| name(existing) == name(&entry))
+    {
+        Some(index) if !same(&entries[index], &entry) => false,
+        Some(index) => {
+            entries[index] = entry;
+            true
+        }
+        None => {
+            entries.push(entry);
+            true
+        }
+    }
+}
+
+// 注册表中的一项导出
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Registration {
+    pub name: &'static str,
+    pub kind: ExportKind,
+    // JS 中函数的 length，导出的值为 0
+    pub arity: usize,
+}
+
+// 当前注册的所有导出，按名称排序，和 exports.__registry 的内容一致
+pub fn registry() -> Vec<Registration> {
+    let fns = REGISTER_FN.read().unwrap();
+    let values = REGISTER_VALUE.read().unwrap();
+    let mut registry = fns
+        .iter()
+        .map(|(name, _, fn_meta)| Registration {
+            name,
+            kind: fn_meta.kind,
+            arity: fn_meta.arity(),
+        })
+        .chain(values.iter().map(|(name, _)| Registration {
+            name,
+            kind: ExportKind::Value,
+            arity: 0,
+        }))
+        .collect::<Vec<_>>();
+    registry.sort_by_key(|registration| registration.name);
+    registry
 }
 
 // 其目的是在Node.js的N-API环境中注册一系列的函数。
 // 这个过程涉及到几个关键步骤，包括获取全局函数注册表、创建N-API函数，并将这些函数绑定到一个导出对象上。
-pub fn gen_fn(env: napi_env, exports: napi_value) {
-    let register = REGISTER_FN.write().unwrap();
-    register.iter().for_each(|(name, cb)| {
-        // let mut fn_ptr = std::ptr::null_mut();初始化一个空指针，它将用于存储N-API创建的函数对象的引用。
-        let mut fn_ptr = std::ptr::null_mut();
-        unsafe {
-            //首先通过format!("{}\0", *name).as_ptr().cast();创建一个以null终止的字符串，这是C语言字符串的要求。这个字符串用作N-API函数的名称。cast()方法将字符串指针转换为适当的类型。
-            let n = format!("{}\0", *name).as_ptr().cast();
-            // 调用创建一个新的N-API函数
-            // 创建的函数对象引用存储在fn_ptr中。
-            sys::napi_create_function(env, n, name.len(), *cb, std::ptr::null_mut(), &mut fn_ptr);
-            // 新创建的函数对象作为一个命名属性添加到exports对象上。这样，当模块被导入到Node.js环境时，这些函数就会作为模块的导出可用。
-            sys::napi_set_named_property(env, exports, n, fn_ptr);
-        };
-    })
+pub fn gen_fn<'a>(env: Env<'a>, exports: JsObject<'a>) {
+    let register = REGISTER_FN.read().unwrap();
+    register.iter().for_each(|(name, cb, _)| {
+        // 创建一个新的N-API函数，并作为一个命名属性添加到exports对象上。这样，当模块被导入到Node.js环境时，这些函数就会作为模块的导出可用。
+        exports.set_named_property(name, env.create_function(name, *cb, ptr::null_mut()));
+    });
+
+    define_values(env, exports);
+
+    // 把所有函数的描述信息挂到 exports.__meta 上，供 IDE 工具和 API 浏览器读取
+    let fns = register
+        .iter()
+        .map(|(name, _, fn_meta)| (*name, *fn_meta))
+        .collect::<Vec<_>>();
+    exports.set_named_property("__meta", meta::create_meta(env, &fns));
+    drop(register);
+
+    // exports.__registry 是 [{ name, kind, arity }] 形式的数组，测试和热重载工具可以据此检查导出了哪些内容
+    exports.set_named_property("__registry", create_registry(env, &registry()));
+}
+
+fn create_registry<'a>(env: Env<'a>, registry: &[Registration]) -> JsArray<'a> {
+    let array = env.create_array(registry.len());
+    registry
+        .iter()
+        .enumerate()
+        .for_each(|(index, registration)| {
+            let desc = env.create_object();
+            desc.set_named_property("name", env.create_string(registration.name));
+            desc.set_named_property("kind", env.create_string(registration.kind.as_str()));
+            desc.set_named_property("arity", env.create_double(registration.arity as f64));
+            array.set(index as u32, desc);
+        });
+    array
+}
+
+// 常量通过 napi_define_properties 定义成只读（不可写、不可配置）但可枚举的属性，
+// JS 中对它们赋值不会生效，严格模式下会抛出 TypeError
+fn define_values<'a>(env: Env<'a>, exports: JsObject<'a>) {
+    let values = REGISTER_VALUE.read().unwrap();
+    if values.is_empty() {
+        return;
+    }
+    let properties = values
+        .iter()
+        .map(|(name, create)| (*name, create(env)))
+        .collect::<Vec<_>>();
+    exports.define_properties(&properties, napi_property_attributes::napi_enumerable);
 }
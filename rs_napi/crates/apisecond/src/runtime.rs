@@ -0,0 +1,187 @@
+use once_cell::sync::OnceCell;
+use std::future::{self, Future};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Wake, Waker};
+use std::thread;
+
+use crate::env::Env;
+use crate::error::Error;
+use crate::js_value::JsObject;
+use crate::promise;
+use crate::threadsafe::ThreadsafeFunction;
+use crate::unwind;
+use crate::value::NapiValue;
+
+pub type BoxFuture = Pin<Box<dyn Future<Output = ()> + Send + 'static>>;
+
+// 驱动 async fn 形式的 #[api] 函数返回的 Future。Future 在执行器的线程上运行，
+// 完成后通过 threadsafe function 回到 JS 主线程兑现 Promise
+pub trait Executor: Send + Sync + 'static {
+    fn spawn(&self, future: BoxFuture);
+}
+
+static EXECUTOR: OnceCell<Box<dyn Executor>> = OnceCell::new();
+
+// 替换默认的执行器。只能设置一次，并且要在第一次调用 async 函数之前设置，
+// 例如在 #[ctor::ctor] 函数中；之后再设置会返回错误
+pub fn set_executor(executor: impl Executor) -> Result<(), Error> {
+    EXECUTOR
+        .set(Box::new(executor))
+        .map_err(|_| Error::from_reason("the executor has already been set or used"))
+}
+
+pub(crate) fn executor() -> &'static dyn Executor {
+    EXECUTOR.get_or_init(default_executor).as_ref()
+}
+
+// 开启 tokio feature 时默认使用 tokio 的多线程运行时，否则使用内置的线程池
+#[cfg(feature = "tokio")]
+fn default_executor() -> Box<dyn Executor> {
+    Box::new(TokioExecutor::new())
+}
+
+#[cfg(not(feature = "tokio"))]
+fn default_executor() -> Box<dyn Executor> {
+    let threads = thread::available_parallelism().map_or(4, |n| n.get());
+    Box::new(ThreadPool::new(threads))
+}
+
+// 在执行器上运行 future，返回一个在 future 完成后兑现的 Promise。
+// future 中的 panic 会变成 Promise 的拒绝原因，而不是让执行器的线程退出
+pub(crate) fn spawn_future<'a, T, F>(env: Env<'a>, future: F) -> JsObject<'a>
+where
+    T: for<'s> NapiValue<'s> + Send + 'static,
+    F: Future<Output = T> + Send + 'static,
+{
+    let (deferred, promise) = promise::create_promise(env);
+    let tsfn = ThreadsafeFunction::new(env, "apisecond:future", move |env, result| {
+        promise::settle(env, deferred, result)
+    });
+    let mut future = Box::pin(future);
+    executor().spawn(Box::pin(async move {
+        let result = future::poll_fn(
+            |cx| match unwind::catch_panic(|| future.as_mut().poll(cx)) {
+                Ok(Poll::Ready(value)) => Poll::Ready(Ok(value)),
+                Ok(Poll::Pending) => Poll::Pending,
+                Err(err) => Poll::Ready(Err(err)),
+            },
+        )
+        .await;
+        tsfn.call(result);
+    }));
+    promise
+}
+
+// 内置的执行器：固定数量的工作线程从同一个队列中取出任务并 poll，
+// 任务被唤醒时重新放回队列。适合 CPU 密集或者只依赖标准库的 Future，
+// 需要 tokio 的 IO、定时器等功能时请开启 tokio feature
+pub struct ThreadPool {
+    sender: Sender<Arc<Task>>,
+}
+
+struct Task {
+    // 已经完成的任务是 None；同一时间只有一个线程能 poll 它
+    future: Mutex<Option<BoxFuture>>,
+    // 是否已经在队列中，避免被多次唤醒时重复入队
+    queued: AtomicBool,
+    sender: Sender<Arc<Task>>,
+}
+
+impl ThreadPool {
+    pub fn new(threads: usize) -> Self {
+        let (sender, receiver) = mpsc::channel::<Arc<Task>>();
+        let receiver = Arc::new(Mutex::new(receiver));
+        (0..threads.max(1)).for_each(|index| {
+            let receiver = receiver.clone();
+            thread::Builder::new()
+                .name(format!("apisecond-worker-{}", index))
+                .spawn(move || work(&receiver))
+                .expect("failed to spawn executor thread");
+        });
+        ThreadPool { sender }
+    }
+}
+
+impl Executor for ThreadPool {
+    fn spawn(&self, future: BoxFuture) {
+        let task = Arc::new(Task {
+            future: Mutex::new(Some(future)),
+            queued: AtomicBool::new(true),
+            sender: self.sender.clone(),
+        });
+        let _ = self.sender.send(task);
+    }
+}
+
+impl Wake for Task {
+    fn wake(self: Arc<Self>) {
+        if !self.queued.swap(true, Ordering::AcqRel) {
+            let _ = self.sender.clone().send(self);
+        }
+    }
+}
+
+fn work(receiver: &Mutex<Receiver<Arc<Task>>>) {
+    loop {
+        // 等待和取出任务期间持有锁，空闲的线程在锁上排队；
+        // 锁在这条语句结束时释放，poll 期间其他线程可以继续取任务
+        let next = receiver.lock().unwrap().recv();
+        let task = match next {
+            Ok(task) => task,
+            Err(_) => return,
+        };
+        // 先清除标记再 poll，poll 过程中的唤醒会让任务重新入队
+        task.queued.store(false, Ordering::Release);
+        let waker = Waker::from(task.clone());
+        let mut future = task.future.lock().unwrap();
+        if let Some(fut) = future.as_mut() {
+            if fut
+                .as_mut()
+                .poll(&mut Context::from_waker(&waker))
+                .is_ready()
+            {
+                *future = None;
+            }
+        }
+    }
+}
+
+// 在 tokio 的多线程运行时上执行 Future
+#[cfg(feature = "tokio")]
+pub struct TokioExecutor {
+    runtime: tokio::runtime::Runtime,
+}
+
+#[cfg(feature = "tokio")]
+impl TokioExecutor {
+    pub fn new() -> Self {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .thread_name("apisecond-tokio")
+            .build()
+            .expect("failed to build the tokio runtime");
+        TokioExecutor { runtime }
+    }
+
+    // 使用自定义的运行时，例如调整了工作线程数
+    pub fn with_runtime(runtime: tokio::runtime::Runtime) -> Self {
+        TokioExecutor { runtime }
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl Default for TokioExecutor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl Executor for TokioExecutor {
+    fn spawn(&self, future: BoxFuture) {
+        self.runtime.spawn(future);
+    }
+}
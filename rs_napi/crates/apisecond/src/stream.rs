@@ -0,0 +1,286 @@
+use futures_core::Stream;
+use std::cell::{Cell, RefCell};
+use std::collections::VecDeque;
+use std::future;
+use std::os::raw::c_void;
+use std::pin::Pin;
+use std::ptr;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+use sys::{napi_callback_info, napi_deferred, napi_env, napi_threadsafe_function, napi_value};
+
+use crate::context::CallContext;
+use crate::env::Env;
+use crate::error::Error;
+use crate::js_value::{JsFunction, JsObject, JsValue};
+use crate::promise;
+use crate::runtime;
+use crate::threadsafe::{self, ThreadsafeFunction};
+use crate::unwind;
+use crate::value::NapiValue;
+
+// 返回 impl Iterator 的 #[api] 函数，结果以异步迭代器的形式交给 JS。
+// 迭代器在执行器的线程上运行，不会阻塞 JS 主线程
+pub(crate) fn from_iter<'a, T, I>(env: Env<'a>, iter: I) -> JsObject<'a>
+where
+    T: for<'s> NapiValue<'s> + Send + 'static,
+    I: Iterator<Item = T> + Send + 'static,
+{
+    from_stream(env, IterStream(Box::new(iter)))
+}
+
+// 返回 impl Stream 的 #[api] 函数，结果是一个实现了 Symbol.asyncIterator 的对象，
+// JS 可以用 for await...of 读取。只有 JS 调用 next() 时才会去 poll 下一个元素，
+// 所以 Rust 侧最多比 JS 多产生 JS 已经请求了的那些元素（背压）
+pub(crate) fn from_stream<'a, T, S>(env: Env<'a>, stream: S) -> JsObject<'a>
+where
+    T: for<'s> NapiValue<'s> + Send + 'static,
+    S: Stream<Item = T> + Send + 'static,
+{
+    let demand = Arc::new(Demand::default());
+    let shared = Rc::new(Shared {
+        pending: RefCell::new(VecDeque::new()),
+        done: Cell::new(false),
+        tsfn: Cell::new(ptr::null_mut()),
+        demand: demand.clone(),
+    });
+    let receiver = shared.clone();
+    let tsfn = ThreadsafeFunction::new(env, "apisecond:stream", move |env, item| {
+        receiver.deliver::<T>(env, item)
+    });
+    shared.tsfn.set(tsfn.raw());
+    // 没有待处理的 next() 时不阻止进程退出
+    threadsafe::set_referenced(env, tsfn.raw(), false);
+
+    runtime::executor().spawn(Box::pin(produce(stream, demand.clone(), tsfn)));
+
+    let state = Rc::new(IterState { shared });
+    let iterator = env.create_object();
+    iterator.set_named_property("next", create_method(env, "next", next, &state));
+    iterator.set_named_property("return", create_method(env, "return", close, &state));
+    let symbol = JsObject::from_js_value(env.get_global().get_named_property("Symbol"));
+    iterator.set_property(
+        symbol.get_named_property("asyncIterator"),
+        env.create_function("[Symbol.asyncIterator]", Some(this), ptr::null_mut()),
+    );
+    iterator
+}
+
+// 在执行器上运行：每收到一个 next() 请求就 poll 出一个元素，通过 threadsafe function 发回 JS 主线程
+async fn produce<T, S>(
+    stream: S,
+    demand: Arc<Demand>,
+    tsfn: ThreadsafeFunction<Result<Option<T>, Error>>,
+) where
+    T: Send + 'static,
+    S: Stream<Item = T>,
+{
+    let mut stream = Box::pin(stream);
+    while future::poll_fn(|cx| demand.poll_take(cx)).await {
+        let item =
+            future::poll_fn(
+                |cx| match unwind::catch_panic(|| stream.as_mut().poll_next(cx)) {
+                    Ok(Poll::Ready(item)) => Poll::Ready(Ok(item)),
+                    Ok(Poll::Pending) => Poll::Pending,
+                    Err(err) => Poll::Ready(Err(err)),
+                },
+            )
+            .await;
+        // 流结束或者 panic 之后不再继续 poll
+        let end = !matches!(item, Ok(Some(_)));
+        tsfn.call(item);
+        if end {
+            break;
+        }
+    }
+}
+
+// JS 请求的、还没有被满足的元素个数，由 next() 增加，生产者每 poll 一个元素减少一个
+#[derive(Default)]
+struct Demand(Mutex<DemandState>);
+
+#[derive(Default)]
+struct DemandState {
+    requested: usize,
+    // return() 被调用或者迭代器被 GC 回收，生产者应该退出
+    closed: bool,
+    waker: Option<Waker>,
+}
+
+impl Demand {
+    fn request(&self) {
+        let mut state = self.0.lock().unwrap();
+        state.requested += 1;
+        if let Some(waker) = state.waker.take() {
+            waker.wake();
+        }
+    }
+
+    fn close(&self) {
+        let mut state = self.0.lock().unwrap();
+        state.closed = true;
+        if let Some(waker) = state.waker.take() {
+            waker.wake();
+        }
+    }
+
+    // 等到有新的请求时返回 true，已经关闭时返回 false
+    fn poll_take(&self, cx: &mut Context<'_>) -> Poll<bool> {
+        let mut state = self.0.lock().unwrap();
+        if state.closed {
+            Poll::Ready(false)
+        } else if state.requested > 0 {
+            state.requested -= 1;
+            Poll::Ready(true)
+        } else {
+            state.waker = Some(cx.waker().clone());
+            Poll::Pending
+        }
+    }
+}
+
+// 只在 JS 主线程上访问的状态：next() 返回的、按顺序等待兑现的 Promise
+struct Shared {
+    pending: RefCell<VecDeque<napi_deferred>>,
+    done: Cell<bool>,
+    tsfn: Cell<napi_threadsafe_function>,
+    // 和生产者共享的请求计数
+    demand: Arc<Demand>,
+}
+
+impl Shared {
+    // 生产者发来的元素总是按 next() 被调用的顺序兑现。和 JS 的 async generator 一样，
+    // 元素是 Err 或者生产者 panic 时拒绝对应的 next()，迭代随之结束
+    fn deliver<'a, T: NapiValue<'a>>(&self, env: Env<'a>, item: Result<Option<T>, Error>) {
+        // return() 已经兑现了所有的请求，之后到达的元素直接丢弃
+        let Some(deferred) = self.pending.borrow_mut().pop_front() else {
+            return;
+        };
+        let item = item
+            .and_then(|item| unwind::catch_panic(|| item.map(|value| value.into_js_value(env))));
+        let end = match item {
+            // Result<T, E> 元素的 Err 在转换时会作为异常留在 env 中
+            Ok(_) if env.is_exception_pending() => {
+                promise::reject(env, deferred, env.take_exception());
+                true
+            }
+            Ok(value) => {
+                let end = value.is_none();
+                promise::resolve(env, deferred, iter_result(env, value));
+                end
+            }
+            Err(err) => {
+                promise::reject(env, deferred, err.into_js_error(env).into());
+                true
+            }
+        };
+        if end {
+            self.finish(env);
+        }
+        if self.pending.borrow().is_empty() {
+            threadsafe::set_referenced(env, self.tsfn.get(), false);
+        }
+    }
+
+    // 迭代结束，之后所有的 next() 都立即以 { done: true } 兑现，生产者不再 poll
+    fn finish(&self, env: Env<'_>) {
+        self.done.set(true);
+        self.demand.close();
+        let pending = self.pending.take();
+        pending
+            .into_iter()
+            .for_each(|deferred| promise::resolve(env, deferred, iter_result(env, None)));
+    }
+}
+
+// next/return 方法绑定的数据，任何一个方法还被 JS 引用时都不会释放
+struct IterState {
+    shared: Rc<Shared>,
+}
+
+impl Drop for IterState {
+    // 迭代器被 GC 回收，通知生产者退出并释放流
+    fn drop(&mut self) {
+        self.shared.demand.close();
+    }
+}
+
+fn create_method<'a>(
+    env: Env<'a>,
+    name: &str,
+    cb: unsafe extern "C" fn(napi_env, napi_callback_info) -> napi_value,
+    state: &Rc<IterState>,
+) -> JsFunction<'a> {
+    let data = Box::into_raw(Box::new(state.clone()));
+    let method = env.create_function(name, Some(cb), data.cast());
+    env.add_finalizer(method.into(), data.cast(), Some(drop_state));
+    method
+}
+
+unsafe extern "C" fn drop_state(_env: napi_env, data: *mut c_void, _hint: *mut c_void) {
+    drop(Box::from_raw(data.cast::<Rc<IterState>>()));
+}
+
+// { value, done }，value 为 None 表示迭代已经结束
+fn iter_result<'a>(env: Env<'a>, value: Option<JsValue<'a>>) -> JsValue<'a> {
+    let result = env.create_object();
+    result.set_named_property("done", env.get_boolean(value.is_none()));
+    result.set_named_property("value", value.unwrap_or_else(|| env.get_undefined()));
+    result.into()
+}
+
+unsafe extern "C" fn next(env: napi_env, info: napi_callback_info) -> napi_value {
+    unwind::catch(env, |env| unsafe {
+        let ctx = CallContext::new(env, info, 0);
+        let state = &*ctx.data().cast::<Rc<IterState>>();
+        let shared = &state.shared;
+        let (deferred, promise) = promise::create_promise(env);
+        if shared.done.get() {
+            promise::resolve(env, deferred, iter_result(env, None));
+        } else {
+            if shared.pending.borrow().is_empty() {
+                threadsafe::set_referenced(env, shared.tsfn.get(), true);
+            }
+            shared.pending.borrow_mut().push_back(deferred);
+            shared.demand.request();
+        }
+        promise.into()
+    })
+}
+
+// for await...of 中途 break 或者抛出异常时调用，停止生产并释放流
+unsafe extern "C" fn close(env: napi_env, info: napi_callback_info) -> napi_value {
+    unwind::catch(env, |env| unsafe {
+        let ctx = CallContext::new(env, info, 1);
+        let state = &*ctx.data().cast::<Rc<IterState>>();
+        let shared = &state.shared;
+        if !shared.done.get() {
+            if !shared.pending.borrow().is_empty() {
+                threadsafe::set_referenced(env, shared.tsfn.get(), false);
+            }
+            shared.finish(env);
+        }
+        let (deferred, promise) = promise::create_promise(env);
+        let result = JsObject::from_unchecked(iter_result(env, None));
+        result.set_named_property("value", ctx.arg(0));
+        promise::resolve(env, deferred, result.into());
+        promise.into()
+    })
+}
+
+// [Symbol.asyncIterator]() 返回迭代器本身
+unsafe extern "C" fn this(env: napi_env, info: napi_callback_info) -> napi_value {
+    unwind::catch(env, |env| unsafe { CallContext::new(env, info, 0).this() })
+}
+
+// 把 Iterator 包装成每次 poll 都立即就绪的 Stream，装箱之后不需要 I: Unpin
+struct IterStream<I>(Box<I>);
+
+impl<I: Iterator> Stream for IterStream<I> {
+    type Item = I::Item;
+
+    fn poll_next(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Option<I::Item>> {
+        Poll::Ready(self.get_mut().0.next())
+    }
+}
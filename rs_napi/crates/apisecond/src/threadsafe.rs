@@ -0,0 +1,148 @@
+use std::marker::PhantomData;
+use std::os::raw::c_void;
+use std::ptr;
+use std::sync::{Arc, RwLock};
+use sys::{napi_env, napi_threadsafe_function, napi_value};
+
+use crate::env::Env;
+use crate::unwind;
+
+// 在 JS 主线程上处理其他线程发来的值
+type Handler<T> = Box<dyn for<'s> FnMut(Env<'s>, T)>;
+
+// 从任意线程把 T 发送到 JS 主线程，由创建时传入的 handler 处理。
+// 每个克隆都持有一次 acquire，全部 drop 之后底层的 threadsafe function 才会被释放，
+// 在此之前它会让 Node.js 的事件循环保持运行
+pub(crate) struct ThreadsafeFunction<T> {
+    raw: napi_threadsafe_function,
+    // 环境销毁时 Node.js 会直接释放 threadsafe function，不等所有句柄 release，
+    // finalize 中置为 true，之后其他线程上的句柄不能再使用 raw
+    closed: Arc<RwLock<bool>>,
+    _marker: PhantomData<fn(T)>,
+}
+
+// napi_threadsafe_function 本身可以在任意线程上调用，handler 只会在 JS 主线程上执行
+unsafe impl<T: Send> Send for ThreadsafeFunction<T> {}
+unsafe impl<T: Send> Sync for ThreadsafeFunction<T> {}
+
+impl<T: Send + 'static> ThreadsafeFunction<T> {
+    // 只能在 JS 主线程上创建，name 会出现在 async_hooks 等诊断信息中
+    pub(crate) fn new(
+        env: Env<'_>,
+        name: &str,
+        handler: impl for<'s> FnMut(Env<'s>, T) + 'static,
+    ) -> Self {
+        let handler: *mut Handler<T> = Box::into_raw(Box::new(Box::new(handler)));
+        let closed = Arc::new(RwLock::new(false));
+        let name = env.create_string(name);
+        let mut raw = ptr::null_mut();
+        unsafe {
+            // 队列长度不设上限，call 永远不会因为队列已满而阻塞或失败
+            sys::napi_create_threadsafe_function(
+                env.raw(),
+                ptr::null_mut(),
+                ptr::null_mut(),
+                name.raw(),
+                0,
+                1,
+                Arc::into_raw(closed.clone()).cast_mut().cast(),
+                Some(finalize::<T>),
+                handler.cast(),
+                Some(call_js::<T>),
+                &mut raw,
+            );
+        };
+        ThreadsafeFunction {
+            raw,
+            closed,
+            _marker: PhantomData,
+        }
+    }
+
+    // 把 value 放进队列，返回 false 表示 threadsafe function 已经关闭（例如环境正在销毁），
+    // 此时 value 会被直接丢弃
+    pub(crate) fn call(&self, value: T) -> bool {
+        // 持有读锁直到调用结束，finalize 不会在调用过程中释放 raw
+        let closed = self.closed.read().unwrap();
+        if *closed {
+            return false;
+        }
+        let data = Box::into_raw(Box::new(value));
+        let status = unsafe {
+            sys::napi_call_threadsafe_function(self.raw, data.cast(), sys::napi_tsfn_nonblocking)
+        };
+        if status != sys::napi_ok {
+            drop(unsafe { Box::from_raw(data) });
+        }
+        status == sys::napi_ok
+    }
+
+    pub(crate) fn raw(&self) -> napi_threadsafe_function {
+        self.raw
+    }
+}
+
+// 是否让 raw 阻止事件循环退出，只能在 JS 主线程上调用，并且 raw 至少还有一个句柄没有 drop。
+// 没有待处理的请求时取消引用，避免一个不再使用、但还没被 GC 回收的句柄让进程无法退出
+pub(crate) fn set_referenced(env: Env<'_>, raw: napi_threadsafe_function, referenced: bool) {
+    unsafe {
+        if referenced {
+            sys::napi_ref_threadsafe_function(env.raw(), raw);
+        } else {
+            sys::napi_unref_threadsafe_function(env.raw(), raw);
+        }
+    };
+}
+
+impl<T> Clone for ThreadsafeFunction<T> {
+    fn clone(&self) -> Self {
+        let closed = self.closed.read().unwrap();
+        if !*closed {
+            unsafe {
+                sys::napi_acquire_threadsafe_function(self.raw);
+            };
+        }
+        ThreadsafeFunction {
+            raw: self.raw,
+            closed: self.closed.clone(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T> Drop for ThreadsafeFunction<T> {
+    fn drop(&mut self) {
+        let closed = self.closed.read().unwrap();
+        if !*closed {
+            unsafe {
+                sys::napi_release_threadsafe_function(self.raw, sys::napi_tsfn_release);
+            };
+        }
+    }
+}
+
+unsafe extern "C" fn call_js<T>(
+    env: napi_env,
+    _js_callback: napi_value,
+    context: *mut c_void,
+    data: *mut c_void,
+) {
+    let value = Box::from_raw(data.cast::<T>());
+    // 环境正在销毁，只需要释放数据
+    if env.is_null() {
+        return;
+    }
+    let env = Env::from_raw(env);
+    let handler = &mut *context.cast::<Handler<T>>();
+    // 这里不在任何 JS 调用中，handler 中的 panic 作为未捕获的异常交给 Node.js 处理
+    if let Err(err) = unwind::catch_panic(|| handler(env, *value)) {
+        err.throw(env);
+    }
+}
+
+// data 是 closed 标记，hint 是 handler
+unsafe extern "C" fn finalize<T>(_env: napi_env, data: *mut c_void, hint: *mut c_void) {
+    let closed = Arc::from_raw(data.cast_const().cast::<RwLock<bool>>());
+    *closed.write().unwrap() = true;
+    drop(Box::from_raw(hint.cast::<Handler<T>>()));
+}
@@ -0,0 +1,84 @@
+use std::any::Any;
+use std::cell::RefCell;
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+use std::sync::Once;
+use sys::{napi_env, napi_value};
+
+use crate::env::Env;
+use crate::error::Error;
+use crate::js_value::JsValue;
+
+thread_local! {
+    // panic hook 记录下来的最近一次 panic 的位置，payload 本身并不包含位置信息
+    static PANIC_LOCATION: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+static HOOK: Once = Once::new();
+
+// 安装一个记录 panic 位置的 hook，原有的 hook 仍然会被调用（默认会把 panic 信息打印到 stderr）
+fn install_hook() {
+    HOOK.call_once(|| {
+        let prev = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            let location = info
+                .location()
+                .map(|l| format!("{}:{}:{}", l.file(), l.line(), l.column()));
+            PANIC_LOCATION.with(|cell| *cell.borrow_mut() = location);
+            prev(info);
+        }));
+    });
+}
+
+// 在 extern "C" 函数中执行 f，panic 不能跨过 FFI 边界展开到 JS 引擎中（这是未定义行为），
+// 所以在这里捕获 panic，并把它转换成一个抛给 JS 的 Error。
+// f 拿到的 Env 只在这次调用中有效，返回的 JS 值也属于同一个 scope
+pub(crate) fn catch(env: napi_env, f: impl for<'s> FnOnce(Env<'s>) -> JsValue<'s>) -> napi_value {
+    let env = unsafe { Env::from_raw(env) };
+    match catch_panic(|| f(env).raw()) {
+        Ok(value) => value,
+        Err(err) => {
+            err.throw(env);
+            ptr::null_mut()
+        }
+    }
+}
+
+// 执行 f 并把 panic 转换成 Error：throw_error 主动中止时原样返回携带的错误，
+// 其他 panic 转换成带有位置信息的 Error。可以在任意线程上调用（例如 async work 的线程池）
+pub(crate) fn catch_panic<R>(f: impl FnOnce() -> R) -> Result<R, Error> {
+    install_hook();
+    panic::catch_unwind(AssertUnwindSafe(f)).map_err(|payload| {
+        let location = PANIC_LOCATION.with(|cell| cell.borrow_mut().take());
+        match payload.downcast::<Error>() {
+            Ok(err) => *err,
+            Err(payload) => panic_error(payload, location),
+        }
+    })
+}
+
+// 参数转换失败这类无法通过返回值报告的错误，中止当前调用并把 err 抛给 JS。
+// 使用 resume_unwind 不会触发 panic hook，也就不会在 stderr 上打印 panic 信息
+pub(crate) fn throw_error(err: Error) -> ! {
+    panic::resume_unwind(Box::new(err))
+}
+
+fn panic_error(payload: Box<dyn Any + Send>, location: Option<String>) -> Error {
+    let message = payload_message(&*payload);
+    let message = match location {
+        Some(location) => format!("Rust panicked at {}: {}", location, message),
+        None => format!("Rust panicked: {}", message),
+    };
+    Error::from_reason(message)
+}
+
+// panic!("...") 的 payload 通常是 &'static str 或者 String
+fn payload_message(payload: &(dyn Any + Send)) -> &str {
+    if let Some(message) = payload.downcast_ref::<&'static str>() {
+        message
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message
+    } else {
+        "Box<dyn Any>"
+    }
+}
@@ -1,33 +1,193 @@
-use std::ptr;
-use sys::{napi_create_double, napi_env, napi_get_value_double, napi_value};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::env::Env;
+use crate::error::Error;
+use crate::js_value::{JsArray, JsBigInt, JsBoolean, JsDate, JsNumber, JsString, JsValue};
+use crate::unwind;
 
 // NapiValue特质旨在为与N-API交互提供抽象，N-API是一个C语言编写的Node.js API，允许原生模块与JavaScript代码进行交互。
-pub trait NapiValue {
-    // get_value_from_raw：这个方法接受两个参数，env和value，分别代表N-API的环境句柄和N-API值。它返回特质实现者自身的类型。这个方法的目的是从原始的N-API值中提取Rust类型的值。
-    fn get_value_from_raw(env: napi_env, value: napi_value) -> Self;
-    // try_into_raw：这个方法也接受两个参数，env和value，其中value是特质实现者自身的类型。它返回一个N-API值。这个方法的目的是将Rust类型的值转换为N-API值。
-    fn try_into_raw(env: napi_env, value: Self) -> napi_value;
-}
-
-impl NapiValue for f64 {
-    fn get_value_from_raw(env: napi_env, value: napi_value) -> f64 {
-        // 首先定义了一个f64类型的变量res，初始值为0.0。
-        let mut res: f64 = 0.0;
-        // 块调用napi_get_value_double函数，这是N-API提供的一个函数，用于从N-API的值中提取出双精度浮点数（f64）
-        unsafe {
-            napi_get_value_double(env, value, &mut res);
-        };
-        // 即从N-API值中提取出的f64值。
-        res
+// 生命周期 'a 是 JS 值所在的 handle scope，Rust 值转换成的 JS 值不能离开这个 scope
+pub trait NapiValue<'a>: Sized {
+    // from_js_value：从 JS 值中提取 Rust 类型的值，JS 值的类型不匹配时抛出 TypeError。
+    fn from_js_value(value: JsValue<'a>) -> Self;
+    // into_js_value：在 env 中创建与 Rust 值对应的 JS 值。
+    fn into_js_value(self, env: Env<'a>) -> JsValue<'a>;
+}
+
+impl<'a> NapiValue<'a> for f64 {
+    fn from_js_value(value: JsValue<'a>) -> f64 {
+        JsNumber::from_js_value(value).get()
+    }
+
+    fn into_js_value(self, env: Env<'a>) -> JsValue<'a> {
+        env.create_double(self).into()
+    }
+}
+
+impl<'a> NapiValue<'a> for bool {
+    fn from_js_value(value: JsValue<'a>) -> bool {
+        JsBoolean::from_js_value(value).get()
+    }
+
+    fn into_js_value(self, env: Env<'a>) -> JsValue<'a> {
+        env.get_boolean(self).into()
+    }
+}
+
+// undefined 和 null 都对应 None，None 转换回 JS 时是 undefined
+impl<'a, T: NapiValue<'a>> NapiValue<'a> for Option<T> {
+    fn from_js_value(value: JsValue<'a>) -> Option<T> {
+        if value.is_undefined_or_null() {
+            None
+        } else {
+            Some(T::from_js_value(value))
+        }
+    }
+
+    fn into_js_value(self, env: Env<'a>) -> JsValue<'a> {
+        match self {
+            Some(value) => value.into_js_value(env),
+            None => env.get_undefined(),
+        }
+    }
+}
+
+impl<'a> NapiValue<'a> for String {
+    fn from_js_value(value: JsValue<'a>) -> String {
+        JsString::from_js_value(value).into_string()
+    }
+
+    fn into_js_value(self, env: Env<'a>) -> JsValue<'a> {
+        env.create_string(&self).into()
+    }
+}
+
+// JS 数组和 Vec<T> 之间逐个元素转换
+impl<'a, T: NapiValue<'a>> NapiValue<'a> for Vec<T> {
+    fn from_js_value(value: JsValue<'a>) -> Vec<T> {
+        let array = JsArray::from_js_value(value);
+        (0..array.len())
+            .map(|index| T::from_js_value(array.get(index)))
+            .collect()
+    }
+
+    fn into_js_value(self, env: Env<'a>) -> JsValue<'a> {
+        let array = env.create_array(self.len());
+        self.into_iter().enumerate().for_each(|(index, element)| {
+            array.set(index as u32, element.into_js_value(env));
+        });
+        array.into()
+    }
+}
+
+// 元组对应 JS 中定长的数组，例如 (f64, f64) 对应 [number, number]
+macro_rules! impl_tuple {
+    ($($name:ident: $index:tt),+) => {
+        impl<'a, $($name: NapiValue<'a>),+> NapiValue<'a> for ($($name,)+) {
+            fn from_js_value(value: JsValue<'a>) -> Self {
+                let array = JsArray::from_js_value(value);
+                ($($name::from_js_value(array.get($index)),)+)
+            }
+
+            fn into_js_value(self, env: Env<'a>) -> JsValue<'a> {
+                let array = env.create_array([$($index),+].len());
+                $(array.set($index, self.$index.into_js_value(env));)+
+                array.into()
+            }
+        }
+    };
+}
+
+impl_tuple!(A: 0);
+impl_tuple!(A: 0, B: 1);
+impl_tuple!(A: 0, B: 1, C: 2);
+impl_tuple!(A: 0, B: 1, C: 2, D: 3);
+
+// JS Date 对应 SystemTime，两者之间以距离 Unix 纪元的毫秒数换算，毫秒以下的精度会丢失
+impl<'a> NapiValue<'a> for SystemTime {
+    fn from_js_value(value: JsValue<'a>) -> SystemTime {
+        let millis = JsDate::from_js_value(value).value_of();
+        // Invalid Date 的值是 NaN，无法表示为 SystemTime
+        let offset = Duration::try_from_secs_f64(millis.abs() / 1000.0).unwrap_or_else(|_| {
+            unwind::throw_error(Error::range_error("Invalid Date").with_code("ERR_INVALID_DATE"))
+        });
+        if millis < 0.0 {
+            UNIX_EPOCH - offset
+        } else {
+            UNIX_EPOCH + offset
+        }
     }
 
-    fn try_into_raw(env: napi_env, value: f64) -> napi_value {
-        // 首先通过 ptr::null_mut() 创建一个空指针 res，这个指针将用来存储函数 napi_create_double 的结果。
-        let mut res = ptr::null_mut();
-        unsafe {
-            //这个函数调用的目的是将传入的浮点数 value 转换为一个 Node.js 可以理解的 napi_value 类型的值，并将这个值的指针存储在 res 中。
-            napi_create_double(env, value, &mut res);
+    fn into_js_value(self, env: Env<'a>) -> JsValue<'a> {
+        let millis = match self.duration_since(UNIX_EPOCH) {
+            Ok(offset) => offset.as_secs_f64() * 1000.0,
+            Err(err) => -err.duration().as_secs_f64() * 1000.0,
         };
-        res
+        env.create_date(millis).into()
+    }
+}
+
+// 读取 BigInt 的符号和绝对值，绝对值超过 128 位时返回 RangeError
+fn get_bigint_u128(value: JsBigInt<'_>, ty: &str) -> Result<(bool, u128), Error> {
+    let (negative, words) = value.get_words();
+    match words.as_slice() {
+        [] => Ok((false, 0)),
+        [low] => Ok((negative, *low as u128)),
+        [low, high] => Ok((negative, (*high as u128) << 64 | *low as u128)),
+        _ => Err(bigint_out_of_range(ty)),
+    }
+}
+
+// 和 Date 的转换一样，无法表示的值不截断也不回绕，而是抛出 RangeError
+fn bigint_out_of_range(ty: &str) -> Error {
+    Error::range_error(format!("The BigInt is out of range for {}", ty))
+        .with_code("ERR_OUT_OF_RANGE")
+}
+
+// 范围是 [-2^127, 2^127 - 1]
+pub(crate) fn bigint_to_i128(value: JsBigInt<'_>) -> Result<i128, Error> {
+    let (negative, magnitude) = get_bigint_u128(value, "i128")?;
+    match (negative, i128::try_from(magnitude)) {
+        (false, Ok(value)) => Ok(value),
+        (true, Ok(value)) => Ok(-value),
+        // 绝对值 2^127 只有负数能表示，就是 i128::MIN
+        (true, Err(_)) if magnitude == i128::MIN.unsigned_abs() => Ok(i128::MIN),
+        _ => Err(bigint_out_of_range("i128")),
+    }
+}
+
+// 范围是 [0, 2^128 - 1]，负数返回 RangeError
+pub(crate) fn bigint_to_u128(value: JsBigInt<'_>) -> Result<u128, Error> {
+    match get_bigint_u128(value, "u128")? {
+        (false, magnitude) => Ok(magnitude),
+        (true, _) => Err(bigint_out_of_range("u128")),
+    }
+}
+
+fn create_bigint_u128(env: Env<'_>, negative: bool, magnitude: u128) -> JsValue<'_> {
+    env.create_bigint_words(negative, &[magnitude as u64, (magnitude >> 64) as u64])
+        .into()
+}
+
+// 128 位整数对应 JS 的 BigInt
+impl<'a> NapiValue<'a> for i128 {
+    fn from_js_value(value: JsValue<'a>) -> i128 {
+        bigint_to_i128(JsBigInt::from_js_value(value))
+            .unwrap_or_else(|err| unwind::throw_error(err))
+    }
+
+    fn into_js_value(self, env: Env<'a>) -> JsValue<'a> {
+        create_bigint_u128(env, self < 0, self.unsigned_abs())
+    }
+}
+
+impl<'a> NapiValue<'a> for u128 {
+    fn from_js_value(value: JsValue<'a>) -> u128 {
+        bigint_to_u128(JsBigInt::from_js_value(value))
+            .unwrap_or_else(|err| unwind::throw_error(err))
+    }
+
+    fn into_js_value(self, env: Env<'a>) -> JsValue<'a> {
+        create_bigint_u128(env, false, self)
     }
 }
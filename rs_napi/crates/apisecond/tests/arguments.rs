@@ -0,0 +1,180 @@
+use sys::napi_value;
+
+mod common;
+
+use common::env;
+
+// 调用 exports[name]，返回结果或者抛出的错误
+fn call(env: &mock::Env, name: &str, args: &[napi_value]) -> Result<napi_value, napi_value> {
+    let exports = env.load_module(apisecond::napi_register_module_v1);
+    let function = env.get_named_property(exports, name);
+    env.call_function(function, args)
+}
+
+fn strings(env: &mock::Env, values: &[&str]) -> napi_value {
+    let values = values
+        .iter()
+        .map(|value| env.create_string(value))
+        .collect::<Vec<_>>();
+    env.create_array(&values)
+}
+
+fn numbers(env: &mock::Env, values: &[f64]) -> napi_value {
+    let values = values
+        .iter()
+        .map(|value| env.create_number(*value))
+        .collect::<Vec<_>>();
+    env.create_array(&values)
+}
+
+#[test]
+fn borrowed_str_and_slice_arguments() {
+    let env = env();
+    // join(["a", "b", "c"], "-")
+    let result = call(
+        &env,
+        "join",
+        &[strings(&env, &["a", "b", "c"]), env.create_string("-")],
+    )
+    .unwrap();
+    assert_eq!(env.get_string(result), "a-b-c");
+    let result = call(&env, "join", &[strings(&env, &[]), env.create_string("-")]).unwrap();
+    assert_eq!(env.get_string(result), "");
+    // 多字节字符原样传递
+    let result = call(&env, "greet", &[env.create_string("世界")]).unwrap();
+    assert_eq!(env.get_string(result), "Hello, 世界!");
+}
+
+#[test]
+fn tuple_pattern_arguments() {
+    let env = env();
+    // distance([0, 0], [3, 4])
+    let result = call(
+        &env,
+        "distance",
+        &[numbers(&env, &[0.0, 0.0]), numbers(&env, &[3.0, 4.0])],
+    )
+    .unwrap();
+    assert_eq!(env.get_number(result), 5.0);
+    let result = call(
+        &env,
+        "distance",
+        &[numbers(&env, &[1.0, -1.0]), numbers(&env, &[-2.0, 3.0])],
+    )
+    .unwrap();
+    assert_eq!(env.get_number(result), 5.0);
+}
+
+#[test]
+fn mismatched_arguments_throw_type_errors() {
+    let env = env();
+    let cases = [
+        // distance(1, [0, 0])
+        (
+            "distance",
+            vec![env.create_number(1.0), numbers(&env, &[0.0, 0.0])],
+            "expected an array, found number",
+        ),
+        // distance([1], [0, 0])：元组缺少的元素是 undefined
+        (
+            "distance",
+            vec![numbers(&env, &[1.0]), numbers(&env, &[0.0, 0.0])],
+            "expected a number, found undefined",
+        ),
+        // greet(5)
+        (
+            "greet",
+            vec![env.create_number(5.0)],
+            "expected a string, found number",
+        ),
+    ];
+    for (name, args, message) in cases {
+        let error = call(&env, name, &args).unwrap_err();
+        assert!(env.describe(error).starts_with("TypeError"), "{}", name);
+        assert_eq!(
+            env.get_string(env.get_named_property(error, "code")),
+            "ERR_INVALID_ARG_TYPE"
+        );
+        assert_eq!(
+            env.get_string(env.get_named_property(error, "message")),
+            message
+        );
+    }
+}
+
+#[test]
+fn missing_optional_arguments_are_none() {
+    let env = env();
+    let name = env.create_string("Ann");
+    for (args, expected) in [
+        (vec![name], "Hello, Ann!"),
+        (vec![name, env.get_undefined()], "Hello, Ann!"),
+        // Option 把 null 也当作没有传入
+        (vec![name, env.get_null()], "Hello, Ann!"),
+        (vec![name, env.create_string("Hi")], "Hi, Ann!"),
+    ] {
+        let result = call(&env, "greet", &args).unwrap();
+        assert_eq!(env.get_string(result), expected);
+    }
+    // 必需的参数缺少时仍然抛出 TypeError
+    let error = call(&env, "greet", &[]).unwrap_err();
+    assert_eq!(
+        env.get_string(env.get_named_property(error, "message")),
+        "expected a string, found undefined"
+    );
+}
+
+#[test]
+fn rest_arguments_collect_every_remaining_argument() {
+    let env = env();
+    for values in [&[][..], &[1.0], &[1.0, 2.0, 3.5]] {
+        let args = values
+            .iter()
+            .map(|value| env.create_number(*value))
+            .collect::<Vec<_>>();
+        let result = call(&env, "sum", &args).unwrap();
+        assert_eq!(env.get_number(result), values.iter().sum::<f64>());
+    }
+    // 每个剩余参数都要转换成元素类型
+    let error = call(
+        &env,
+        "sum",
+        &[env.create_number(1.0), env.create_string("x")],
+    )
+    .unwrap_err();
+    assert_eq!(
+        env.get_string(env.get_named_property(error, "message")),
+        "expected a number, found string"
+    );
+}
+
+#[test]
+fn default_values_replace_missing_or_undefined_arguments() {
+    let env = env();
+    let two = env.create_number(2.0);
+    // #[api(default(scale = 1.0))]
+    for (args, expected) in [
+        (vec![two], 2.0),
+        (vec![two, env.get_undefined()], 2.0),
+        (vec![two, env.create_number(3.0)], 6.0),
+    ] {
+        let result = call(&env, "scale", &args).unwrap();
+        assert_eq!(env.get_number(result), expected);
+    }
+    // #[default = 2.0] 写在参数上
+    let value = env.create_number(1.23456);
+    for (args, expected) in [
+        (vec![value], "1.23"),
+        (vec![value, env.create_number(3.0)], "1.235"),
+        (vec![value, env.create_number(0.0)], "1"),
+    ] {
+        let result = call(&env, "format_number", &args).unwrap();
+        assert_eq!(env.get_string(result), expected);
+    }
+    // 和 JS 的默认参数一样，只有 undefined 会使用默认值，null 仍然按参数类型转换
+    let error = call(&env, "scale", &[two, env.get_null()]).unwrap_err();
+    assert_eq!(
+        env.get_string(env.get_named_property(error, "message")),
+        "expected a number, found null"
+    );
+}
@@ -0,0 +1,198 @@
+use mock::PromiseState;
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::{Mutex, MutexGuard};
+use std::thread;
+use std::time::{Duration, Instant};
+use sys::napi_value;
+
+mod common;
+
+use common::env;
+
+// spins() 是所有 spin 调用共享的计数，读取它的测试不能同时运行
+fn serial() -> MutexGuard<'static, ()> {
+    static LOCK: Mutex<()> = Mutex::new(());
+    LOCK.lock().unwrap_or_else(|err| err.into_inner())
+}
+
+// 一个最小的 AbortSignal，记录当前注册着的 abort 监听器
+struct Signal {
+    object: napi_value,
+    listeners: Rc<RefCell<Vec<napi_value>>>,
+}
+
+impl Signal {
+    fn new(env: &mock::Env, aborted: bool) -> Signal {
+        let object = env.create_object();
+        let listeners = Rc::new(RefCell::new(Vec::new()));
+        env.set_named_property(object, "aborted", env.get_boolean(aborted));
+        let added = listeners.clone();
+        let add = env.create_function("addEventListener", move |env, args| {
+            assert_eq!(env.get_string(args[0]), "abort");
+            added.borrow_mut().push(args[1]);
+            env.get_undefined()
+        });
+        let removed = listeners.clone();
+        let remove = env.create_function("removeEventListener", move |env, args| {
+            assert_eq!(env.get_string(args[0]), "abort");
+            removed
+                .borrow_mut()
+                .retain(|listener| !env.same_value(*listener, args[1]));
+            env.get_undefined()
+        });
+        env.set_named_property(object, "addEventListener", add);
+        env.set_named_property(object, "removeEventListener", remove);
+        Signal { object, listeners }
+    }
+
+    // 相当于 controller.abort(reason)：设置 aborted 和 reason 之后同步调用所有监听器
+    fn abort(&self, env: &mock::Env, reason: napi_value) {
+        env.set_named_property(self.object, "aborted", env.get_boolean(true));
+        env.set_named_property(self.object, "reason", reason);
+        let listeners = self.listeners.borrow().clone();
+        for listener in listeners {
+            env.call_function(listener, &[]).unwrap();
+        }
+    }
+
+    fn listeners(&self) -> usize {
+        self.listeners.borrow().len()
+    }
+}
+
+struct Fixture {
+    env: mock::Env,
+    exports: napi_value,
+}
+
+impl Fixture {
+    fn new() -> Fixture {
+        let env = env();
+        let exports = env.load_module(apisecond::napi_register_module_v1);
+        Fixture { env, exports }
+    }
+
+    fn call(&self, name: &str, args: &[napi_value]) -> napi_value {
+        let function = self.env.get_named_property(self.exports, name);
+        self.env.call_function(function, args).unwrap()
+    }
+
+    fn spins(&self) -> f64 {
+        self.env.get_number(self.call("spins", &[]))
+    }
+
+    fn rejection(&self, promise: napi_value) -> napi_value {
+        match self.env.promise_state(promise) {
+            PromiseState::Rejected(error) => error,
+            state => panic!("expected a rejected promise, found {:?}", state),
+        }
+    }
+
+    // Promise 以 AbortError 拒绝，err.cause 是 signal.reason
+    fn assert_aborted(&self, promise: napi_value, reason: napi_value) {
+        let env = &self.env;
+        let error = self.rejection(promise);
+        assert_eq!(
+            env.get_string(env.get_named_property(error, "name")),
+            "AbortError"
+        );
+        assert_eq!(
+            env.get_string(env.get_named_property(error, "code")),
+            "ABORT_ERR"
+        );
+        assert!(env.same_value(env.get_named_property(error, "cause"), reason));
+    }
+}
+
+#[test]
+fn resolves_and_removes_the_abort_listener() {
+    let fixture = Fixture::new();
+    let env = &fixture.env;
+    let signal = Signal::new(env, false);
+
+    let promise = fixture.call("fibonacci", &[env.create_number(10.0), signal.object]);
+    assert_eq!(signal.listeners(), 1);
+    env.run();
+    let PromiseState::Fulfilled(value) = env.promise_state(promise) else {
+        panic!("expected a fulfilled promise");
+    };
+    assert_eq!(env.get_number(value), 55.0);
+    assert_eq!(signal.listeners(), 0);
+}
+
+#[test]
+fn rejects_without_queueing_when_already_aborted() {
+    let fixture = Fixture::new();
+    let env = &fixture.env;
+    let signal = Signal::new(env, true);
+    let reason = env.create_string("stopped");
+    env.set_named_property(signal.object, "reason", reason);
+
+    // 不需要运行事件循环，调用时就已经拒绝
+    let promise = fixture.call("fibonacci", &[env.create_number(10.0), signal.object]);
+    fixture.assert_aborted(promise, reason);
+    assert_eq!(signal.listeners(), 0);
+}
+
+#[test]
+fn cancels_work_that_is_still_queued() {
+    let _serial = serial();
+    let fixture = Fixture::new();
+    let env = &fixture.env;
+    let signal = Signal::new(env, false);
+    let spins = fixture.spins();
+
+    env.hold_async_work();
+    let promise = fixture.call("spin", &[signal.object]);
+    let reason = env.create_string("stopped");
+    signal.abort(env, reason);
+    env.run();
+
+    fixture.assert_aborted(promise, reason);
+    assert_eq!(signal.listeners(), 0);
+    // 任务在开始执行之前就被移出了队列
+    assert_eq!(fixture.spins(), spins);
+}
+
+#[test]
+fn cancels_work_that_is_already_running() {
+    let _serial = serial();
+    let fixture = Fixture::new();
+    let env = &fixture.env;
+    let signal = Signal::new(env, false);
+    let spins = fixture.spins();
+
+    let promise = fixture.call("spin", &[signal.object]);
+    // 等到任务在线程池中开始执行，这时只能通过令牌让它自己结束
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while fixture.spins() == spins {
+        assert!(Instant::now() < deadline, "spin never started");
+        thread::sleep(Duration::from_millis(1));
+    }
+    let reason = env.create_string("stopped");
+    signal.abort(env, reason);
+    env.run();
+
+    fixture.assert_aborted(promise, reason);
+    assert_eq!(signal.listeners(), 0);
+}
+
+#[test]
+fn rejects_when_settling_panics() {
+    let fixture = Fixture::new();
+    let env = &fixture.env;
+    let signal = Signal::new(env, false);
+
+    let promise = fixture.call("fibonacci", &[env.create_number(10.0), signal.object]);
+    // 任务完成时移除监听器会失败，Promise 仍然要被拒绝，而不是一直处于 pending
+    env.set_named_property(signal.object, "removeEventListener", env.create_number(1.0));
+    env.run();
+
+    let error = fixture.rejection(promise);
+    assert_eq!(
+        env.describe(error),
+        "TypeError: String(\"expected a function, found number\")"
+    );
+    assert!(env.take_uncaught().is_empty());
+}
@@ -0,0 +1,100 @@
+use sys::napi_value;
+
+mod common;
+
+use common::env;
+
+// 调用 exports[name]，返回结果或者抛出的错误
+fn call(env: &mock::Env, name: &str, args: &[napi_value]) -> Result<napi_value, napi_value> {
+    let exports = env.load_module(apisecond::napi_register_module_v1);
+    let function = env.get_named_property(exports, name);
+    env.call_function(function, args)
+}
+
+fn assert_out_of_range(env: &mock::Env, error: napi_value, ty: &str) {
+    assert_eq!(
+        env.get_string(env.get_named_property(error, "name")),
+        "RangeError"
+    );
+    assert_eq!(
+        env.get_string(env.get_named_property(error, "code")),
+        "ERR_OUT_OF_RANGE"
+    );
+    assert_eq!(
+        env.get_string(env.get_named_property(error, "message")),
+        format!("The BigInt is out of range for {}", ty)
+    );
+}
+
+#[test]
+fn u128_round_trips_across_both_words() {
+    let env = env();
+    for (words, expected) in [
+        (vec![], vec![1]),
+        (vec![u64::MAX], vec![0, 1]),
+        (vec![5, 7], vec![6, 7]),
+        (vec![u64::MAX, 1], vec![0, 2]),
+    ] {
+        let id = env.create_bigint(false, &words);
+        let next = call(&env, "next_id", &[id]).unwrap();
+        assert_eq!(env.get_bigint(next), (false, expected));
+    }
+}
+
+#[test]
+fn u128_rejects_negative_and_wide_bigints() {
+    let env = env();
+    // next_id(-1n)
+    let error = call(&env, "next_id", &[env.create_bigint(true, &[1])]).unwrap_err();
+    assert_out_of_range(&env, error, "u128");
+    // next_id(2n ** 200n)
+    let error = call(
+        &env,
+        "next_id",
+        &[env.create_bigint(false, &[0, 0, 0, 1 << 8])],
+    )
+    .unwrap_err();
+    assert_out_of_range(&env, error, "u128");
+}
+
+#[test]
+fn i128_accepts_the_full_range() {
+    let env = env();
+    let min = env.create_bigint(true, &[0, 1 << 63]);
+    let max = env.create_bigint(false, &[u64::MAX, u64::MAX >> 1]);
+    let diff = call(&env, "abs_diff", &[min, max]).unwrap();
+    assert_eq!(env.get_bigint(diff), (false, vec![u64::MAX, u64::MAX]));
+
+    let a = env.create_bigint(true, &[3]);
+    let b = env.create_bigint(false, &[4]);
+    let diff = call(&env, "abs_diff", &[a, b]).unwrap();
+    assert_eq!(env.get_bigint(diff), (false, vec![7]));
+}
+
+#[test]
+fn i128_rejects_bigints_beyond_its_range() {
+    let env = env();
+    let zero = env.create_bigint(false, &[]);
+    for (negative, words) in [
+        // 2n ** 127n
+        (false, vec![0, 1 << 63]),
+        // -(2n ** 127n) - 1n
+        (true, vec![1, 1 << 63]),
+        // -(2n ** 128n)
+        (true, vec![0, 0, 1]),
+    ] {
+        let value = env.create_bigint(negative, &words);
+        let error = call(&env, "abs_diff", &[value, zero]).unwrap_err();
+        assert_out_of_range(&env, error, "i128");
+    }
+}
+
+#[test]
+fn non_bigints_are_type_errors() {
+    let env = env();
+    let error = call(&env, "next_id", &[env.create_number(1.0)]).unwrap_err();
+    assert_eq!(
+        env.get_string(env.get_named_property(error, "name")),
+        "TypeError"
+    );
+}
@@ -0,0 +1,40 @@
+use apisecond::MemoryTracker;
+
+mod common;
+
+use common::env;
+
+// 计数是整个进程共享的，这个测试二进制中只有这一个测试，计数不会被其他测试同时修改
+#[test]
+fn external_buffers_are_tracked_only_when_created() {
+    let (bytes, count) = (
+        MemoryTracker::external_bytes(),
+        MemoryTracker::external_count(),
+    );
+    {
+        let env = env();
+        let exports = env.load_module(apisecond::napi_register_module_v1);
+        let fill_buffer = env.get_named_property(exports, "fill_buffer");
+        let (byte, len) = (env.create_number(7.0), env.create_number(1024.0));
+
+        env.call_function(fill_buffer, &[byte, len]).unwrap();
+        assert_eq!(MemoryTracker::external_bytes(), bytes + 1024);
+        assert_eq!(MemoryTracker::external_count(), count + 1);
+        assert_eq!(env.external_memory(), 1024);
+
+        // 宿主拒绝创建 external buffer 时抛出错误，数据被释放，也不会报告给 V8
+        env.disallow_external_buffers();
+        let error = env.call_function(fill_buffer, &[byte, len]).unwrap_err();
+        let message = env.get_string(env.get_named_property(error, "message"));
+        assert_eq!(
+            message,
+            "failed to create an external buffer (napi_status 22)"
+        );
+        assert_eq!(MemoryTracker::external_bytes(), bytes + 1024);
+        assert_eq!(MemoryTracker::external_count(), count + 1);
+        assert_eq!(env.external_memory(), 1024);
+    }
+    // env 销毁时调用成功创建的 Buffer 的 finalizer
+    assert_eq!(MemoryTracker::external_bytes(), bytes);
+    assert_eq!(MemoryTracker::external_count(), count);
+}
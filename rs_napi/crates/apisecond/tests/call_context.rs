@@ -0,0 +1,67 @@
+mod common;
+
+use common::env;
+
+#[test]
+fn plain_call_has_undefined_this_and_no_new_target() {
+    let env = env();
+    let exports = env.load_module(apisecond::napi_register_module_v1);
+    let call_site = env.get_named_property(exports, "call_site");
+    let site = env.call_function(call_site, &[]).unwrap();
+    assert_eq!(
+        env.type_of(env.get_named_property(site, "this")),
+        "undefined"
+    );
+    assert_eq!(
+        env.type_of(env.get_named_property(site, "newTarget")),
+        "undefined"
+    );
+}
+
+#[test]
+fn method_call_passes_the_receiver_as_this() {
+    let env = env();
+    let exports = env.load_module(apisecond::napi_register_module_v1);
+    let call_site = env.get_named_property(exports, "call_site");
+    // receiver.callSite()
+    let receiver = env.create_object();
+    env.set_named_property(receiver, "callSite", call_site);
+    let site = env.call_method(receiver, call_site, &[]).unwrap();
+    assert!(env.same_value(env.get_named_property(site, "this"), receiver));
+    assert_eq!(
+        env.type_of(env.get_named_property(site, "newTarget")),
+        "undefined"
+    );
+}
+
+#[test]
+fn construct_call_exposes_new_target() {
+    let env = env();
+    let exports = env.load_module(apisecond::napi_register_module_v1);
+    let call_site = env.get_named_property(exports, "call_site");
+    // new call_site()，函数返回了对象，所以结果是它而不是新创建的 this
+    let site = env.construct(call_site, &[]).unwrap();
+    assert!(env.same_value(env.get_named_property(site, "newTarget"), call_site));
+    let this = env.get_named_property(site, "this");
+    assert_eq!(env.type_of(this), "object");
+    assert!(!env.same_value(this, site));
+}
+
+#[test]
+fn argc_counts_only_the_arguments_actually_passed() {
+    let env = env();
+    let exports = env.load_module(apisecond::napi_register_module_v1);
+    let arg_count = env.get_named_property(exports, "arg_count");
+    for count in [0, 1, 3] {
+        let args = (0..count)
+            .map(|i| env.create_number(i as f64))
+            .collect::<Vec<_>>();
+        let result = env.call_function(arg_count, &args).unwrap();
+        assert_eq!(env.get_number(result), count as f64);
+    }
+    // 显式传入的 undefined 也算一个参数
+    let result = env
+        .call_function(arg_count, &[env.get_undefined(), env.get_undefined()])
+        .unwrap();
+    assert_eq!(env.get_number(result), 2.0);
+}
@@ -0,0 +1,8 @@
+use std::sync::Once;
+
+// 测试二进制中没有 Node.js，所有 napi_* 调用都转发到 mock 运行时
+pub fn env() -> mock::Env {
+    static LOAD: Once = Once::new();
+    LOAD.call_once(|| unsafe { sys::load_with(mock::symbol).unwrap() });
+    mock::Env::new()
+}
@@ -0,0 +1,99 @@
+use sys::napi_value;
+
+mod common;
+
+use common::env;
+
+fn call(env: &mock::Env, name: &str, args: &[napi_value]) -> Result<napi_value, napi_value> {
+    let exports = env.load_module(apisecond::napi_register_module_v1);
+    let function = env.get_named_property(exports, name);
+    env.call_function(function, args)
+}
+
+// 错误的类（describe 的前缀就是创建它的构造函数）、code 和 message
+fn assert_error(env: &mock::Env, error: napi_value, class: &str, code: &str, message: &str) {
+    assert!(
+        env.describe(error).starts_with(&format!("{}: ", class)),
+        "expected a {}, found {}",
+        class,
+        env.describe(error)
+    );
+    assert_eq!(env.get_string(env.get_named_property(error, "name")), class);
+    assert_eq!(env.get_string(env.get_named_property(error, "code")), code);
+    assert_eq!(
+        env.get_string(env.get_named_property(error, "message")),
+        message
+    );
+}
+
+#[test]
+fn ok_results_are_returned() {
+    let env = env();
+    let result = call(&env, "sqrt", &[env.create_number(9.0)]).unwrap();
+    assert_eq!(env.get_number(result), 3.0);
+    let result = call(
+        &env,
+        "log",
+        &[env.create_number(8.0), env.create_number(2.0)],
+    )
+    .unwrap();
+    assert_eq!(env.get_number(result), 3.0);
+}
+
+#[test]
+fn errors_are_thrown_with_their_class_and_code() {
+    let env = env();
+    let error = call(&env, "sqrt", &[env.create_number(-1.0)]).unwrap_err();
+    assert_error(
+        &env,
+        error,
+        "RangeError",
+        "ERR_NEGATIVE_SQRT",
+        "cannot take the square root of -1",
+    );
+    assert_eq!(
+        env.type_of(env.get_named_property(error, "cause")),
+        "undefined"
+    );
+
+    let error = call(
+        &env,
+        "log",
+        &[env.create_number(8.0), env.create_number(1.0)],
+    )
+    .unwrap_err();
+    assert_error(
+        &env,
+        error,
+        "RangeError",
+        "ERR_INVALID_LOG_BASE",
+        "invalid logarithm base 1",
+    );
+}
+
+#[test]
+fn source_errors_become_the_cause() {
+    let env = env();
+    let error = call(
+        &env,
+        "log",
+        &[env.create_number(0.0), env.create_number(10.0)],
+    )
+    .unwrap_err();
+    // 没有指定 kind 的变体是普通的 Error
+    assert_error(
+        &env,
+        error,
+        "Error",
+        "ERR_LOG",
+        "cannot take the logarithm of 0",
+    );
+    let cause = env.get_named_property(error, "cause");
+    assert_error(
+        &env,
+        cause,
+        "RangeError",
+        "ERR_OUT_OF_DOMAIN",
+        "logarithm is only defined for positive numbers",
+    );
+}
@@ -0,0 +1,145 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use sys::napi_value;
+
+mod common;
+
+use common::env;
+
+type Events = Rc<RefCell<Vec<(String, f64)>>>;
+
+// 一个只实现了 emit 的 EventEmitter，按派发顺序记录 (事件名, payload)
+fn recorder(env: &mock::Env) -> (napi_value, Events) {
+    let events = Rc::new(RefCell::new(Vec::new()));
+    let record = events.clone();
+    let emitter = env.create_object();
+    let emit = env.create_function("emit", move |env, args| {
+        let name = env.get_string(args[0]);
+        record.borrow_mut().push((name, env.get_number(args[1])));
+        env.get_boolean(true)
+    });
+    env.set_named_property(emitter, "emit", emit);
+    (emitter, events)
+}
+
+fn ticks(count: usize) -> Vec<(String, f64)> {
+    (0..count)
+        .map(|i| ("tick".to_string(), i as f64))
+        .chain([("done".to_string(), count as f64)])
+        .collect()
+}
+
+#[test]
+fn delivers_events_in_order_on_the_js_thread() {
+    let env = env();
+    let exports = env.load_module(apisecond::napi_register_module_v1);
+    let emit_ticks = env.get_named_property(exports, "emit_ticks");
+    let (emitter, events) = recorder(&env);
+
+    let count = env.create_number(100.0);
+    let result = env.call_function(emit_ticks, &[emitter, count]).unwrap();
+    assert!(env.same_value(result, emitter));
+    // 派发总是异步的，事件循环运行之前监听器不会被调用
+    assert!(events.borrow().is_empty());
+
+    // 所有句柄 drop 之后事件循环才会退出，此时所有事件都已经派发
+    env.run();
+    assert_eq!(*events.borrow(), ticks(100));
+    assert!(env.take_uncaught().is_empty());
+}
+
+#[test]
+fn concurrent_emitters_keep_their_own_order() {
+    let env = env();
+    let exports = env.load_module(apisecond::napi_register_module_v1);
+    let emit_ticks = env.get_named_property(exports, "emit_ticks");
+
+    let recorders = (0..8).map(|_| recorder(&env)).collect::<Vec<_>>();
+    for (emitter, _) in &recorders {
+        let count = env.create_number(50.0);
+        env.call_function(emit_ticks, &[*emitter, count]).unwrap();
+    }
+    env.run();
+    for (_, events) in &recorders {
+        assert_eq!(*events.borrow(), ticks(50));
+    }
+}
+
+// merged 是否由 a 和 b 交错而成，并且保持了两者各自的顺序
+fn is_interleaving<T: PartialEq>(merged: &[T], a: &[T], b: &[T]) -> bool {
+    match merged.split_first() {
+        None => a.is_empty() && b.is_empty(),
+        Some((first, rest)) => {
+            (a.first() == Some(first) && is_interleaving(rest, &a[1..], b))
+                || (b.first() == Some(first) && is_interleaving(rest, a, &b[1..]))
+        }
+    }
+}
+
+#[test]
+fn converting_the_same_object_twice_creates_independent_queues() {
+    let env = env();
+    let exports = env.load_module(apisecond::napi_register_module_v1);
+    let emit_ticks = env.get_named_property(exports, "emit_ticks");
+    let (emitter, events) = recorder(&env);
+
+    // 两次调用各自转换出一个句柄，只保证每个句柄（和它的克隆）发出的事件保持顺序
+    for count in [3.0, 5.0] {
+        let count = env.create_number(count);
+        env.call_function(emit_ticks, &[emitter, count]).unwrap();
+    }
+    env.run();
+    let events = events.borrow();
+    assert_eq!(events.len(), 4 + 6);
+    assert!(
+        is_interleaving(&events, &ticks(3), &ticks(5)),
+        "{:?} does not keep the order of each handle",
+        events
+    );
+}
+
+#[test]
+fn listener_exceptions_do_not_stop_delivery() {
+    let env = env();
+    let exports = env.load_module(apisecond::napi_register_module_v1);
+    let emit_ticks = env.get_named_property(exports, "emit_ticks");
+
+    let events = Rc::new(RefCell::new(Vec::new()));
+    let record = events.clone();
+    let emitter = env.create_object();
+    let emit = env.create_function("emit", move |env, args| {
+        let payload = env.get_number(args[1]);
+        record.borrow_mut().push(payload);
+        if payload == 1.0 {
+            env.throw(env.create_string("boom"));
+        }
+        env.get_undefined()
+    });
+    env.set_named_property(emitter, "emit", emit);
+
+    let count = env.create_number(3.0);
+    env.call_function(emit_ticks, &[emitter, count]).unwrap();
+    env.run();
+    assert_eq!(*events.borrow(), [0.0, 1.0, 2.0, 3.0]);
+    let uncaught = env.take_uncaught();
+    assert_eq!(uncaught.len(), 1);
+    assert_eq!(env.get_string(uncaught[0]), "boom");
+}
+
+#[test]
+fn rejects_objects_without_emit() {
+    let env = env();
+    let exports = env.load_module(apisecond::napi_register_module_v1);
+    let emit_ticks = env.get_named_property(exports, "emit_ticks");
+
+    let count = env.create_number(1.0);
+    let error = env
+        .call_function(emit_ticks, &[env.create_object(), count])
+        .unwrap_err();
+    let code = env.get_named_property(error, "code");
+    assert_eq!(env.get_string(code), "ERR_INVALID_ARG_TYPE");
+    let name = env.get_named_property(error, "name");
+    assert_eq!(env.get_string(name), "TypeError");
+    // 没有创建任何 threadsafe function，事件循环立即退出
+    env.run();
+}
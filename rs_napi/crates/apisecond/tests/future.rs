@@ -0,0 +1,95 @@
+use mock::PromiseState;
+use sys::napi_value;
+
+mod common;
+
+use common::env;
+
+struct Fixture {
+    env: mock::Env,
+    exports: napi_value,
+}
+
+impl Fixture {
+    fn new() -> Fixture {
+        let env = env();
+        let exports = env.load_module(apisecond::napi_register_module_v1);
+        Fixture { env, exports }
+    }
+
+    fn call(&self, name: &str, args: &[napi_value]) -> napi_value {
+        let function = self.env.get_named_property(self.exports, name);
+        self.env.call_function(function, args).unwrap()
+    }
+
+    // repeat(text, count)
+    fn repeat(&self, text: &str, count: f64) -> napi_value {
+        let text = self.env.create_string(text);
+        let count = self.env.create_number(count);
+        self.call("repeat", &[text, count])
+    }
+
+    fn fulfilled(&self, promise: napi_value) -> String {
+        match self.env.promise_state(promise) {
+            PromiseState::Fulfilled(value) => self.env.get_string(value),
+            state => panic!("expected a fulfilled promise, found {:?}", state),
+        }
+    }
+}
+
+#[test]
+fn async_fn_resolves_on_the_js_thread() {
+    let fixture = Fixture::new();
+    let promise = fixture.repeat("ab", 3.0);
+    // 结果要通过事件循环回到主线程，运行之前 Promise 一定还没有兑现
+    assert!(matches!(
+        fixture.env.promise_state(promise),
+        PromiseState::Pending
+    ));
+    fixture.env.run();
+    assert_eq!(fixture.fulfilled(promise), "ababab");
+}
+
+#[test]
+fn concurrent_calls_resolve_independently() {
+    let fixture = Fixture::new();
+    // 比执行器的线程数多得多的调用
+    let promises = (0..64)
+        .map(|count| fixture.repeat("x", count as f64))
+        .collect::<Vec<_>>();
+    fixture.env.run();
+    for (count, promise) in promises.into_iter().enumerate() {
+        assert_eq!(fixture.fulfilled(promise), "x".repeat(count));
+    }
+}
+
+#[test]
+fn panic_rejects_the_promise() {
+    let fixture = Fixture::new();
+    let env = &fixture.env;
+    // nth_async([1, 2], 5)
+    let values = env.create_array(&[env.create_number(1.0), env.create_number(2.0)]);
+    let promise = fixture.call("nth_async", &[values, env.create_number(5.0)]);
+    env.run();
+
+    let PromiseState::Rejected(error) = env.promise_state(promise) else {
+        panic!("expected a rejected promise");
+    };
+    let message = env.get_string(env.get_named_property(error, "message"));
+    assert!(
+        message.starts_with("Rust panicked at ") && message.contains("demo.rs"),
+        "{}",
+        message
+    );
+    assert!(
+        message.ends_with("index out of bounds: the len is 2 but the index is 5"),
+        "{}",
+        message
+    );
+    assert!(env.take_uncaught().is_empty());
+
+    // 执行器的线程没有因为 panic 退出
+    let promise = fixture.repeat("ok", 1.0);
+    env.run();
+    assert_eq!(fixture.fulfilled(promise), "ok");
+}
@@ -0,0 +1,205 @@
+use sys::napi_value;
+
+mod common;
+
+use common::env;
+
+fn object(env: &mock::Env, props: &[(&str, napi_value)]) -> napi_value {
+    let object = env.create_object();
+    for (name, value) in props {
+        env.set_named_property(object, name, *value);
+    }
+    object
+}
+
+fn point(env: &mock::Env, x: f64, y: f64) -> napi_value {
+    object(
+        env,
+        &[("x", env.create_number(x)), ("y", env.create_number(y))],
+    )
+}
+
+fn shape(env: &mock::Env, kind: napi_value) -> napi_value {
+    object(
+        env,
+        &[
+            ("id", env.create_bigint(false, &[7])),
+            ("kind", kind),
+            ("origin", point(env, 1.0, 2.0)),
+            ("label", env.create_string("a")),
+        ],
+    )
+}
+
+// 调用 translate(shape, 10, 20)
+fn translate(env: &mock::Env, shape: napi_value) -> Result<napi_value, napi_value> {
+    let exports = env.load_module(apisecond::napi_register_module_v1);
+    let translate = env.get_named_property(exports, "translate");
+    let (dx, dy) = (env.create_number(10.0), env.create_number(20.0));
+    env.call_function(translate, &[shape, dx, dy])
+}
+
+fn assert_error(env: &mock::Env, error: napi_value, name: &str, code: &str, message: &str) {
+    let field = |field| env.get_string(env.get_named_property(error, field));
+    assert_eq!(field("name"), name);
+    assert_eq!(field("code"), code);
+    assert!(
+        field("message").contains(message),
+        "{:?} does not contain {:?}",
+        field("message"),
+        message
+    );
+}
+
+#[test]
+fn struct_and_enum_variants_round_trip() {
+    let env = env();
+    let unit = env.create_string("Dot");
+    let newtype = object(&env, &[("Circle", env.create_number(3.0))]);
+    let tuple = env.create_array(&[env.create_number(2.0), env.create_number(4.0)]);
+    let tuple = object(&env, &[("Rect", tuple)]);
+    let points = env.create_array(&[point(&env, 0.0, 0.0), point(&env, 1.0, 1.0)]);
+    let polygon = object(&env, &[("points", points)]);
+    let polygon = object(&env, &[("Polygon", polygon)]);
+
+    for kind in [unit, newtype, tuple, polygon] {
+        let result = translate(&env, shape(&env, kind)).unwrap();
+        assert_eq!(
+            env.property_names(result),
+            ["id", "kind", "origin", "label"]
+        );
+        assert_eq!(
+            env.describe(env.get_named_property(result, "kind")),
+            env.describe(kind)
+        );
+        let origin = env.get_named_property(result, "origin");
+        assert_eq!(env.get_number(env.get_named_property(origin, "x")), 11.0);
+        assert_eq!(env.get_number(env.get_named_property(origin, "y")), 22.0);
+        let label = env.get_named_property(result, "label");
+        assert_eq!(env.get_string(label), "a");
+    }
+}
+
+#[test]
+fn optional_fields_accept_undefined_and_unknown_fields_are_ignored() {
+    let env = env();
+    let input = shape(&env, env.create_string("Dot"));
+    env.set_named_property(input, "label", env.get_undefined());
+    // 未知字段引用了对象自身，跳过时不会读取其中的内容
+    env.set_named_property(input, "parent", input);
+
+    let result = translate(&env, input).unwrap();
+    assert_eq!(env.type_of(env.get_named_property(result, "label")), "null");
+    assert_eq!(
+        env.type_of(env.get_named_property(result, "parent")),
+        "undefined"
+    );
+}
+
+#[test]
+fn missing_fields_are_type_errors() {
+    let env = env();
+    let input = object(
+        &env,
+        &[
+            ("id", env.create_bigint(false, &[1])),
+            ("kind", env.create_string("Dot")),
+        ],
+    );
+    let error = translate(&env, input).unwrap_err();
+    assert_error(
+        &env,
+        error,
+        "TypeError",
+        "ERR_DESERIALIZE",
+        "missing field `origin`",
+    );
+}
+
+#[test]
+fn wrong_types_are_type_errors() {
+    let env = env();
+    let input = shape(&env, env.create_string("Dot"));
+    let origin = object(
+        &env,
+        &[("x", env.create_string("1")), ("y", env.create_number(2.0))],
+    );
+    env.set_named_property(input, "origin", origin);
+    let error = translate(&env, input).unwrap_err();
+    assert_error(&env, error, "TypeError", "ERR_DESERIALIZE", "expected f64");
+
+    let error = translate(&env, shape(&env, env.create_string("Hexagon"))).unwrap_err();
+    assert_error(
+        &env,
+        error,
+        "TypeError",
+        "ERR_DESERIALIZE",
+        "unknown variant `Hexagon`",
+    );
+
+    let kind = object(
+        &env,
+        &[("Dot", env.get_null()), ("Circle", env.create_number(1.0))],
+    );
+    let error = translate(&env, shape(&env, kind)).unwrap_err();
+    assert_error(
+        &env,
+        error,
+        "TypeError",
+        "ERR_DESERIALIZE",
+        "single key for an enum variant",
+    );
+}
+
+#[test]
+fn cycles_are_type_errors() {
+    let env = env();
+    // const group = { ...shape, kind: { Group: [group] } }
+    let children = env.create_array(&[]);
+    let group = shape(&env, object(&env, &[("Group", children)]));
+    env.set_named_property(children, "0", group);
+
+    let error = translate(&env, group).unwrap_err();
+    assert_error(
+        &env,
+        error,
+        "TypeError",
+        "ERR_DESERIALIZE",
+        "nested more than 128 levels deep",
+    );
+}
+
+#[test]
+fn nested_groups_within_the_limit_are_accepted() {
+    let env = env();
+    let mut inner = shape(&env, env.create_string("Dot"));
+    // 每层组合占用对象、kind 和数组三层
+    for _ in 0..40 {
+        let children = env.create_array(&[inner]);
+        inner = shape(&env, object(&env, &[("Group", children)]));
+    }
+    translate(&env, inner).unwrap();
+}
+
+#[test]
+fn bigints_beyond_u128_are_range_errors() {
+    let env = env();
+    let input = shape(&env, env.create_string("Dot"));
+    // 2n ** 128n
+    env.set_named_property(input, "id", env.create_bigint(false, &[0, 0, 1]));
+    let error = translate(&env, input).unwrap_err();
+    assert_error(
+        &env,
+        error,
+        "RangeError",
+        "ERR_OUT_OF_RANGE",
+        "out of range for u128",
+    );
+
+    // 2n ** 128n - 1n 超出了 i128 的范围，但仍然可以作为 u128 读取
+    let input = shape(&env, env.create_string("Dot"));
+    let max = env.create_bigint(false, &[u64::MAX, u64::MAX]);
+    env.set_named_property(input, "id", max);
+    let result = translate(&env, input).unwrap();
+    assert!(env.same_value(env.get_named_property(result, "id"), max));
+}
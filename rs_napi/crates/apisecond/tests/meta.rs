@@ -0,0 +1,113 @@
+use sys::napi_value;
+
+mod common;
+
+use common::env;
+
+// exports.__meta[name] 中的一个参数：(name, type, optional, rest)
+type Param = (String, String, bool, bool);
+
+// 读取 exports.__meta[name]，返回 (doc, params, returns)
+fn meta(env: &mock::Env, exports: napi_value, name: &str) -> (String, Vec<Param>, String) {
+    let meta = env.get_named_property(env.get_named_property(exports, "__meta"), name);
+    assert_eq!(env.type_of(meta), "object", "__meta.{} is missing", name);
+    let field = |object, name| env.get_named_property(object, name);
+    let params = field(meta, "params");
+    let params = (0..env.array_length(params))
+        .map(|index| {
+            let param = env.get_element(params, index);
+            (
+                env.get_string(field(param, "name")),
+                env.get_string(field(param, "type")),
+                env.get_bool(field(param, "optional")),
+                env.get_bool(field(param, "rest")),
+            )
+        })
+        .collect();
+    (
+        env.get_string(field(meta, "doc")),
+        params,
+        env.get_string(field(meta, "returns")),
+    )
+}
+
+fn param(name: &str, ty: &str, optional: bool, rest: bool) -> Param {
+    (name.to_string(), ty.to_string(), optional, rest)
+}
+
+#[test]
+fn meta_carries_doc_comments_and_parameter_names() {
+    let env = env();
+    let exports = env.load_module(apisecond::napi_register_module_v1);
+
+    let (doc, params, returns) = meta(&env, exports, "add");
+    assert_eq!(doc, "返回两个数的和");
+    assert_eq!(
+        params,
+        [
+            param("left", "number", false, false),
+            param("right", "number", false, false)
+        ]
+    );
+    assert_eq!(returns, "number");
+
+    let (doc, params, returns) = meta(&env, exports, "join");
+    assert_eq!(doc, "把一组字符串用分隔符连接起来");
+    assert_eq!(
+        params,
+        [
+            param("parts", "string[]", false, false),
+            param("separator", "string", false, false)
+        ]
+    );
+    assert_eq!(returns, "string");
+}
+
+#[test]
+fn meta_describes_optional_rest_and_context_parameters() {
+    let env = env();
+    let exports = env.load_module(apisecond::napi_register_module_v1);
+
+    let (_, params, _) = meta(&env, exports, "greet");
+    assert_eq!(
+        params,
+        [
+            param("name", "string", false, false),
+            param("greeting", "string", true, false)
+        ]
+    );
+    // 有默认值的参数是可选的
+    let (_, params, _) = meta(&env, exports, "scale");
+    assert_eq!(params[1], param("scale", "number", true, false));
+    let (_, params, _) = meta(&env, exports, "sum");
+    assert_eq!(params, [param("values", "number[]", false, true)]);
+    // 不是标识符的参数按位置命名
+    let (_, params, _) = meta(&env, exports, "distance");
+    assert_eq!(
+        params,
+        [
+            param("arg0", "[number, number]", false, false),
+            param("arg1", "[number, number]", false, false)
+        ]
+    );
+    // CallContext 不是 JS 参数
+    let (_, params, _) = meta(&env, exports, "arg_count");
+    assert!(params.is_empty());
+}
+
+#[test]
+fn meta_describes_async_functions_and_skips_values() {
+    let env = env();
+    let exports = env.load_module(apisecond::napi_register_module_v1);
+
+    let (_, params, returns) = meta(&env, exports, "fibonacci");
+    assert_eq!(params[1], param("token", "AbortSignal", true, false));
+    assert_eq!(returns, "Promise<number>");
+    let (_, _, returns) = meta(&env, exports, "repeat");
+    assert_eq!(returns, "Promise<string>");
+
+    // 常量不是函数，__meta 中没有它们
+    let names = env.property_names(env.get_named_property(exports, "__meta"));
+    assert!(names.iter().any(|name| name == "add"));
+    assert!(!names.iter().any(|name| name == "VERSION"));
+}
@@ -0,0 +1,57 @@
+use sys::napi_value;
+
+mod common;
+
+use common::env;
+
+fn call(env: &mock::Env, name: &str, args: &[napi_value]) -> Result<napi_value, napi_value> {
+    let exports = env.load_module(apisecond::napi_register_module_v1);
+    let function = env.get_named_property(exports, name);
+    env.call_function(function, args)
+}
+
+fn numbers(env: &mock::Env, values: &[f64]) -> napi_value {
+    let values = values
+        .iter()
+        .map(|value| env.create_number(*value))
+        .collect::<Vec<_>>();
+    env.create_array(&values)
+}
+
+#[test]
+fn panic_is_thrown_as_an_error() {
+    let env = env();
+    // nth([1, 2], 5)
+    let error = call(
+        &env,
+        "nth",
+        &[numbers(&env, &[1.0, 2.0]), env.create_number(5.0)],
+    )
+    .unwrap_err();
+    assert!(env.describe(error).starts_with("Error"));
+    let message = env.get_string(env.get_named_property(error, "message"));
+    // 消息中带有 panic 的位置和原本的信息
+    assert!(
+        message.starts_with("Rust panicked at ") && message.contains("demo.rs"),
+        "{}",
+        message
+    );
+    assert!(
+        message.ends_with("index out of bounds: the len is 2 but the index is 5"),
+        "{}",
+        message
+    );
+    // 没有未处理的异常留在环境中
+    assert!(env.take_uncaught().is_empty());
+}
+
+#[test]
+fn module_keeps_working_after_a_panic() {
+    let env = env();
+    let values = numbers(&env, &[1.0, 2.0]);
+    for _ in 0..3 {
+        assert!(call(&env, "nth", &[values, env.create_number(2.0)]).is_err());
+        let second = call(&env, "nth", &[values, env.create_number(1.0)]).unwrap();
+        assert_eq!(env.get_number(second), 2.0);
+    }
+}
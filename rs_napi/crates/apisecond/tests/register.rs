@@ -0,0 +1,61 @@
+use apisecond::{ExportKind, FnMeta};
+use std::ptr;
+use sys::{napi_callback_info, napi_env, napi_value};
+
+// 直接调用 register_fn，和 #[api] 生成的注册代码一样。注册表是进程内共享的，这些测试放在单独的测试程序中，
+// 不影响 registry.rs 中对完整注册表的断言
+
+const META: FnMeta = FnMeta {
+    kind: ExportKind::Function,
+    doc: "",
+    params: &[],
+    ret: "void",
+};
+
+unsafe extern "C" fn first(_env: napi_env, _info: napi_callback_info) -> napi_value {
+    ptr::null_mut()
+}
+
+unsafe extern "C" fn second(_env: napi_env, _info: napi_callback_info) -> napi_value {
+    ptr::null_mut()
+}
+
+fn count(name: &str) -> usize {
+    apisecond::registry()
+        .iter()
+        .filter(|registration| registration.name == name)
+        .count()
+}
+
+#[test]
+fn registering_the_same_callback_twice_keeps_one_entry() {
+    apisecond::register_fn("__same_callback", Some(first), META);
+    let registry = apisecond::registry();
+    apisecond::register_fn("__same_callback", Some(first), META);
+    assert_eq!(count("__same_callback"), 1);
+    // 替换时保持原来的位置
+    let position = |registry: &[apisecond::Registration]| {
+        registry
+            .iter()
+            .position(|registration| registration.name == "__same_callback")
+    };
+    assert_eq!(position(&apisecond::registry()), position(&registry));
+}
+
+#[test]
+#[should_panic(expected = "`__conflict` is already registered with a different callback")]
+fn registering_a_different_callback_under_the_same_name_panics() {
+    apisecond::register_fn("__conflict", Some(first), META);
+    apisecond::register_fn("__conflict", Some(second), META);
+}
+
+#[test]
+fn conflicting_registration_leaves_the_registry_usable() {
+    apisecond::register_fn("__kept", Some(first), META);
+    let result = std::panic::catch_unwind(|| {
+        apisecond::register_fn("__kept", Some(second), META);
+    });
+    assert!(result.is_err());
+    // 注册表没有因为 panic 中毒，原来的条目仍然在
+    assert_eq!(count("__kept"), 1);
+}
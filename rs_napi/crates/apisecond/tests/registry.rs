@@ -0,0 +1,96 @@
+use apisecond::{ExportKind, Registration};
+use std::collections::HashSet;
+use sys::napi_value;
+
+mod common;
+
+use common::env;
+
+// 读取 exports.__registry
+fn registry(env: &mock::Env, exports: napi_value) -> Vec<(String, String, f64)> {
+    let registry = env.get_named_property(exports, "__registry");
+    (0..env.array_length(registry))
+        .map(|index| {
+            let entry = env.get_element(registry, index);
+            let field = |name| env.get_named_property(entry, name);
+            (
+                env.get_string(field("name")),
+                env.get_string(field("kind")),
+                env.get_number(field("arity")),
+            )
+        })
+        .collect()
+}
+
+fn find(name: &str) -> Registration {
+    apisecond::registry()
+        .into_iter()
+        .find(|registration| registration.name == name)
+        .unwrap_or_else(|| panic!("{} is not registered", name))
+}
+
+#[test]
+fn registry_describes_every_export() {
+    let registration = |name, kind, arity| Registration { name, kind, arity };
+    assert_eq!(find("add"), registration("add", ExportKind::Function, 2));
+    assert_eq!(
+        find("greet"),
+        registration("greet", ExportKind::Function, 1)
+    );
+    assert_eq!(find("sum"), registration("sum", ExportKind::Function, 0));
+    assert_eq!(find("repeat"), registration("repeat", ExportKind::Async, 2));
+    assert_eq!(
+        find("fibonacci"),
+        registration("fibonacci", ExportKind::Async, 1)
+    );
+    assert_eq!(
+        find("range"),
+        registration("range", ExportKind::Iterator, 2)
+    );
+    assert_eq!(
+        find("VERSION"),
+        registration("VERSION", ExportKind::Value, 0)
+    );
+
+    let names = apisecond::registry()
+        .iter()
+        .map(|registration| registration.name)
+        .collect::<HashSet<_>>();
+    assert_eq!(names.len(), apisecond::registry().len());
+}
+
+#[test]
+fn loading_into_two_envs_exports_the_same_registry() {
+    let expected = apisecond::registry();
+    let first = env();
+    let second = env();
+    let first_exports = first.load_module(apisecond::napi_register_module_v1);
+    let second_exports = second.load_module(apisecond::napi_register_module_v1);
+    // 同一个 env 中再次加载（例如 jest 重新 require 模块）也不会重复注册
+    let reloaded = first.load_module(apisecond::napi_register_module_v1);
+
+    let first_registry = registry(&first, first_exports);
+    assert_eq!(first_registry.len(), expected.len());
+    for (entry, registration) in first_registry.iter().zip(&expected) {
+        assert_eq!(entry.0, registration.name);
+        assert_eq!(entry.1, registration.kind.as_str());
+        assert_eq!(entry.2, registration.arity as f64);
+    }
+    assert_eq!(registry(&second, second_exports), first_registry);
+    assert_eq!(registry(&first, reloaded), first_registry);
+    assert_eq!(apisecond::registry(), expected);
+
+    // 两个 env 中的导出互相独立，都可以正常调用
+    for (env, exports) in [(&first, first_exports), (&second, second_exports)] {
+        let add = env.get_named_property(exports, "add");
+        let (left, right) = (env.create_number(1.0), env.create_number(2.0));
+        let sum = env.call_function(add, &[left, right]).unwrap();
+        assert_eq!(env.get_number(sum), 3.0);
+        for (name, _, _) in &first_registry {
+            assert_ne!(
+                env.type_of(env.get_named_property(exports, name)),
+                "undefined"
+            );
+        }
+    }
+}
@@ -0,0 +1,173 @@
+use mock::PromiseState;
+use std::sync::{Mutex, MutexGuard};
+use sys::napi_value;
+
+mod common;
+
+use common::env;
+
+// scanned() 是所有流共享的计数，读取它的测试不能同时运行
+fn serial() -> MutexGuard<'static, ()> {
+    static LOCK: Mutex<()> = Mutex::new(());
+    LOCK.lock().unwrap_or_else(|err| err.into_inner())
+}
+
+struct Fixture {
+    env: mock::Env,
+    exports: napi_value,
+}
+
+impl Fixture {
+    fn new() -> Fixture {
+        let env = env();
+        let exports = env.load_module(apisecond::napi_register_module_v1);
+        Fixture { env, exports }
+    }
+
+    fn call(&self, name: &str, args: &[napi_value]) -> napi_value {
+        let function = self.env.get_named_property(self.exports, name);
+        self.env.call_function(function, args).unwrap()
+    }
+
+    // scan(count, fail_at)
+    fn scan(&self, count: f64, fail_at: Option<f64>) -> napi_value {
+        let count = self.env.create_number(count);
+        let fail_at = match fail_at {
+            Some(index) => self.env.create_number(index),
+            None => self.env.get_undefined(),
+        };
+        self.call("scan", &[count, fail_at])
+    }
+
+    fn scanned(&self) -> f64 {
+        self.env.get_number(self.call("scanned", &[]))
+    }
+
+    // iterator.next() 或 iterator.return()，返回 Promise
+    fn method(&self, iterator: napi_value, name: &str) -> napi_value {
+        let method = self.env.get_named_property(iterator, name);
+        self.env.call_function(method, &[]).unwrap()
+    }
+
+    // 已经兑现的 { value, done }，done 为 true 时返回 None
+    fn fulfilled(&self, promise: napi_value) -> Option<f64> {
+        let PromiseState::Fulfilled(result) = self.env.promise_state(promise) else {
+            panic!("expected a fulfilled promise");
+        };
+        let done = self.env.get_named_property(result, "done");
+        let value = self.env.get_named_property(result, "value");
+        if self.env.get_bool(done) {
+            assert_eq!(self.env.type_of(value), "undefined");
+            None
+        } else {
+            Some(self.env.get_number(value))
+        }
+    }
+}
+
+#[test]
+fn next_resolves_in_call_order() {
+    let _serial = serial();
+    let fixture = Fixture::new();
+    let iterator = fixture.scan(3.0, None);
+
+    // 在事件循环运行之前连续调用 next()，多出来的请求在流结束时以 done 兑现
+    let promises = (0..5)
+        .map(|_| fixture.method(iterator, "next"))
+        .collect::<Vec<_>>();
+    fixture.env.run();
+    let results = promises
+        .iter()
+        .map(|promise| fixture.fulfilled(*promise))
+        .collect::<Vec<_>>();
+    assert_eq!(results, [Some(0.0), Some(1.0), Some(2.0), None, None]);
+
+    // 结束之后的 next() 立即兑现
+    let promise = fixture.method(iterator, "next");
+    assert_eq!(fixture.fulfilled(promise), None);
+}
+
+#[test]
+fn producer_only_polls_after_next() {
+    let _serial = serial();
+    let fixture = Fixture::new();
+    let before = fixture.scanned();
+    let iterator = fixture.scan(100.0, None);
+
+    // 没有 next() 请求时生产者不会 poll，事件循环也不会因为这个流而保持运行
+    fixture.env.run();
+    assert_eq!(fixture.scanned(), before);
+
+    for expected in 0..3 {
+        let promise = fixture.method(iterator, "next");
+        fixture.env.run();
+        assert_eq!(fixture.fulfilled(promise), Some(expected as f64));
+        assert_eq!(fixture.scanned(), before + expected as f64 + 1.0);
+    }
+
+    let promises = [
+        fixture.method(iterator, "next"),
+        fixture.method(iterator, "next"),
+    ];
+    fixture.env.run();
+    assert_eq!(fixture.fulfilled(promises[1]), Some(4.0));
+    assert_eq!(fixture.scanned(), before + 5.0);
+}
+
+#[test]
+fn return_stops_the_producer() {
+    let _serial = serial();
+    let fixture = Fixture::new();
+    let before = fixture.scanned();
+    let iterator = fixture.scan(100.0, None);
+
+    let first = fixture.method(iterator, "next");
+    fixture.env.run();
+    assert_eq!(fixture.fulfilled(first), Some(0.0));
+
+    // return() 兑现所有还在等待的 next()，之后生产者不再 poll
+    let pending = fixture.method(iterator, "next");
+    let closed = fixture.method(iterator, "return");
+    assert_eq!(fixture.fulfilled(pending), None);
+    assert_eq!(fixture.fulfilled(closed), None);
+    fixture.env.run();
+
+    let after = fixture.method(iterator, "next");
+    assert_eq!(fixture.fulfilled(after), None);
+    fixture.env.run();
+    assert!(fixture.scanned() <= before + 2.0);
+    let scanned = fixture.scanned();
+    fixture.env.run();
+    assert_eq!(fixture.scanned(), scanned);
+}
+
+#[test]
+fn errors_reject_next_and_end_the_stream() {
+    let _serial = serial();
+    let fixture = Fixture::new();
+    let before = fixture.scanned();
+    let iterator = fixture.scan(10.0, Some(1.0));
+
+    let first = fixture.method(iterator, "next");
+    let second = fixture.method(iterator, "next");
+    fixture.env.run();
+    assert_eq!(fixture.fulfilled(first), Some(0.0));
+    let PromiseState::Rejected(error) = fixture.env.promise_state(second) else {
+        panic!("expected the second next() to reject");
+    };
+    let field = |name| {
+        fixture
+            .env
+            .get_string(fixture.env.get_named_property(error, name))
+    };
+    assert_eq!(field("name"), "RangeError");
+    assert_eq!(field("code"), "ERR_SCAN");
+    assert_eq!(field("message"), "failed to scan item 1");
+    assert!(fixture.env.take_uncaught().is_empty());
+
+    // 和 async generator 一样，出错之后迭代已经结束，生产者不再 poll
+    let after = fixture.method(iterator, "next");
+    assert_eq!(fixture.fulfilled(after), None);
+    fixture.env.run();
+    assert_eq!(fixture.scanned(), before + 2.0);
+}
@@ -0,0 +1,53 @@
+mod common;
+
+use common::env;
+
+#[test]
+fn consts_and_statics_are_exported_as_values() {
+    let env = env();
+    let exports = env.load_module(apisecond::napi_register_module_v1);
+    assert_eq!(
+        env.get_string(env.get_named_property(exports, "VERSION")),
+        env!("CARGO_PKG_VERSION")
+    );
+    let limits = env.get_named_property(exports, "SCALE_LIMITS");
+    assert_eq!(env.array_length(limits), 2);
+    assert_eq!(env.get_number(env.get_element(limits, 0)), 0.0);
+    assert_eq!(env.get_number(env.get_element(limits, 1)), 1024.0);
+    // 常量是可枚举的
+    let names = env.property_names(exports);
+    assert!(names.iter().any(|name| name == "VERSION"));
+    assert!(names.iter().any(|name| name == "SCALE_LIMITS"));
+}
+
+#[test]
+fn writing_to_an_exported_value_does_not_change_it() {
+    let env = env();
+    let exports = env.load_module(apisecond::napi_register_module_v1);
+    let limits = env.get_named_property(exports, "SCALE_LIMITS");
+
+    env.set_named_property(exports, "VERSION", env.create_string("0.0.0"));
+    env.set_named_property(exports, "SCALE_LIMITS", env.get_null());
+    assert_eq!(
+        env.get_string(env.get_named_property(exports, "VERSION")),
+        env!("CARGO_PKG_VERSION")
+    );
+    assert!(env.same_value(env.get_named_property(exports, "SCALE_LIMITS"), limits));
+
+    // 函数是普通的属性，可以被替换
+    let replacement = env.create_object();
+    env.set_named_property(exports, "add", replacement);
+    assert!(env.same_value(env.get_named_property(exports, "add"), replacement));
+}
+
+#[test]
+fn each_env_gets_its_own_values() {
+    let (first, second) = (env(), env());
+    let first_exports = first.load_module(apisecond::napi_register_module_v1);
+    let second_exports = second.load_module(apisecond::napi_register_module_v1);
+    // 修改一个 env 中数组的元素不影响另一个 env
+    let limits = first.get_named_property(first_exports, "SCALE_LIMITS");
+    first.set_named_property(limits, "0", first.create_number(-1.0));
+    let other = second.get_named_property(second_exports, "SCALE_LIMITS");
+    assert_eq!(second.get_number(second.get_element(other, 0)), 0.0);
+}
@@ -0,0 +1,35 @@
+use proc_macro2::TokenStream;
+use syn::parse::Parser;
+use syn::{Expr, Ident, Path};
+
+// #[api(...)] 中可以使用的参数
+#[derive(Default)]
+pub(crate) struct ApiAttr {
+    // default(name = value, ...)：JS 没有传入对应参数时使用的默认值
+    pub(crate) defaults: Vec<(Ident, Expr)>,
+    // async：在 libuv 线程池中执行函数，返回 Promise。保存路径本身用于报错时定位
+    pub(crate) asyncness: Option<Path>,
+}
+
+pub(crate) fn parse_attr(attr: TokenStream) -> syn::Result<ApiAttr> {
+    let mut api_attr = ApiAttr::default();
+    let parser = syn::meta::parser(|meta| {
+        if meta.path.is_ident("async") {
+            api_attr.asyncness = Some(meta.path);
+            Ok(())
+        } else if meta.path.is_ident("default") {
+            meta.parse_nested_meta(|nested| {
+                let name = nested.path.require_ident()?.clone();
+                let value = nested.value()?.parse::<Expr>()?;
+                api_attr.defaults.push((name, value));
+                Ok(())
+            })
+        } else {
+            Err(meta.error(
+                "unsupported #[api] argument, expected `async` or `default(name = value, ...)`",
+            ))
+        }
+    });
+    parser.parse2(attr)?;
+    Ok(api_attr)
+}
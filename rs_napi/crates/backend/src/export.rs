@@ -0,0 +1,103 @@
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{Attribute, Ident, ItemConst, ItemStatic, StaticMutability, Type};
+
+use crate::{attr, module_init, typedef};
+
+// #[api] const NAME: T = ...;
+pub(crate) fn expand_const(api_attr: attr::ApiAttr, item: ItemConst) -> syn::Result<TokenStream2> {
+    check_attr(&api_attr, &item.ident)?;
+    if !item.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &item.generics,
+            "#[api] does not support generic constants",
+        ));
+    }
+    let register = register_value(&item.attrs, &item.ident, &item.ty)?;
+    Ok(quote! {
+        #item
+        #register
+    })
+}
+
+// #[api] static NAME: T = ...;
+pub(crate) fn expand_static(
+    api_attr: attr::ApiAttr,
+    item: ItemStatic,
+) -> syn::Result<TokenStream2> {
+    check_attr(&api_attr, &item.ident)?;
+    // 导出的属性是只读的，而且读取 static mut 需要 unsafe，所以不支持
+    if let StaticMutability::Mut(mutability) = &item.mutability {
+        return Err(syn::Error::new_spanned(
+            mutability,
+            "#[api] does not support `static mut`, exported values are read-only",
+        ));
+    }
+    let register = register_value(&item.attrs, &item.ident, &item.ty)?;
+    Ok(quote! {
+        #item
+        #register
+    })
+}
+
+// default(...) 和 async 只对函数有意义
+fn check_attr(api_attr: &attr::ApiAttr, name: &Ident) -> syn::Result<()> {
+    if let Some(asyncness) = &api_attr.asyncness {
+        return Err(syn::Error::new_spanned(
+            asyncness,
+            format!(
+                "`async` is only supported on functions, `{}` is a value",
+                name
+            ),
+        ));
+    }
+    match api_attr.defaults.first() {
+        Some((ident, _)) => Err(syn::Error::new_spanned(
+            ident,
+            format!(
+                "`default` is only supported on functions, `{}` is a value",
+                name
+            ),
+        )),
+        None => Ok(()),
+    }
+}
+
+// 在加载时把值的一份拷贝注册下来，模块初始化时会以只读属性的形式定义到 exports 上
+fn register_value(attrs: &[Attribute], name: &Ident, ty: &Type) -> syn::Result<TokenStream2> {
+    let doc = typedef::doc_comment(attrs);
+    typedef::write_type_def(
+        name,
+        &typedef::const_decl(&name.to_string(), &doc, &typedef::ts_type(ty)),
+    )?;
+
+    // &str、&[T] 这类借用的值先转换成对应的 owned 类型（String、Vec<T>）再交给 NapiValue
+    let (owned_ty, value) = match ty {
+        Type::Reference(r) => {
+            let elem = &r.elem;
+            (
+                quote! { <#elem as ToOwned>::Owned },
+                quote! { <#elem as ToOwned>::to_owned(#name) },
+            )
+        }
+        _ => (
+            quote! { #ty },
+            quote! { <#ty as ToOwned>::to_owned(&#name) },
+        ),
+    };
+
+    let name_str = name.to_string();
+    let init_js_value = Ident::new(&format!("_napi_{}", name), proc_macro2::Span::call_site());
+    let init = module_init();
+    Ok(quote! {
+        #init
+
+        #[ctor::ctor]
+        #[allow(non_snake_case)]
+        fn #init_js_value() {
+            crate::register::register_value(#name_str, |env| {
+                <#owned_ty as crate::value::NapiValue>::into_js_value(#value, env)
+            });
+        }
+    })
+}
@@ -0,0 +1,141 @@
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{Attribute, Data, DeriveInput, Fields, Ident, LitStr, Member};
+
+// 变体上的 #[js_error(kind = "...", code = "...")]
+struct VariantAttr {
+    kind: Ident,
+    code: Option<LitStr>,
+}
+
+// #[derive(JsError)]：为错误枚举生成 From<E> for crate::error::Error。
+// 消息来自 Display，JS 错误类和 err.code 由每个变体上的 #[js_error(...)] 决定，
+// 标注了 #[js_error(cause)] 的字段会成为 err.cause
+pub(crate) fn expand(input: TokenStream2) -> syn::Result<TokenStream2> {
+    let input = syn::parse2::<DeriveInput>(input)?;
+    let Data::Enum(data) = &input.data else {
+        return Err(syn::Error::new_spanned(
+            &input.ident,
+            "#[derive(JsError)] can only be applied to enums",
+        ));
+    };
+
+    let name = &input.ident;
+    let mut errors = Vec::<syn::Error>::new();
+    let arms = data
+        .variants
+        .iter()
+        .filter_map(|variant| {
+            let attr = parse_variant_attr(&variant.attrs)
+                .map_err(|err| errors.push(err))
+                .ok()?;
+            let cause = find_cause(&variant.fields)
+                .map_err(|err| errors.push(err))
+                .ok()?;
+
+            let ident = &variant.ident;
+            let kind = &attr.kind;
+            let code = attr.code.map(|code| quote! { .with_code(#code) });
+            // 用 `E::V { 0: cause, .. }` 的写法同时匹配元组、具名和单元变体
+            let (pattern, with_cause) = match cause {
+                Some(member) => (
+                    quote! { #name::#ident { #member: cause, .. } },
+                    Some(quote! { .with_cause(cause) }),
+                ),
+                None => (quote! { #name::#ident { .. } }, None),
+            };
+            Some(quote! {
+                #pattern => crate::error::Error::new(crate::error::ErrorKind::#kind, message)
+                    #code
+                    #with_cause,
+            })
+        })
+        .collect::<Vec<_>>();
+    if let Some(err) = errors.into_iter().reduce(|mut acc, err| {
+        acc.combine(err);
+        acc
+    }) {
+        return Err(err);
+    }
+
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    // 没有变体的枚举不存在值，match 需要一个分支才能通过类型检查
+    let body = if arms.is_empty() {
+        quote! { match err {} }
+    } else {
+        quote! {
+            let message = ::std::string::ToString::to_string(&err);
+            match err {
+                #(#arms)*
+            }
+        }
+    };
+    Ok(quote! {
+        impl #impl_generics ::core::convert::From<#name #ty_generics> for crate::error::Error #where_clause {
+            fn from(err: #name #ty_generics) -> Self {
+                #body
+            }
+        }
+    })
+}
+
+fn parse_variant_attr(attrs: &[Attribute]) -> syn::Result<VariantAttr> {
+    let mut variant_attr = VariantAttr {
+        kind: Ident::new("Error", proc_macro2::Span::call_site()),
+        code: None,
+    };
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("js_error")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("kind") {
+                let kind = meta.value()?.parse::<LitStr>()?;
+                match kind.value().as_str() {
+                    "Error" | "TypeError" | "RangeError" | "SyntaxError" | "AbortError" => {
+                        variant_attr.kind = Ident::new(&kind.value(), kind.span());
+                        Ok(())
+                    }
+                    _ => Err(syn::Error::new_spanned(
+                        kind,
+                        "unsupported error kind, expected one of `Error`, `TypeError`, `RangeError`, `SyntaxError`, `AbortError`",
+                    )),
+                }
+            } else if meta.path.is_ident("code") {
+                variant_attr.code = Some(meta.value()?.parse::<LitStr>()?);
+                Ok(())
+            } else {
+                Err(meta.error(
+                    "unsupported #[js_error] argument, expected `kind = \"...\"` or `code = \"...\"`",
+                ))
+            }
+        })?;
+    }
+    Ok(variant_attr)
+}
+
+// 找到标注了 #[js_error(cause)] 的字段，每个变体最多只能有一个
+fn find_cause(fields: &Fields) -> syn::Result<Option<Member>> {
+    let mut cause = None;
+    for (index, field) in fields.iter().enumerate() {
+        for attr in field
+            .attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("js_error"))
+        {
+            attr.parse_nested_meta(|meta| {
+                if !meta.path.is_ident("cause") {
+                    return Err(
+                        meta.error("unsupported #[js_error] argument on a field, expected `cause`")
+                    );
+                }
+                if cause.is_some() {
+                    return Err(meta.error("only one field can be marked as #[js_error(cause)]"));
+                }
+                cause = Some(match &field.ident {
+                    Some(ident) => Member::Named(ident.clone()),
+                    None => Member::Unnamed(index.into()),
+                });
+                Ok(())
+            })?;
+        }
+    }
+    Ok(cause)
+}
@@ -1,69 +1,95 @@
 use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
 use quote::quote;
-use std::ops::Deref;
 use std::sync::atomic::{AtomicBool, Ordering};
-use syn::{ItemFn, Pat::Ident, Type};
+use syn::{
+    Expr, FnArg, GenericParam, Item, ItemConst, ItemFn, ItemStatic, Meta, Pat, ReturnType,
+    Signature, Type,
+};
+
+mod attr;
+mod export;
+mod js_error;
+mod typedef;
 
 struct NapiFnArgs {
-    _ident: syn::Ident,
+    // 参数名，用于类型声明和 __meta；元组、通配符等非标识符模式使用 arg{index}
+    name: String,
     ty: Type,
+    // 是否为 CallContext 参数，这个参数由宏传入，不对应任何 JS 实参
+    is_ctx: bool,
+    // 是否为 CancellationToken 参数，对应的 JS 实参是一个可选的 AbortSignal
+    is_token: bool,
+    // 是否为 #[rest] 参数，收集剩余的所有 JS 实参
+    is_rest: bool,
+    // JS 没有传入这个参数（或传入 undefined）时使用的默认值
+    default: Option<Expr>,
 }
 
 // 声明原子操作 用于确保当前为第一个宏展开
 static REGISTER_INIT: AtomicBool = AtomicBool::new(false);
 
 #[proc_macro_attribute]
-pub fn api(_attr: TokenStream, input: TokenStream) -> TokenStream {
-    let ast = syn::parse::<ItemFn>(input).unwrap();
+pub fn api(attr: TokenStream, input: TokenStream) -> TokenStream {
+    // 所有错误都转换成带有位置信息的 compile_error!，而不是让编译器 panic
+    expand(attr.into(), input.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+// 把错误枚举映射为 JS 的 Error 子类，#[api] 函数返回 Result<T, E> 时 Err 会以对应的类抛出
+#[proc_macro_derive(JsError, attributes(js_error))]
+pub fn derive_js_error(input: TokenStream) -> TokenStream {
+    js_error::expand(input.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+// 为 Json<T> 中使用的结构体生成 TypeScript interface，只在生成声明文件时有作用，不生成任何代码
+#[proc_macro_derive(TypeDef)]
+pub fn derive_type_def(input: TokenStream) -> TokenStream {
+    typedef::expand_interface(input.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn expand(attr: TokenStream2, input: TokenStream2) -> syn::Result<TokenStream2> {
+    let api_attr = attr::parse_attr(attr)?;
+    match parse_item(input)? {
+        ApiItem::Fn(ast) => expand_fn(api_attr, ast),
+        ApiItem::Const(item) => export::expand_const(api_attr, item),
+        ApiItem::Static(item) => export::expand_static(api_attr, item),
+    }
+}
+
+// 为函数生成 extern "C" 包装函数，并在加载时注册到 exports 上
+fn expand_fn(api_attr: attr::ApiAttr, mut ast: ItemFn) -> syn::Result<TokenStream2> {
+    check_sig(&ast.sig)?;
+    let mut args = parse_args(&ast.sig)?;
+    let is_async = api_attr.asyncness.is_some();
+    if let (Some(_), Some(asyncness)) = (&api_attr.asyncness, &ast.sig.asyncness) {
+        return Err(syn::Error::new_spanned(
+            asyncness,
+            "`async fn` already returns a Promise, remove `async` from #[api(async)]",
+        ));
+    }
+    check_async(&args, is_async, ast.sig.asyncness.is_some())?;
+    let iter = check_return(&ast.sig.output, is_async || ast.sig.asyncness.is_some())?;
+    apply_defaults(&mut args, api_attr.defaults)?;
+    // #[rest]、#[default = ...] 只是给宏看的标记，生成原函数时要去掉，否则编译器不认识这些属性
+    ast.sig.inputs.iter_mut().for_each(|arg| {
+        if let FnArg::Typed(p) = arg {
+            p.attrs
+                .retain(|attr| !attr.path().is_ident("rest") && !attr.path().is_ident("default"));
+        }
+    });
 
     // 函数名
     let name = &ast.sig.ident;
     let org_name_str = quote! {#name}.to_string();
 
-    // 通过REGISTER_INIT.load(Ordering::SeqCst)检查REGISTER_INIT的值。
-    // 这里使用的Ordering::SeqCst保证了这个操作在多线程环境下的内存顺序性，确保这个操作看起来是在一个单一的、全局的操作序列中执行的。
-    let init = match REGISTER_INIT.load(Ordering::SeqCst) {
-        // 如果REGISTER_INIT的值为false，表示模块尚未注册，那么就执行注册逻辑。
-        false => {
-            // 过REGISTER_INIT.store(true, Ordering::SeqCst)将REGISTER_INIT的值设置为true，以防止后续的重复注册。
-            REGISTER_INIT.store(true, Ordering::SeqCst);
-            quote! {
-                // 代码定义了一个名为napi_register_module_v1的外部"C"函数，这个函数是Node.js原生模块注册的入口点
-                unsafe extern "C" fn napi_register_module_v1(
-                    env: sys::napi_env,
-                    exports: sys::napi_value,
-                ) -> sys::napi_value {
-                    let desc = crate::register::gen_fn(env,exports);
-                    exports
-                }
-                #[ctor::ctor]
-                fn init() {
-                    let name = std::ffi::CString::new("api").unwrap();
-                    let mut modules = sys::napi_module {
-                        nm_version: 1,
-                        nm_filename: std::ptr::null_mut(),
-                        nm_flags: 0,
-                        nm_modname: name.as_ptr().cast(),
-                        nm_priv: std::ptr::null_mut() as *mut _,
-                        nm_register_func: Some(napi_register_module_v1),
-                        reserved: [std::ptr::null_mut() as *mut _; 4],
-                    };
-                    unsafe {
-                        // 并通过sys::napi_module_register函数将其注册到Node.js环境中。
-                        sys::napi_module_register(&mut modules);
-                    };
-                }
-            }
-        }
-        _ => {
-            quote!()
-        }
-    };
-
     // ast.sig获取函数的签名
     let sig = &ast.sig;
-    // 获取函数参数
-    let params = &ast.sig.inputs;
     // 获取函数返回值
     let result = &ast.sig.output;
     // 获取函数块
@@ -75,47 +101,118 @@ pub fn api(_attr: TokenStream, input: TokenStream) -> TokenStream {
         syn::ReturnType::Default => quote! { () },
     };
 
-    // 生成原始函数签名
-    let org_sig = quote! { #sig };
+    // 生成原始函数签名，保留原函数上的属性（包括 /// 文档注释）和可见性
+    let attrs = &ast.attrs;
+    let vis = &ast.vis;
+    let org_sig = quote! { #(#attrs)* #vis #sig };
 
     // 生成原始函数块
     let org_block = quote! { #fn_blocks };
 
-    // 生成原始函数参数
-    let args = params
+    // 生成原始函数参数的长度，CallContext 和 #[rest] 参数不计算在内
+    let arg_cnt = args
         .iter()
-        .filter_map(|arg| match arg {
-            // ref关键字用于在模式匹配中创建对匹配值的引用，而不是获取其所有权。这在处理不想获取所有权但需要访问数据的场景中非常有用。
-            // ref p表示创建一个名为p的变量，它是对匹配到的值的引用，而不是值本身的所有权。这里的p是对Typed变体中包含的值的引用。
-            syn::FnArg::Typed(ref p) => {
-                // 里的ident是对Ident中包含的值的引用。
-                // 在syn库中，Ident是用来表示Rust程序中的标识符的类型。
-                // syn::Pat::Ident包含了关于标识符的信息，比如它的名称。
-                if let Ident(ref ident) = *p.pat {
-                    Some(NapiFnArgs {
-                        _ident: ident.ident.clone(),
-                        ty: p.ty.clone().deref().clone(),
-                    })
-                } else {
-                    None
-                }
+        .filter(|arg| !arg.is_ctx && !arg.is_rest)
+        .count();
+
+    // 记录导出函数的文档注释、参数名、参数类型和返回值类型，用于生成 TypeScript 声明文件和运行时的 __meta
+    let doc = typedef::doc_comment(attrs);
+    let js_params = args.iter().filter(|arg| !arg.is_ctx).collect::<Vec<_>>();
+    let ts_params = js_params
+        .iter()
+        .enumerate()
+        .map(|(index, arg)| {
+            // 只有后面全是可选参数（或者 #[rest] 参数）的 Option<T> 和带默认值的参数，
+            // 才能在 TS 中声明为 name?: T
+            let optional = !arg.is_rest
+                && js_params[index..].iter().all(|arg| {
+                    arg.is_rest
+                        || arg.is_token
+                        || arg.default.is_some()
+                        || typedef::option_inner(&arg.ty).is_some()
+                });
+            let ty = match typedef::option_inner(&arg.ty) {
+                Some(inner) if optional => typedef::ts_type(inner),
+                _ => typedef::ts_type(&arg.ty),
+            };
+            typedef::TsParam {
+                name: arg.name.clone(),
+                ty,
+                optional,
+                rest: arg.is_rest,
             }
-            syn::FnArg::Receiver(ref _p) => None,
         })
-        .collect::<Vec<NapiFnArgs>>();
-
-    // 生成原始函数参数的长度
-    let arg_cnt = args.len();
+        .collect::<Vec<_>>();
+    let ts_ret = typedef::ts_return(result, sig.asyncness.is_some() || is_async);
+    typedef::write_type_def(
+        name,
+        &typedef::fn_decl(&org_name_str, &doc, &ts_params, &ts_ret),
+    )?;
+    let meta_params = ts_params.iter().map(|param| {
+        let name = &param.name;
+        let ty = &param.ty;
+        let optional = param.optional;
+        let rest = param.rest;
+        quote! {
+            crate::meta::ParamMeta { name: #name, ty: #ty, optional: #optional, rest: #rest }
+        }
+    });
 
     // 这段Rust代码是在一个宏定义中使用的，它的目的是将Rust函数的参数转换为Node.js的N-API值。
-    let js_args = args.iter().enumerate().map(|(index, &ref ident)| {
+    // JS 实参的下标跳过 CallContext 参数，局部变量 arg_N 的下标仍然对应原函数参数的位置
+    let js_args = args
+        .iter()
+        .enumerate()
+        .filter(|(_, arg)| !arg.is_ctx)
+        .enumerate()
+        .map(|(index, (position, ident))| {
         let arg = syn::Ident::new(
-            format!("arg_{}", index).as_str(),
+            format!("arg_{}", position).as_str(),
             proc_macro2::Span::call_site(),
         );
-        let ty = &ident.ty.clone();
-        quote! {
-            let #arg = <#ty as crate::value::NapiValue>::get_value_from_raw(env,args[#index]);
+        match &ident.ty {
+            // CancellationToken 由 async_work::spawn 创建，这里只读取用来取消它的 AbortSignal
+            _ if ident.is_token => quote! {
+                let signal = crate::async_work::abort_signal(ctx.arg(#index));
+            },
+            // #[rest] 参数收集从这个位置开始的所有实参，JS 实际传入多少个就收集多少个
+            ty if ident.is_rest => quote! {
+                let #arg: #ty = ctx.rest(#index);
+            },
+            // 有默认值的参数：JS 没有传入时计算默认值表达式，借用参数的默认值转换成拥有所有权的值后再借用
+            Type::Reference(r) if ident.default.is_some() => {
+                let elem = &r.elem;
+                let mutability = &r.mutability;
+                let default = &ident.default;
+                quote! {
+                    let #mutability #arg: <#elem as std::borrow::ToOwned>::Owned = if ctx.has_arg(#index) {
+                        crate::value::NapiValue::from_js_value(ctx.arg(#index))
+                    } else {
+                        <#elem as std::borrow::ToOwned>::to_owned(#default)
+                    };
+                }
+            }
+            ty if ident.default.is_some() => {
+                let default = &ident.default;
+                quote! {
+                    let #arg: #ty = if ctx.has_arg(#index) {
+                        crate::value::NapiValue::from_js_value(ctx.arg(#index))
+                    } else {
+                        #default
+                    };
+                }
+            }
+            // 借用参数（例如 &str、&[T]）先转换成对应的拥有所有权的值（String、Vec<T>），调用时再借用
+            Type::Reference(r) => {
+                let elem = &r.elem;
+                let mutability = &r.mutability;
+                quote! {
+                    let #mutability #arg = <<#elem as std::borrow::ToOwned>::Owned as crate::value::NapiValue>::from_js_value(ctx.arg(#index));
+                }
+            }
+            ty => quote! {
+                let #arg = <#ty as crate::value::NapiValue>::from_js_value(ctx.arg(#index));
+            },
         }
     });
 
@@ -131,7 +228,7 @@ pub fn api(_attr: TokenStream, input: TokenStream) -> TokenStream {
         proc_macro2::Span::call_site(),
     );
 
-    let run_args = args.iter().enumerate().map(|(index, _ident)| {
+    let run_args = args.iter().enumerate().map(|(index, ident)| {
         // 使用 format!("arg_{}", index) 来创建一个新的字符串，该字符串以 "arg_" 开头，后跟元素的索引。这个字符串用于创建一个新的 syn::Ident 实例，表示一个标识符。syn::Ident::new 函数的第一个参数是标识符的名称，第二个参数是一个 Span，在这里使用 proc_macro2::Span::call_site() 来获取调用宏的位置。
 
         // syn::Ident::new 函数返回一个 Ident 类型的实例，这个实例可以在宏的输出中被用作变量名、函数名等标识符。
@@ -139,11 +236,71 @@ pub fn api(_attr: TokenStream, input: TokenStream) -> TokenStream {
             format!("arg_{}", index).as_str(),
             proc_macro2::Span::call_site(),
         );
-        quote! {
-           #arg
+        match &ident.ty {
+            _ if ident.is_ctx => quote! { ctx },
+            _ if ident.is_token => quote! { token },
+            Type::Reference(r) if r.mutability.is_some() => quote! { &mut #arg },
+            Type::Reference(_) => quote! { &#arg },
+            _ => quote! { #arg },
         }
     });
 
+    // 导出的种类，和 exports.__registry 中的 kind 对应
+    let kind = if sig.asyncness.is_some() || is_async {
+        quote! { Async }
+    } else if iter.is_some() {
+        quote! { Iterator }
+    } else {
+        quote! { Function }
+    };
+
+    // async 函数把转换好的参数移动到线程池中执行，async fn 把它们移动到 Future 中交给执行器，
+    // 两者都立即返回一个 Promise；普通函数直接调用，并把返回值转换成 JS 值
+    let call = if sig.asyncness.is_some() {
+        quote! {
+            crate::runtime::spawn_future(env, async move { #name(#(#run_args),*).await }).into()
+        }
+    } else if is_async {
+        let has_token = args.iter().any(|arg| arg.is_token);
+        let signal = if has_token {
+            quote! { signal }
+        } else {
+            quote! { None }
+        };
+        let token = syn::Ident::new(
+            if has_token { "token" } else { "_token" },
+            proc_macro2::Span::call_site(),
+        );
+        quote! {
+            crate::async_work::spawn(
+                env,
+                #signal,
+                move |#token: crate::async_work::CancellationToken| -> #ret_ty {
+                    #name(#(#run_args),*)
+                },
+            )
+            .into()
+        }
+    } else if let Some(iter) = iter {
+        // 返回的迭代器交给执行器，JS 每调用一次 next() 才取下一个元素
+        let from = if iter == "Stream" {
+            quote! { from_stream }
+        } else {
+            quote! { from_iter }
+        };
+        quote! {
+            let ret = #name(#(#run_args),*);
+            crate::stream::#from(env, ret).into()
+        }
+    } else {
+        quote! {
+            let ret = #name(#(#run_args),*);
+            <#ret_ty as crate::value::NapiValue>::into_js_value(ret, env)
+        }
+    };
+
+    let init = module_init();
+
     //，quote! { ... }; 用于在宏中生成代码，
     // # 符号用于插入变量值。
     // 展示了如何使用quote!宏来生成包含不安全外部函数的Rust代码，
@@ -158,29 +315,21 @@ pub fn api(_attr: TokenStream, input: TokenStream) -> TokenStream {
             env: sys::napi_env,
             callback: sys::napi_callback_info,
         ) -> sys::napi_value {
-            unsafe {
-                let mut args = [std::ptr::null_mut(); #arg_cnt];
-                // 调用sys::napi_get_cb_info函数来填充这个数组，这个函数从Node.js环境中获取回调信息，包括传递给函数的参数。
-                sys::napi_get_cb_info(
-                    env,
-                    callback,
-                    &mut #arg_cnt,
-                    args.as_mut_ptr(),
-                    std::ptr::null_mut(),
-                    std::ptr::null_mut(),
-                );
+            // 参数转换、函数调用和返回值转换都放在 catch 中执行，panic 会变成一个抛给 JS 的 Error。
+            // 闭包拿到的 env 以及由它创建的 JS 值只在这次调用中有效
+            crate::unwind::catch(env, |env| unsafe {
+                // CallContext::new 内部调用sys::napi_get_cb_info，从Node.js环境中获取回调信息，包括传递给函数的参数、this 和 data。
+                let ctx = crate::context::CallContext::new(env, callback, #arg_cnt);
 
                 // #(#js_args)*是一个宏替换片段，用于处理或转换JavaScript传递过来的参数。
                 #(#js_args)*
 
                 //用另一个Rust函数（或可能是同一个函数的不同部分），这个函数执行实际的逻辑处理，并返回一个结果。
-                let ret = #name(#(#run_args),*);
-
                 // 将处理结果转换为N-API可以识别的值类型，以便将结果返回给JavaScript环境。
                 // 这里#ret_ty是返回值的类型，
-                // try_into_raw方法负责将Rust类型转换为N-API的值类型。
-                <#ret_ty as crate::value::NapiValue>::try_into_raw(env,ret)
-            }
+                // into_js_value方法负责将Rust类型转换为JS值。
+                #call
+            })
         }
 
         // 函数#init_js_fn()（这里的#init_js_fn是一个占位符，表示实际的函数名将在宏展开时被替换）的主要任务是调用crate::register::register_fn函数。这个调用传递了两个参数：#org_name_str和Some(#js_name)。这里的#org_name_str和#js_name同样是占位符，分别代表原始函数名的字符串表示和一个可能的JavaScript函数名
@@ -190,9 +339,358 @@ pub fn api(_attr: TokenStream, input: TokenStream) -> TokenStream {
         // 这段代码的目的是在程序启动时自动注册一个Rust函数，使其可以被JavaScript代码调用。这是在Rust中创建Node.js本地扩展的常见步骤之一，允许开发者利用Rust的性能优势在Node.js应用中执行高效的后端逻辑。
         #[ctor::ctor]
         fn #init_js_fn() {
-            crate::register::register_fn(#org_name_str,Some(#js_name));
+            crate::register::register_fn(
+                #org_name_str,
+                Some(#js_name),
+                crate::meta::FnMeta {
+                    kind: crate::meta::ExportKind::#kind,
+                    doc: #doc,
+                    params: &[#(#meta_params),*],
+                    ret: #ts_ret,
+                },
+            );
         }
     };
 
-    expanded.into()
+    Ok(expanded)
+}
+
+// 第一个展开的 #[api] 负责生成模块的注册入口，之后的展开不再重复生成
+pub(crate) fn module_init() -> TokenStream2 {
+    // 通过REGISTER_INIT.load(Ordering::SeqCst)检查REGISTER_INIT的值。
+    // 这里使用的Ordering::SeqCst保证了这个操作在多线程环境下的内存顺序性，确保这个操作看起来是在一个单一的、全局的操作序列中执行的。
+    match REGISTER_INIT.load(Ordering::SeqCst) {
+        // 如果REGISTER_INIT的值为false，表示模块尚未注册，那么就执行注册逻辑。
+        false => {
+            // 过REGISTER_INIT.store(true, Ordering::SeqCst)将REGISTER_INIT的值设置为true，以防止后续的重复注册。
+            REGISTER_INIT.store(true, Ordering::SeqCst);
+            quote! {
+                // 代码定义了一个名为napi_register_module_v1的外部"C"函数，这个函数是Node.js原生模块注册的入口点
+                // 导出这个符号后，没有调用 napi_module_register 的宿主也可以通过 dlsym 找到它
+                #[no_mangle]
+                pub unsafe extern "C" fn napi_register_module_v1(
+                    env: sys::napi_env,
+                    exports: sys::napi_value,
+                ) -> sys::napi_value {
+                    // 注册过程中的 panic 同样不能展开到 JS 引擎中
+                    crate::unwind::catch(env, |env| {
+                        let exports = crate::js_value::JsObject::from_unchecked(
+                            unsafe { crate::js_value::JsValue::from_raw(env, exports) },
+                        );
+                        crate::register::gen_fn(env, exports);
+                        exports.into()
+                    })
+                }
+                #[ctor::ctor]
+                fn init() {
+                    // 动态加载模式下此时还没有宿主可以注册，交给宿主调用 napi_register_module_v1
+                    if sys::DYNAMIC_LOADING {
+                        return;
+                    }
+                    // Node.js 只保存 napi_module 的指针，在 dlopen 返回后才调用注册函数，
+                    // 所以模块描述和名称都必须一直有效，不能放在栈上
+                    let modules = Box::leak(Box::new(sys::napi_module {
+                        nm_version: 1,
+                        nm_filename: std::ptr::null_mut(),
+                        nm_flags: 0,
+                        nm_modname: b"api\0".as_ptr().cast(),
+                        nm_priv: std::ptr::null_mut() as *mut _,
+                        nm_register_func: Some(napi_register_module_v1),
+                        reserved: [std::ptr::null_mut() as *mut _; 4],
+                    }));
+                    unsafe {
+                        // 并通过sys::napi_module_register函数将其注册到Node.js环境中。
+                        sys::napi_module_register(modules);
+                    };
+                }
+            }
+        }
+        _ => {
+            quote!()
+        }
+    }
+}
+
+// #[api] 可以标注的条目：函数、常量和静态变量
+enum ApiItem {
+    Fn(ItemFn),
+    Const(ItemConst),
+    Static(ItemStatic),
+}
+
+// 解析被标注的条目
+fn parse_item(input: TokenStream2) -> syn::Result<ApiItem> {
+    let message = "#[api] can only be applied to functions, constants and statics";
+    match syn::parse2::<Item>(input)? {
+        Item::Fn(item) => Ok(ApiItem::Fn(item)),
+        Item::Const(item) => Ok(ApiItem::Const(item)),
+        Item::Static(item) => Ok(ApiItem::Static(item)),
+        Item::Struct(item) => Err(syn::Error::new_spanned(item.struct_token, message)),
+        Item::Enum(item) => Err(syn::Error::new_spanned(item.enum_token, message)),
+        Item::Union(item) => Err(syn::Error::new_spanned(item.union_token, message)),
+        Item::Type(item) => Err(syn::Error::new_spanned(item.type_token, message)),
+        Item::Trait(item) => Err(syn::Error::new_spanned(item.trait_token, message)),
+        Item::Impl(item) => Err(syn::Error::new_spanned(item.impl_token, message)),
+        Item::Mod(item) => Err(syn::Error::new_spanned(item.mod_token, message)),
+        item => Err(syn::Error::new_spanned(item, message)),
+    }
+}
+
+// 检查函数签名能否被导出给 JS 调用
+fn check_sig(sig: &Signature) -> syn::Result<()> {
+    if let Some(unsafety) = &sig.unsafety {
+        return Err(syn::Error::new_spanned(
+            unsafety,
+            "#[api] cannot export an `unsafe fn`: JS callers cannot uphold its safety contract, \
+             wrap the unsafe code in a safe function instead",
+        ));
+    }
+    if let Some(abi) = &sig.abi {
+        return Err(syn::Error::new_spanned(
+            abi,
+            "#[api] functions must use the Rust ABI: the generated wrapper is already the \
+             `extern \"C\"` entry point called by N-API",
+        ));
+    }
+    if let Some(variadic) = &sig.variadic {
+        return Err(syn::Error::new_spanned(
+            variadic,
+            "#[api] functions cannot be variadic",
+        ));
+    }
+    // 生命周期参数是允许的（例如借用参数），类型参数和常量参数则无法从 JS 值推导出来
+    if let Some(param) = sig
+        .generics
+        .params
+        .iter()
+        .find(|param| !matches!(param, GenericParam::Lifetime(_)))
+    {
+        return Err(syn::Error::new_spanned(
+            param,
+            "#[api] functions cannot have type or const parameters: \
+             every JS argument must convert into one concrete Rust type",
+        ));
+    }
+    Ok(())
+}
+
+// 解析所有参数并检查 CallContext、#[rest] 参数的位置和个数
+fn parse_args(sig: &Signature) -> syn::Result<Vec<NapiFnArgs>> {
+    // 生成原始函数参数，每个参数都必须保留下来，否则 arg_N 的下标和调用参数个数会对不上，
+    // 不支持的参数会生成带有对应位置信息的 compile_error!
+    let mut args = Vec::new();
+    let mut errors: Option<syn::Error> = None;
+    sig.inputs
+        .iter()
+        .enumerate()
+        .for_each(|(index, arg)| match parse_arg(index, arg) {
+            Ok(arg) => args.push(arg),
+            Err(err) => match errors.as_mut() {
+                Some(errors) => errors.combine(err),
+                None => errors = Some(err),
+            },
+        });
+    if let Some(errors) = errors {
+        return Err(errors);
+    }
+    if let Some(ctx) = args.iter().filter(|arg| arg.is_ctx).nth(1) {
+        return Err(syn::Error::new_spanned(
+            &ctx.ty,
+            "#[api] functions can take at most one `CallContext` parameter",
+        ));
+    }
+    if let Some(token) = args.iter().filter(|arg| arg.is_token).nth(1) {
+        return Err(syn::Error::new_spanned(
+            &token.ty,
+            "#[api] functions can take at most one `CancellationToken` parameter",
+        ));
+    }
+
+    if let Some(rest) = args
+        .iter()
+        .filter(|arg| !arg.is_ctx)
+        .rev()
+        .skip(1)
+        .find(|arg| arg.is_rest)
+    {
+        return Err(syn::Error::new_spanned(
+            &rest.ty,
+            "#[rest] can only be applied to the last parameter",
+        ));
+    }
+    Ok(args)
+}
+
+// #[api(async)] 函数在线程池中执行，async fn 在执行器的线程上执行，都拿不到只在 JS 线程上有效的
+// CallContext；CancellationToken 只能在 #[api(async)] 函数中被取消
+fn check_async(args: &[NapiFnArgs], is_async: bool, is_async_fn: bool) -> syn::Result<()> {
+    if let Some(ctx) = args
+        .iter()
+        .find(|arg| arg.is_ctx && (is_async || is_async_fn))
+    {
+        return Err(syn::Error::new_spanned(
+            &ctx.ty,
+            "async #[api] functions run on another thread and cannot take a `CallContext`",
+        ));
+    }
+    if let Some(token) = args.iter().find(|arg| arg.is_token && !is_async) {
+        return Err(syn::Error::new_spanned(
+            &token.ty,
+            "`CancellationToken` parameters are only supported by #[api(async)] functions",
+        ));
+    }
+    Ok(())
+}
+
+// 返回值只支持 impl Iterator<Item = T> 和 impl Stream<Item = T> 两种 impl Trait，
+// 它们本身就是异步迭代器，不能再放到 async 函数中返回。返回 trait 名
+fn check_return(output: &ReturnType, is_async: bool) -> syn::Result<Option<String>> {
+    let ReturnType::Type(_, ty) = output else {
+        return Ok(None);
+    };
+    if !matches!(**ty, Type::ImplTrait(_)) {
+        return Ok(None);
+    }
+    let Some((iter, _)) = typedef::impl_iter(ty) else {
+        return Err(syn::Error::new_spanned(
+            ty,
+            "only `impl Iterator<Item = T>` and `impl Stream<Item = T>` can be returned from #[api] functions",
+        ));
+    };
+    if is_async {
+        return Err(syn::Error::new_spanned(
+            ty,
+            format!(
+                "`impl {}` is already consumed asynchronously, return it from a synchronous #[api] function",
+                iter
+            ),
+        ));
+    }
+    Ok(Some(iter.to_string()))
+}
+
+// 把 #[api(default(...))] 中的默认值对应到参数上
+fn apply_defaults(args: &mut [NapiFnArgs], defaults: Vec<(syn::Ident, Expr)>) -> syn::Result<()> {
+    defaults.into_iter().try_for_each(|(name, value)| {
+        let Some(arg) = args.iter_mut().find(|arg| name == arg.name) else {
+            return Err(syn::Error::new_spanned(
+                &name,
+                format!("no parameter named `{}`", name),
+            ));
+        };
+        if arg.default.is_some() {
+            return Err(syn::Error::new_spanned(
+                &name,
+                format!("default value for `{}` is specified more than once", name),
+            ));
+        }
+        arg.default = Some(value);
+        check_default(arg, &name)
+    })
+}
+
+// CallContext、CancellationToken 和 #[rest] 参数总是有值，不能指定默认值
+fn check_default(arg: &NapiFnArgs, span: impl quote::ToTokens) -> syn::Result<()> {
+    if arg.default.is_some() && (arg.is_ctx || arg.is_token || arg.is_rest) {
+        return Err(syn::Error::new_spanned(
+            span,
+            "default values cannot be used with `CallContext`, `CancellationToken` or #[rest] parameters",
+        ));
+    }
+    Ok(())
+}
+
+// 解析单个参数，参数的模式可以是任意的（标识符、元组、通配符等），因为调用原函数时只按位置传参
+fn parse_arg(index: usize, arg: &FnArg) -> syn::Result<NapiFnArgs> {
+    match arg {
+        FnArg::Typed(p) => {
+            check_type(&p.ty)?;
+            let name = match &*p.pat {
+                Pat::Ident(ident) => ident.ident.to_string(),
+                _ => format!("arg{}", index),
+            };
+            let is_rest = p.attrs.iter().any(|attr| attr.path().is_ident("rest"));
+            if is_rest && !typedef::is_vec(&p.ty) {
+                return Err(syn::Error::new_spanned(
+                    &p.ty,
+                    "#[rest] parameters must have type `Vec<T>`",
+                ));
+            }
+            // 参数上的 #[default = value]
+            let default = p
+                .attrs
+                .iter()
+                .find(|attr| attr.path().is_ident("default"))
+                .map(|attr| match &attr.meta {
+                    Meta::NameValue(nv) => Ok((attr, nv.value.clone())),
+                    _ => Err(syn::Error::new_spanned(
+                        attr,
+                        "expected `#[default = value]`",
+                    )),
+                })
+                .transpose()?;
+            let arg = NapiFnArgs {
+                name,
+                ty: (*p.ty).clone(),
+                is_ctx: is_named(&p.ty, "CallContext"),
+                is_token: is_named(&p.ty, "CancellationToken"),
+                is_rest,
+                default: default.as_ref().map(|(_, value)| value.clone()),
+            };
+            if let Some((attr, _)) = default {
+                check_default(&arg, attr)?;
+            }
+            Ok(arg)
+        }
+        FnArg::Receiver(r) => Err(syn::Error::new_spanned(
+            r,
+            "#[api] functions cannot take `self`",
+        )),
+    }
+}
+
+// 检查参数类型能否从 JS 值转换得到
+fn check_type(ty: &Type) -> syn::Result<()> {
+    match ty {
+        Type::Reference(r) => match &*r.elem {
+            Type::Reference(_) => Err(syn::Error::new_spanned(
+                ty,
+                "#[api] parameters can only be borrowed once, e.g. `&str` or `&[T]`",
+            )),
+            elem => check_type(elem),
+        },
+        Type::ImplTrait(_) => Err(syn::Error::new_spanned(
+            ty,
+            "`impl Trait` parameters are not supported by #[api], use a concrete type",
+        )),
+        Type::TraitObject(_) => Err(syn::Error::new_spanned(
+            ty,
+            "trait object parameters are not supported by #[api], use a concrete type",
+        )),
+        Type::Infer(_) | Type::Never(_) | Type::Macro(_) | Type::Verbatim(_) => Err(
+            syn::Error::new_spanned(ty, "unsupported parameter type for #[api]"),
+        ),
+        // External<T> 交给 JS 之后就归 GC 所有，不能再从 JS 值中取回所有权
+        Type::Path(_) if is_named(ty, "External") => {
+            Err(syn::Error::new_spanned(
+                ty,
+                "`External<T>` can only be returned from #[api] functions, take an `ExternalRef<T>` parameter instead",
+            ))
+        }
+        Type::Paren(p) => check_type(&p.elem),
+        Type::Group(g) => check_type(&g.elem),
+        _ => Ok(()),
+    }
+}
+
+// 按类型名识别 CallContext、CancellationToken 这类由宏特殊处理的参数
+fn is_named(ty: &Type, name: &str) -> bool {
+    match ty {
+        Type::Path(p) => p
+            .path
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == name),
+        _ => false,
+    }
 }
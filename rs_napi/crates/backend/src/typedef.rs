@@ -0,0 +1,305 @@
+use proc_macro2::TokenStream as TokenStream2;
+use std::env;
+use std::fs;
+use std::path::Path;
+use syn::{
+    Attribute, Data, DeriveInput, Expr, ExprLit, Fields, GenericArgument, Ident, Lit, Meta,
+    PathArguments, ReturnType, Type, TypeParamBound,
+};
+
+// 设置了这个环境变量时，宏会把每个导出函数的 TypeScript 声明写到该目录下，
+// 再由 `cargo xtask gen-dts` 合并成最终的 index.d.ts
+const TYPE_DEF_DIR: &str = "API_TYPE_DEF_DIR";
+
+// 把 Rust 类型映射为对应的 TypeScript 类型，无法识别的类型统一映射为 any
+pub(crate) fn ts_type(ty: &Type) -> String {
+    match ty {
+        Type::Reference(r) => ts_type(&r.elem),
+        Type::Paren(p) => ts_type(&p.elem),
+        Type::Group(g) => ts_type(&g.elem),
+        Type::Slice(s) => format!("{}[]", ts_type(&s.elem)),
+        Type::Array(a) => format!("{}[]", ts_type(&a.elem)),
+        Type::Tuple(t) if t.elems.is_empty() => "void".to_string(),
+        Type::Tuple(t) => {
+            let elems = t.elems.iter().map(ts_type).collect::<Vec<_>>();
+            format!("[{}]", elems.join(", "))
+        }
+        Type::Path(p) => {
+            let Some(segment) = p.path.segments.last() else {
+                return "any".to_string();
+            };
+            let generic = |index: usize| match &segment.arguments {
+                PathArguments::AngleBracketed(args) => args
+                    .args
+                    .iter()
+                    .filter_map(|arg| match arg {
+                        GenericArgument::Type(ty) => Some(ts_type(ty)),
+                        _ => None,
+                    })
+                    .nth(index)
+                    .unwrap_or_else(|| "any".to_string()),
+                _ => "any".to_string(),
+            };
+            match segment.ident.to_string().as_str() {
+                "f32" | "f64" | "i8" | "i16" | "i32" | "i64" | "isize" | "u8" | "u16" | "u32"
+                | "u64" | "usize" => "number".to_string(),
+                "String" | "str" | "char" => "string".to_string(),
+                "bool" => "boolean".to_string(),
+                "i128" | "u128" => "bigint".to_string(),
+                "SystemTime" => "Date".to_string(),
+                "JsSymbol" => "symbol".to_string(),
+                "JsBoolean" => "boolean".to_string(),
+                "JsNumber" => "number".to_string(),
+                "JsString" => "string".to_string(),
+                "JsBigInt" => "bigint".to_string(),
+                "JsDate" => "Date".to_string(),
+                "JsObject" => "object".to_string(),
+                "JsArray" => "unknown[]".to_string(),
+                "JsFunction" => "Function".to_string(),
+                "JsValue" => "unknown".to_string(),
+                // external 值在 JS 中是一个没有属性的对象
+                "External" | "ExternalRef" | "JsExternal" => "object".to_string(),
+                "Buffer" | "JsBuffer" => "Buffer".to_string(),
+                "CancellationToken" => "AbortSignal".to_string(),
+                "EventEmitter" => "NodeJS.EventEmitter".to_string(),
+                "Vec" => format!("{}[]", array_elem(&generic(0))),
+                "Option" => format!("{} | undefined", generic(0)),
+                // 同步函数的 Err 会以异常的形式同步抛给 JS，不会变成 Promise，所以只保留成功时的类型；
+                // async 函数的 Promise 由 ts_return 加上
+                "Result" => generic(0),
+                "Box" | "Rc" | "Arc" => generic(0),
+                "Json" => json_type(&segment.arguments),
+                _ => "any".to_string(),
+            }
+        }
+        // impl Iterator<Item = T> 和 impl Stream<Item = T> 在 JS 中都是异步迭代器
+        Type::ImplTrait(_) => match impl_iter(ty) {
+            Some((_, Some(item))) => format!("AsyncIterableIterator<{}>", ts_type(item)),
+            Some((_, None)) => "AsyncIterableIterator<any>".to_string(),
+            None => "any".to_string(),
+        },
+        _ => "any".to_string(),
+    }
+}
+
+// Json<T> 中的 T 由 serde 转换，没有内置映射的类型使用它自己的名字，
+// 对应的 interface 由 #[derive(TypeDef)] 生成
+fn json_type(arguments: &PathArguments) -> String {
+    let PathArguments::AngleBracketed(args) = arguments else {
+        return "any".to_string();
+    };
+    let Some(ty) = args.args.iter().find_map(|arg| match arg {
+        GenericArgument::Type(ty) => Some(ty),
+        _ => None,
+    }) else {
+        return "any".to_string();
+    };
+    match (ts_type(ty), ty) {
+        (ts, Type::Path(p)) if ts == "any" => p
+            .path
+            .segments
+            .last()
+            .map_or(ts, |segment| segment.ident.to_string()),
+        (ts, _) => ts,
+    }
+}
+
+// 识别 impl Iterator<Item = T> 和 impl Stream<Item = T>，返回 trait 名和元素类型 T
+pub(crate) fn impl_iter(ty: &Type) -> Option<(&Ident, Option<&Type>)> {
+    let Type::ImplTrait(i) = ty else {
+        return None;
+    };
+    i.bounds.iter().find_map(|bound| {
+        let TypeParamBound::Trait(bound) = bound else {
+            return None;
+        };
+        let segment = bound.path.segments.last()?;
+        if segment.ident != "Iterator" && segment.ident != "Stream" {
+            return None;
+        }
+        let item = match &segment.arguments {
+            PathArguments::AngleBracketed(args) => args.args.iter().find_map(|arg| match arg {
+                GenericArgument::AssocType(assoc) if assoc.ident == "Item" => Some(&assoc.ty),
+                _ => None,
+            }),
+            _ => None,
+        };
+        Some((&segment.ident, item))
+    })
+}
+
+// 返回 Option<T> 中的 T，其他类型返回 None
+pub(crate) fn option_inner(ty: &Type) -> Option<&Type> {
+    let Type::Path(p) = ty else {
+        return None;
+    };
+    let segment = p.path.segments.last()?;
+    if segment.ident != "Option" {
+        return None;
+    }
+    match &segment.arguments {
+        PathArguments::AngleBracketed(args) => args.args.iter().find_map(|arg| match arg {
+            GenericArgument::Type(ty) => Some(ty),
+            _ => None,
+        }),
+        _ => None,
+    }
+}
+
+pub(crate) fn is_vec(ty: &Type) -> bool {
+    match ty {
+        Type::Path(p) => p
+            .path
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == "Vec"),
+        _ => false,
+    }
+}
+
+// 联合类型作为数组元素时需要加括号
+fn array_elem(ty: &str) -> String {
+    if ty.contains(' ') {
+        format!("({})", ty)
+    } else {
+        ty.to_string()
+    }
+}
+
+// 生成函数返回值对应的 TypeScript 类型，async 函数返回 Promise
+pub(crate) fn ts_return(output: &ReturnType, is_async: bool) -> String {
+    let ty = match output {
+        ReturnType::Default => "void".to_string(),
+        ReturnType::Type(_, ty) => ts_type(ty),
+    };
+    if is_async {
+        format!("Promise<{}>", ty)
+    } else {
+        ty
+    }
+}
+
+// 收集 /// 文档注释（也就是 #[doc = "..."] 属性），多行之间用换行连接
+pub(crate) fn doc_comment(attrs: &[Attribute]) -> String {
+    attrs
+        .iter()
+        .filter_map(|attr| match &attr.meta {
+            Meta::NameValue(nv) if nv.path.is_ident("doc") => match &nv.value {
+                Expr::Lit(ExprLit {
+                    lit: Lit::Str(s), ..
+                }) => Some(s.value()),
+                _ => None,
+            },
+            _ => None,
+        })
+        .map(|line| line.strip_prefix(' ').map(str::to_string).unwrap_or(line))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// 导出函数的一个 JS 参数
+pub(crate) struct TsParam {
+    pub(crate) name: String,
+    pub(crate) ty: String,
+    // 可以不传的参数，声明为 name?: T
+    pub(crate) optional: bool,
+    // 剩余参数，声明为 ...name: T[]
+    pub(crate) rest: bool,
+}
+
+// 生成单个导出函数的声明，例如 `export function add(left: number, right: number): number;`，
+// 有文档注释时会在前面加上对应的 JSDoc
+pub(crate) fn fn_decl(name: &str, doc: &str, params: &[TsParam], ret: &str) -> String {
+    let params = params
+        .iter()
+        .map(|param| match param {
+            TsParam { rest: true, .. } => format!("...{}: {}", param.name, param.ty),
+            TsParam { optional: true, .. } => format!("{}?: {}", param.name, param.ty),
+            _ => format!("{}: {}", param.name, param.ty),
+        })
+        .collect::<Vec<_>>();
+    let mut decl = js_doc(doc);
+    decl.push_str(&format!(
+        "export function {}({}): {};\n",
+        name,
+        params.join(", "),
+        ret
+    ));
+    decl
+}
+
+// 生成导出常量的声明，例如 `export const VERSION: string;`
+pub(crate) fn const_decl(name: &str, doc: &str, ty: &str) -> String {
+    let mut decl = js_doc(doc);
+    decl.push_str(&format!("export const {}: {};\n", name, ty));
+    decl
+}
+
+// #[derive(TypeDef)]：为具名字段的结构体生成 `export interface Name { field: T; }`，
+// 供 Json<Name> 在声明中引用。字段名按 Rust 中的写法输出，Option<T> 字段声明为 field?: T
+pub(crate) fn expand_interface(input: TokenStream2) -> syn::Result<TokenStream2> {
+    let input = syn::parse2::<DeriveInput>(input)?;
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    &input.ident,
+                    "#[derive(TypeDef)] only supports structs with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                "#[derive(TypeDef)] can only be applied to structs",
+            ))
+        }
+    };
+    let mut decl = js_doc(&doc_comment(&input.attrs));
+    decl.push_str(&format!("export interface {} {{\n", input.ident));
+    for field in fields {
+        let name = field.ident.as_ref().expect("named field");
+        let field_doc = js_doc(&doc_comment(&field.attrs));
+        field_doc
+            .lines()
+            .for_each(|line| decl.push_str(&format!("  {}\n", line)));
+        match option_inner(&field.ty) {
+            Some(inner) => decl.push_str(&format!("  {}?: {};\n", name, ts_type(inner))),
+            None => decl.push_str(&format!("  {}: {};\n", name, ts_type(&field.ty))),
+        }
+    }
+    decl.push_str("}\n");
+    write_type_def(&input.ident, &decl)?;
+    Ok(TokenStream2::new())
+}
+
+// 把文档注释转换成 JSDoc，没有文档注释时返回空字符串
+fn js_doc(doc: &str) -> String {
+    let mut decl = String::new();
+    if !doc.is_empty() {
+        decl.push_str("/**\n");
+        doc.lines().for_each(|line| {
+            decl.push_str(format!(" * {}\n", line).trim_end());
+            decl.push('\n');
+        });
+        decl.push_str(" */\n");
+    }
+    decl
+}
+
+// 每个导出单独写一个文件，重复编译时直接覆盖，避免声明重复
+pub(crate) fn write_type_def(name: &Ident, decl: &str) -> syn::Result<()> {
+    let Some(dir) = env::var_os(TYPE_DEF_DIR) else {
+        return Ok(());
+    };
+    let dir = Path::new(&dir);
+    fs::create_dir_all(dir)
+        .and_then(|_| fs::write(dir.join(format!("{}.d.ts", name)), decl))
+        .map_err(|err| {
+            syn::Error::new_spanned(
+                name,
+                format!("failed to write type definition for `{}`: {}", name, err),
+            )
+        })
+}
@@ -0,0 +1,6 @@
+// 检查 #[api] 在各种不支持的用法下给出的编译错误
+#[test]
+fn ui() {
+    let t = trybuild::TestCases::new();
+    t.compile_fail("tests/ui/*.rs");
+}
@@ -0,0 +1,34 @@
+use backend::api;
+
+struct CallContext;
+struct CancellationToken;
+
+#[api(async)]
+pub fn with_context(ctx: CallContext) -> f64 {
+    0.0
+}
+
+#[api]
+pub fn not_async(token: CancellationToken) -> f64 {
+    0.0
+}
+
+#[api(async)]
+pub fn two_tokens(a: CancellationToken, b: CancellationToken) -> f64 {
+    0.0
+}
+
+#[api(async)]
+pub async fn twice(value: f64) -> f64 {
+    value * 2.0
+}
+
+#[api]
+pub async fn with_async_context(ctx: CallContext) -> f64 {
+    0.0
+}
+
+#[api(async)]
+pub const LIMIT: f64 = 1.0;
+
+fn main() {}
@@ -0,0 +1,8 @@
+use backend::api;
+
+#[api(name = "sum")]
+pub fn add(left: f64, right: f64) -> f64 {
+    left + right
+}
+
+fn main() {}
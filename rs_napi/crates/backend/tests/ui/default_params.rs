@@ -0,0 +1,18 @@
+use backend::api;
+
+#[api(default(factor = 2.0))]
+pub fn scale(value: f64, scale: f64) -> f64 {
+    value * scale
+}
+
+#[api(default(digits = 2.0))]
+pub fn format_number(value: f64, #[default = 3.0] digits: f64) -> String {
+    format!("{:.*}", digits as usize, value)
+}
+
+#[api]
+pub fn sum(#[rest] #[default = vec![]] values: Vec<f64>) -> f64 {
+    values.iter().sum()
+}
+
+fn main() {}
@@ -0,0 +1,8 @@
+use backend::api;
+
+#[api]
+pub fn identity<T>(value: T) -> T {
+    value
+}
+
+fn main() {}
@@ -0,0 +1,18 @@
+use backend::api;
+
+#[api]
+pub fn displayable() -> impl std::fmt::Display {
+    0.0
+}
+
+#[api(async)]
+pub fn async_iter() -> impl Iterator<Item = f64> {
+    std::iter::empty()
+}
+
+#[api]
+pub async fn async_fn_iter() -> impl Iterator<Item = f64> {
+    std::iter::empty()
+}
+
+fn main() {}
@@ -0,0 +1,15 @@
+use backend::JsError;
+
+#[derive(JsError)]
+pub struct NotAnEnum;
+
+#[derive(JsError)]
+pub enum BadError {
+    #[js_error(kind = "EvalError")]
+    Eval,
+    #[js_error(status = 400)]
+    Status,
+    Wrapped(#[js_error(cause)] String, #[js_error(cause)] String),
+}
+
+fn main() {}
@@ -0,0 +1,13 @@
+use backend::api;
+
+#[api]
+pub fn sum(#[rest] values: Vec<f64>, scale: f64) -> f64 {
+    values.iter().sum::<f64>() * scale
+}
+
+#[api]
+pub fn count(#[rest] values: &[f64]) -> f64 {
+    values.len() as f64
+}
+
+fn main() {}
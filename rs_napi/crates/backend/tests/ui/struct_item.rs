@@ -0,0 +1,9 @@
+use backend::api;
+
+#[api]
+pub struct Point {
+    x: f64,
+    y: f64,
+}
+
+fn main() {}
@@ -0,0 +1,12 @@
+use backend::TypeDef;
+
+#[derive(TypeDef)]
+pub enum Shape {
+    Circle,
+    Square,
+}
+
+#[derive(TypeDef)]
+pub struct Pair(f64, f64);
+
+fn main() {}
@@ -0,0 +1,8 @@
+use backend::api;
+
+#[api]
+pub unsafe fn read(ptr: f64) -> f64 {
+    ptr
+}
+
+fn main() {}
@@ -0,0 +1,13 @@
+use backend::api;
+
+#[api]
+pub fn call(callback: impl Fn(f64) -> f64, name: &&str) -> f64 {
+    callback(name.len() as f64)
+}
+
+#[api]
+pub fn consume(value: External<f64>) -> f64 {
+    *value
+}
+
+fn main() {}
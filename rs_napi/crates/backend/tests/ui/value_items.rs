@@ -0,0 +1,9 @@
+use backend::api;
+
+#[api]
+pub static mut COUNTER: f64 = 0.0;
+
+#[api(default(value = 1.0))]
+pub const ONE: f64 = 1.0;
+
+fn main() {}
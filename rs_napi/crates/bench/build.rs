@@ -0,0 +1,4 @@
+fn main() {
+    // 把可执行文件中的符号（包括 mock 导出的 napi_*）放进动态符号表，dlopen 加载的原生模块才能解析到它们
+    println!("cargo:rustc-link-arg-bins=-rdynamic");
+}
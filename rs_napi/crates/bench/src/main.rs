@@ -0,0 +1,415 @@
+// 比较 rs_napi（crates/apisecond）和 napi-rs（rs_libuv/node_uv）两种实现的调用开销、参数转换开销和异步任务吞吐量。
+// 两个模块都以编译好的动态库的形式通过 dlopen 加载，和 Node.js 加载 .node 文件的方式相同，
+// 其中的 napi_* 符号解析到这个可执行文件导出的 mock 运行时。结果包含 mock 本身的开销，
+// 只适合比较两种实现之间、以及和直接调用 Rust 函数之间的相对差异，不代表在 Node.js 中的绝对耗时。
+// rs_libuv/node_worker 是纯 JS 的 worker_threads 实现，需要真正的 V8，不在这里测量
+use libloading::Library;
+use std::env;
+use std::hint::black_box;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::time::{Duration, Instant};
+use sys::{napi_env, napi_value};
+
+type Register = unsafe extern "C" fn(napi_env, napi_value) -> napi_value;
+
+// 同一组测试在两种实现中对应的导出名称
+struct Stack {
+    name: &'static str,
+    arg_count: &'static str,
+    add: &'static str,
+    join: &'static str,
+    // 计算 fib(20) 的异步函数，以及调用时需要传入的参数。两边都是不检查取消的朴素递归，
+    // 差异只来自异步任务本身的开销
+    fib: &'static str,
+    fib_args: &'static [f64],
+    // 用参数 0 调用这个函数关闭实现中的结果缓存，让每次调用都真正计算一次
+    disable_cache: Option<&'static str>,
+}
+
+const RS_NAPI: Stack = Stack {
+    name: "rs_napi",
+    arg_count: "arg_count",
+    add: "add",
+    join: "join",
+    fib: "fib",
+    fib_args: &[20.0],
+    disable_cache: None,
+};
+
+const NAPI_RS: Stack = Stack {
+    name: "napi-rs",
+    arg_count: "argCount",
+    add: "add",
+    join: "join",
+    fib: "nativeUVFib",
+    fib_args: &[20.0],
+    disable_cache: Some("setCacheCapacity"),
+};
+
+// fib(20)，两种实现的结果都应该是这个值
+const FIB_20: f64 = 6765.0;
+
+// 异步测试中同时在执行的任务个数
+const IN_FLIGHT: usize = 32;
+
+// 一个加载到独立 mock 环境中的原生模块
+struct Module {
+    stack: &'static Stack,
+    env: mock::Env,
+    exports: napi_value,
+    // 字段按声明顺序 drop，env 销毁时还会调用模块中的 finalizer，所以动态库必须最后卸载
+    _library: Library,
+}
+
+impl Module {
+    fn load(stack: &'static Stack, path: &Path) -> Result<Module, String> {
+        let library = unsafe { Library::new(path) }.map_err(|err| err.to_string())?;
+        let register = unsafe { library.get::<Register>(b"napi_register_module_v1\0") }
+            .map_err(|err| format!("{}: {}", path.display(), err))?;
+        let env = mock::Env::new();
+        let exports = env.load_module(*register);
+        Ok(Module {
+            stack,
+            env,
+            exports,
+            _library: library,
+        })
+    }
+
+    fn function(&self, name: &str) -> napi_value {
+        let function = self.env.get_named_property(self.exports, name);
+        assert_eq!(
+            self.env.type_of(function),
+            "function",
+            "{} does not export {}",
+            self.stack.name,
+            name
+        );
+        function
+    }
+
+    fn call(&self, function: napi_value, args: &[napi_value]) -> napi_value {
+        self.env
+            .call_function(function, args)
+            .unwrap_or_else(|err| panic!("{} threw {}", self.stack.name, self.env.describe(err)))
+    }
+}
+
+// 命令行参数
+struct Options {
+    apisecond: PathBuf,
+    node_uv: PathBuf,
+    // 每轮测量持续的时间，每个测试测量 ROUNDS 轮并取中位数
+    round: Duration,
+}
+
+const ROUNDS: usize = 5;
+
+fn usage() -> ! {
+    eprintln!("usage: bench [--apisecond <path>] [--node-uv <path>] [--round-ms <ms>]");
+    process::exit(2);
+}
+
+fn parse_options() -> Options {
+    // 默认使用和当前可执行文件相同 profile 的构建产物
+    let profile = if cfg!(debug_assertions) {
+        "debug"
+    } else {
+        "release"
+    };
+    let manifest = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let mut options = Options {
+        apisecond: manifest
+            .join("../../target")
+            .join(profile)
+            .join(libloading::library_filename("apisecond")),
+        node_uv: manifest
+            .join("../../../rs_libuv/node_uv/target")
+            .join(profile)
+            .join(libloading::library_filename("napi_package_template")),
+        round: Duration::from_millis(200),
+    };
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let value = args.next().unwrap_or_else(|| usage());
+        match arg.as_str() {
+            "--apisecond" => options.apisecond = value.into(),
+            "--node-uv" => options.node_uv = value.into(),
+            "--round-ms" => {
+                // 每轮至少要有时间执行一次 batch
+                let ms = value
+                    .parse()
+                    .ok()
+                    .filter(|&ms| ms > 0)
+                    .unwrap_or_else(|| usage());
+                options.round = Duration::from_millis(ms);
+            }
+            _ => usage(),
+        }
+    }
+    options
+}
+
+// 反复执行 batch，直到超过 round，返回平均每个操作的耗时。batch 返回它执行了多少个操作。
+// 至少执行一次 batch，round 很短（例如预热轮的 round / 4 为 0）时也不会除以 0
+fn measure_round(round: Duration, mut batch: impl FnMut() -> u64) -> Duration {
+    let start = Instant::now();
+    let mut ops = batch();
+    while start.elapsed() < round {
+        ops += batch();
+    }
+    start.elapsed() / ops.max(1) as u32
+}
+
+// 预热一轮之后测量 ROUNDS 轮，取中位数
+fn measure(round: Duration, mut batch: impl FnMut() -> u64) -> Duration {
+    measure_round(round / 4, &mut batch);
+    let mut samples = (0..ROUNDS)
+        .map(|_| measure_round(round, &mut batch))
+        .collect::<Vec<_>>();
+    samples.sort();
+    samples[ROUNDS / 2]
+}
+
+// 一个测试在各个实现中的结果，None 表示对应的模块没有加载
+struct Row {
+    name: &'static str,
+    // 同时给出每秒完成的操作数
+    throughput: bool,
+    direct: Duration,
+    stacks: Vec<Option<Duration>>,
+}
+
+// 每次调用都在单独的 handle scope 中，和 Node.js 调用原生函数时一样回收句柄
+fn bench_sync(
+    module: &Module,
+    round: Duration,
+    function: &str,
+    args: impl FnOnce(&mock::Env) -> Vec<napi_value>,
+    check: impl Fn(&mock::Env, napi_value),
+) -> Duration {
+    let function = module.function(function);
+    let args = args(&module.env);
+    module
+        .env
+        .handle_scope(|| check(&module.env, module.call(function, &args)));
+    measure(round, || {
+        for _ in 0..100 {
+            module
+                .env
+                .handle_scope(|| black_box(module.call(function, &args)));
+        }
+        100
+    })
+}
+
+fn call_overhead(module: &Module, round: Duration) -> Duration {
+    bench_sync(
+        module,
+        round,
+        module.stack.arg_count,
+        |_| Vec::new(),
+        |env, result| assert_eq!(env.get_number(result), 0.0),
+    )
+}
+
+fn number_args(module: &Module, round: Duration) -> Duration {
+    bench_sync(
+        module,
+        round,
+        module.stack.add,
+        |env| vec![env.create_number(1.5), env.create_number(2.5)],
+        |env, result| assert_eq!(env.get_number(result), 4.0),
+    )
+}
+
+fn string_args(module: &Module, round: Duration) -> Duration {
+    bench_sync(
+        module,
+        round,
+        module.stack.join,
+        |env| {
+            let parts = parts()
+                .iter()
+                .map(|part| env.create_string(part))
+                .collect::<Vec<_>>();
+            vec![env.create_array(&parts), env.create_string(",")]
+        },
+        |env, result| assert_eq!(env.get_string(result), parts().join(",")),
+    )
+}
+
+// 每轮同时发起 IN_FLIGHT 个 fib(20)，运行事件循环直到全部完成
+fn async_throughput(module: &Module, round: Duration) -> Duration {
+    let env = &module.env;
+    let function = module.function(module.stack.fib);
+    if let Some(disable_cache) = module.stack.disable_cache {
+        module.call(module.function(disable_cache), &[env.create_number(0.0)]);
+    }
+    let args = module
+        .stack
+        .fib_args
+        .iter()
+        .map(|arg| env.create_number(*arg))
+        .collect::<Vec<_>>();
+    measure(round, || {
+        env.handle_scope(|| {
+            let promises = (0..IN_FLIGHT)
+                .map(|_| module.call(function, &args))
+                .collect::<Vec<_>>();
+            env.run();
+            for promise in promises {
+                match env.promise_state(promise) {
+                    mock::PromiseState::Fulfilled(value) => {
+                        assert_eq!(env.get_number(value), FIB_20)
+                    }
+                    _ => panic!("{} did not fulfill fib(20)", module.stack.name),
+                }
+            }
+        });
+        IN_FLIGHT as u64
+    })
+}
+
+fn parts() -> Vec<String> {
+    (0..100).map(|i| format!("part-{}", i)).collect()
+}
+
+fn fib(n: u32) -> u32 {
+    match n {
+        0 | 1 => n,
+        _ => fib(n - 1) + fib(n - 2),
+    }
+}
+
+// 不经过 N-API，直接调用等价的 Rust 代码，作为参照
+fn direct(round: Duration, mut f: impl FnMut()) -> Duration {
+    measure(round, || {
+        for _ in 0..100 {
+            f();
+        }
+        100
+    })
+}
+
+fn format_duration(duration: Duration) -> String {
+    let ns = duration.as_nanos();
+    if ns >= 1_000_000 {
+        format!("{:.2} ms", ns as f64 / 1e6)
+    } else if ns >= 1_000 {
+        format!("{:.2} µs", ns as f64 / 1e3)
+    } else {
+        format!("{} ns", ns)
+    }
+}
+
+fn format_cell(duration: Duration, throughput: bool) -> String {
+    if throughput {
+        let per_second = 1.0 / duration.as_secs_f64();
+        format!("{} ({:.0}/s)", format_duration(duration), per_second)
+    } else {
+        format_duration(duration)
+    }
+}
+
+fn print_report(stacks: &[&Stack], rows: &[Row]) {
+    let mut header = vec!["benchmark".to_string(), "rust (direct)".to_string()];
+    header.extend(stacks.iter().map(|stack| stack.name.to_string()));
+    header.push(format!("{} / {}", stacks[1].name, stacks[0].name));
+    let lines = rows
+        .iter()
+        .map(|row| {
+            let cell = |time: Duration| format_cell(time, row.throughput);
+            let mut line = vec![row.name.to_string(), cell(row.direct)];
+            line.extend(
+                row.stacks
+                    .iter()
+                    .map(|time| time.map_or("-".to_string(), cell)),
+            );
+            line.push(match (row.stacks[0], row.stacks[1]) {
+                (Some(base), Some(other)) => {
+                    format!("{:.2}x", other.as_secs_f64() / base.as_secs_f64())
+                }
+                _ => "-".to_string(),
+            });
+            line
+        })
+        .collect::<Vec<_>>();
+    println!("| {} |", header.join(" | "));
+    println!("|{}", " --- |".repeat(header.len()));
+    for line in lines {
+        println!("| {} |", line.join(" | "));
+    }
+}
+
+fn main() {
+    let options = parse_options();
+    let modules = [(&RS_NAPI, &options.apisecond), (&NAPI_RS, &options.node_uv)]
+        .into_iter()
+        .map(|(stack, path)| match Module::load(stack, path) {
+            Ok(module) => Some(module),
+            Err(err) => {
+                eprintln!("{}: skipped, {}", stack.name, err);
+                None
+            }
+        })
+        .collect::<Vec<_>>();
+    if modules.iter().all(Option::is_none) {
+        eprintln!(
+            "build the modules first: `cargo build --release -p apisecond --features demo` in rs_napi \
+             and `cargo build --release --features bench` in rs_libuv/node_uv"
+        );
+        process::exit(1);
+    }
+
+    let round = options.round;
+    let run = |bench: fn(&Module, Duration) -> Duration| {
+        modules
+            .iter()
+            .map(|module| module.as_ref().map(|module| bench(module, round)))
+            .collect::<Vec<_>>()
+    };
+    let parts = parts();
+    let rows = vec![
+        Row {
+            name: "call overhead (no args)",
+            throughput: false,
+            direct: direct(round, || {
+                black_box(black_box(&[] as &[f64]).len());
+            }),
+            stacks: run(call_overhead),
+        },
+        Row {
+            name: "number args (add)",
+            throughput: false,
+            direct: direct(round, || {
+                black_box(black_box(1.5) + black_box(2.5));
+            }),
+            stacks: run(number_args),
+        },
+        Row {
+            name: "string array args (join 100)",
+            throughput: false,
+            direct: direct(round, || {
+                black_box(black_box(&parts).join(","));
+            }),
+            stacks: run(string_args),
+        },
+        Row {
+            name: "async fib(20), 32 in flight",
+            throughput: true,
+            direct: measure(round, || {
+                black_box(fib(black_box(20)));
+                1
+            }),
+            stacks: run(async_throughput),
+        },
+    ];
+
+    println!(
+        "time per operation, median of {} rounds of {:?}\n",
+        ROUNDS, round
+    );
+    print_report(&[&RS_NAPI, &NAPI_RS], &rows);
+}
@@ -0,0 +1,606 @@
+use std::cell::{Cell, RefCell};
+use std::ffi::CString;
+use std::mem::ManuallyDrop;
+use std::os::raw::{c_int, c_void};
+use std::ptr;
+use std::rc::Rc;
+use std::sync::Arc;
+use sys::{napi_env, napi_value};
+
+use crate::napi;
+use crate::task::{Tsfn, Wakeup};
+use crate::value::{Closure, Key, Kind, Object, Promise, Value};
+
+// 一个模拟的 JS 环境，相当于一个 Node.js 实例（或者一个 worker_thread）。
+// 只能在创建它的线程上使用；drop 时和 Node.js 销毁环境一样关闭所有的 threadsafe function
+// 并调用所有的 finalizer
+pub struct Env {
+    state: Rc<State>,
+}
+
+// Promise 的当前状态，兑现的值和拒绝的原因是新创建的句柄
+#[derive(Clone, Copy, Debug)]
+pub enum PromiseState {
+    Pending,
+    Fulfilled(napi_value),
+    Rejected(napi_value),
+}
+
+pub(crate) struct State {
+    // napi_value 是这个表中的下标加一。没有 handle scope，句柄一直有效
+    values: RefCell<Vec<Value>>,
+    objects: RefCell<Vec<Object>>,
+    // Symbol 的描述
+    pub(crate) symbols: RefCell<Vec<Option<String>>>,
+    // napi_ref 是下标加一，删除后为 None
+    pub(crate) refs: RefCell<Vec<Option<Value>>>,
+    // napi_deferred 是下标加一，对应 Promise 对象的下标，兑现之后为 None
+    pub(crate) deferreds: RefCell<Vec<Option<usize>>>,
+    pub(crate) exception: RefCell<Option<Value>>,
+    // 在事件循环的回调中抛出、没有被处理的异常，Node.js 中会触发 uncaughtException
+    pub(crate) uncaught: RefCell<Vec<Value>>,
+    pub(crate) global: Cell<usize>,
+    pub(crate) external_memory: Cell<i64>,
+    pub(crate) wakeup: Arc<Wakeup>,
+    // 创建过的所有 threadsafe function，finalize 之后仍然保留，其他线程上残留的句柄不会访问到已释放的内存
+    pub(crate) tsfns: RefCell<Vec<Arc<Tsfn>>>,
+    // 已经 queue、还没有调用 complete 的 async work 个数
+    pub(crate) works: Cell<usize>,
+    // napi_add_env_cleanup_hook 注册的回调和参数
+    pub(crate) cleanup_hooks: RefCell<Vec<(unsafe extern "C" fn(*mut c_void), usize)>>,
+    // 为 false 时 napi_create_external_buffer 返回 napi_no_external_buffers_allowed
+    pub(crate) external_buffers: Cell<bool>,
+}
+
+impl State {
+    pub(crate) unsafe fn from_raw<'a>(env: napi_env) -> &'a State {
+        &*env.cast_const().cast::<State>()
+    }
+
+    pub(crate) fn raw(&self) -> napi_env {
+        (self as *const State).cast_mut().cast()
+    }
+
+    pub(crate) fn alloc(&self, value: Value) -> napi_value {
+        let mut values = self.values.borrow_mut();
+        values.push(value);
+        values.len() as napi_value
+    }
+
+    // NULL 句柄按 undefined 处理
+    pub(crate) fn get(&self, value: napi_value) -> Value {
+        match value as usize {
+            0 => Value::Undefined,
+            index => self.values.borrow()[index - 1].clone(),
+        }
+    }
+
+    pub(crate) fn new_object(&self, kind: Kind) -> Value {
+        let mut objects = self.objects.borrow_mut();
+        objects.push(Object::new(kind));
+        Value::Object(objects.len() - 1)
+    }
+
+    // 句柄对应的对象的下标，不是对象时返回 None
+    pub(crate) fn object_id(&self, value: napi_value) -> Option<usize> {
+        match self.get(value) {
+            Value::Object(id) => Some(id),
+            _ => None,
+        }
+    }
+
+    // f 中不能调用任何可能再次访问对象表的回调
+    pub(crate) fn with_object<R>(&self, id: usize, f: impl FnOnce(&mut Object) -> R) -> R {
+        f(&mut self.objects.borrow_mut()[id])
+    }
+
+    pub(crate) fn create_function(&self, name: &str, kind: Kind) -> Value {
+        let function = self.new_object(kind);
+        if let Value::Object(id) = function {
+            self.with_object(id, |object| {
+                object.set(
+                    Key::String("name".to_string()),
+                    Value::String(name.to_string()),
+                    false,
+                )
+            });
+        }
+        function
+    }
+
+    pub(crate) fn create_error(&self, name: &'static str, code: Value, message: Value) -> Value {
+        let error = self.new_object(Kind::Error(name));
+        if let Value::Object(id) = error {
+            self.with_object(id, |object| {
+                object.set(Key::String("message".to_string()), message, false);
+                if code != Value::Undefined && code != Value::Null {
+                    object.set(Key::String("code".to_string()), code, true);
+                }
+            });
+        }
+        error
+    }
+
+    fn report_uncaught(&self) {
+        if let Some(exception) = self.exception.take() {
+            self.uncaught.borrow_mut().push(exception);
+        }
+    }
+
+    // 处理一轮已经到达的事件，返回是否处理了任何事件
+    fn tick(&self) -> bool {
+        let mut progressed = false;
+        while let Some((work, status)) = self.wakeup.take_completed() {
+            self.works.set(self.works.get() - 1);
+            let (complete, data) = unsafe { ((*work).complete, (*work).data) };
+            if let Some(complete) = complete {
+                unsafe { complete(self.raw(), status, data) };
+            }
+            self.report_uncaught();
+            progressed = true;
+        }
+        let tsfns = self.tsfns.borrow().clone();
+        for tsfn in tsfns {
+            while let Some(data) = tsfn.pop() {
+                let func = tsfn
+                    .func
+                    .map_or(ptr::null_mut(), |func| self.reference(func));
+                match tsfn.call_js {
+                    Some(call_js) => unsafe { call_js(self.raw(), func, tsfn.context.0, data) },
+                    // 没有 call_js 时直接不带参数地调用 func
+                    None => unsafe {
+                        let mut result = ptr::null_mut();
+                        napi::napi_call_function(
+                            self.raw(),
+                            self.alloc(Value::Undefined),
+                            func,
+                            0,
+                            ptr::null(),
+                            &mut result,
+                        );
+                    },
+                }
+                self.report_uncaught();
+                progressed = true;
+            }
+            if tsfn.take_finished() {
+                self.finalize_tsfn(&tsfn);
+                progressed = true;
+            }
+        }
+        progressed
+    }
+
+    fn finalize_tsfn(&self, tsfn: &Tsfn) {
+        if let Some(finalize) = tsfn.finalize {
+            unsafe { finalize(self.raw(), tsfn.finalize_data.0, tsfn.context.0) };
+        }
+        if let Some(func) = tsfn.func {
+            self.refs.borrow_mut()[func] = None;
+        }
+    }
+
+    // 还有正在执行的 async work 或者被引用的 threadsafe function 时事件循环不会退出
+    fn alive(&self) -> bool {
+        self.works.get() > 0 || self.tsfns.borrow().iter().any(|tsfn| tsfn.keeps_alive())
+    }
+
+    fn reference(&self, index: usize) -> napi_value {
+        let value = self.refs.borrow()[index].clone();
+        value.map_or(ptr::null_mut(), |value| self.alloc(value))
+    }
+
+    // 和 Node.js 销毁环境时一样：先按注册的相反顺序调用 cleanup hook，再关闭所有 threadsafe function，
+    // 队列中剩下的数据以 NULL env 交给 call_js 释放，然后调用所有的 finalizer
+    fn teardown(&self) {
+        // 没有等到 run() 的 async work 不能一直阻塞它的线程
+        self.wakeup.release();
+        while let Some((hook, arg)) = self.cleanup_hooks.borrow_mut().pop() {
+            unsafe { hook(arg as *mut c_void) };
+        }
+        let tsfns = self.tsfns.borrow().clone();
+        for tsfn in tsfns {
+            let finalized = {
+                let mut state = tsfn.state.lock().unwrap();
+                state.closing = true;
+                state.finalized
+            };
+            if finalized {
+                continue;
+            }
+            while let Some(data) = tsfn.pop() {
+                if let Some(call_js) = tsfn.call_js {
+                    unsafe { call_js(ptr::null_mut(), ptr::null_mut(), tsfn.context.0, data) };
+                }
+            }
+            tsfn.state.lock().unwrap().finalized = true;
+            self.finalize_tsfn(&tsfn);
+        }
+        // finalizer 中可能创建新的对象，所以每次只取出当前的 finalizer
+        loop {
+            let finalizers = self
+                .objects
+                .borrow_mut()
+                .iter_mut()
+                .flat_map(|object| object.finalizers.drain(..))
+                .collect::<Vec<_>>();
+            if finalizers.is_empty() {
+                break;
+            }
+            finalizers.into_iter().for_each(|finalizer| {
+                if let Some(cb) = finalizer.cb {
+                    unsafe { cb(self.raw(), finalizer.data, finalizer.hint) };
+                }
+            });
+        }
+    }
+}
+
+// 模拟环境中的 napi_value 只是值表中的下标，不会被当作指针解引用
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+impl Env {
+    pub fn new() -> Env {
+        let state = Rc::new(State {
+            values: RefCell::new(Vec::new()),
+            objects: RefCell::new(Vec::new()),
+            symbols: RefCell::new(Vec::new()),
+            refs: RefCell::new(Vec::new()),
+            deferreds: RefCell::new(Vec::new()),
+            exception: RefCell::new(None),
+            uncaught: RefCell::new(Vec::new()),
+            global: Cell::new(0),
+            external_memory: Cell::new(0),
+            wakeup: Arc::new(Wakeup::default()),
+            tsfns: RefCell::new(Vec::new()),
+            works: Cell::new(0),
+            cleanup_hooks: RefCell::new(Vec::new()),
+            external_buffers: Cell::new(true),
+        });
+        // 全局对象上只有 Symbol.asyncIterator
+        state
+            .symbols
+            .borrow_mut()
+            .push(Some("Symbol.asyncIterator".to_string()));
+        let symbol = state.new_object(Kind::Plain);
+        let global = state.new_object(Kind::Plain);
+        if let (Value::Object(symbol_id), Value::Object(global_id)) = (&symbol, &global) {
+            state.with_object(*symbol_id, |object| {
+                object.set(
+                    Key::String("asyncIterator".to_string()),
+                    Value::Symbol(0),
+                    false,
+                )
+            });
+            state.with_object(*global_id, |object| {
+                object.set(Key::String("Symbol".to_string()), symbol.clone(), false)
+            });
+            state.global.set(*global_id);
+        }
+        Env { state }
+    }
+
+    // 在 N-API 回调中拿到调用者的 Env。返回的 Env 不拥有环境，也不会被 drop
+    pub(crate) unsafe fn borrow(env: napi_env) -> ManuallyDrop<Env> {
+        ManuallyDrop::new(Env {
+            state: Rc::from_raw(env.cast_const().cast::<State>()),
+        })
+    }
+
+    pub fn raw(&self) -> napi_env {
+        self.state.raw()
+    }
+
+    // 像 Node.js 加载原生模块一样创建 exports 并调用模块的注册函数，返回最终的 exports
+    pub fn load_module(
+        &self,
+        register: unsafe extern "C" fn(napi_env, napi_value) -> napi_value,
+    ) -> napi_value {
+        let exports = self.create_object();
+        let result = unsafe { register(self.raw(), exports) };
+        if let Some(exception) = self.state.exception.take() {
+            let message = self.describe(self.state.alloc(exception));
+            panic!("module registration threw {}", message);
+        }
+        if result.is_null() {
+            exports
+        } else {
+            result
+        }
+    }
+
+    pub fn get_undefined(&self) -> napi_value {
+        self.state.alloc(Value::Undefined)
+    }
+
+    pub fn get_null(&self) -> napi_value {
+        self.state.alloc(Value::Null)
+    }
+
+    pub fn get_boolean(&self, value: bool) -> napi_value {
+        self.state.alloc(Value::Boolean(value))
+    }
+
+    pub fn create_number(&self, value: f64) -> napi_value {
+        self.state.alloc(Value::Number(value))
+    }
+
+    pub fn create_string(&self, value: &str) -> napi_value {
+        self.state.alloc(Value::String(value.to_string()))
+    }
+
+    // 符号和按小端顺序排列的 64 位 words，和 napi_create_bigint_words 一样去掉高位的 0
+    pub fn create_bigint(&self, negative: bool, words: &[u64]) -> napi_value {
+        let mut result = ptr::null_mut();
+        let status = unsafe {
+            napi::napi_create_bigint_words(
+                self.raw(),
+                negative as c_int,
+                words.len(),
+                words.as_ptr(),
+                &mut result,
+            )
+        };
+        assert_eq!(status, sys::napi_ok);
+        result
+    }
+
+    pub fn create_object(&self) -> napi_value {
+        let object = self.state.new_object(Kind::Plain);
+        self.state.alloc(object)
+    }
+
+    pub fn create_array(&self, elements: &[napi_value]) -> napi_value {
+        let elements = elements
+            .iter()
+            .map(|value| self.state.get(*value))
+            .collect();
+        let array = self.state.new_object(Kind::Array(elements));
+        self.state.alloc(array)
+    }
+
+    // 用 Rust 闭包实现一个 JS 函数，闭包收到的是实参列表
+    pub fn create_function(
+        &self,
+        name: &str,
+        f: impl Fn(&Env, &[napi_value]) -> napi_value + 'static,
+    ) -> napi_value {
+        let closure: Closure = Rc::new(f);
+        let function = self.state.create_function(name, Kind::Closure(closure));
+        self.state.alloc(function)
+    }
+
+    // 相当于 JS 中的 throw value，只能在 create_function 的闭包中使用
+    pub fn throw(&self, value: napi_value) {
+        *self.state.exception.borrow_mut() = Some(self.state.get(value));
+    }
+
+    pub fn get_named_property(&self, object: napi_value, name: &str) -> napi_value {
+        let Some(id) = self.state.object_id(object) else {
+            panic!("expected an object, found {}", self.type_of(object));
+        };
+        let key = Key::String(name.to_string());
+        let value = self.state.with_object(id, |object| object.get(&key));
+        self.state.alloc(value.unwrap_or(Value::Undefined))
+    }
+
+    pub fn set_named_property(&self, object: napi_value, name: &str, value: napi_value) {
+        let name = CString::new(name).unwrap();
+        let status =
+            unsafe { napi::napi_set_named_property(self.raw(), object, name.as_ptr(), value) };
+        assert_eq!(status, sys::napi_ok, "expected an object");
+    }
+
+    // 可枚举的字符串 key，和 Object.keys 的结果一致
+    pub fn property_names(&self, object: napi_value) -> Vec<String> {
+        let Some(id) = self.state.object_id(object) else {
+            panic!("expected an object, found {}", self.type_of(object));
+        };
+        self.state.with_object(id, |object| object.keys())
+    }
+
+    pub fn array_length(&self, array: napi_value) -> usize {
+        self.get_number(self.get_named_property(array, "length")) as usize
+    }
+
+    pub fn get_element(&self, array: napi_value, index: usize) -> napi_value {
+        self.get_named_property(array, &index.to_string())
+    }
+
+    // typeof 的结果，null 返回 "null"
+    pub fn type_of(&self, value: napi_value) -> &'static str {
+        let mut ty = sys::napi_undefined;
+        unsafe { napi::napi_typeof(self.raw(), value, &mut ty) };
+        match ty {
+            sys::napi_null => "null",
+            sys::napi_boolean => "boolean",
+            sys::napi_number => "number",
+            sys::napi_string => "string",
+            sys::napi_symbol => "symbol",
+            sys::napi_object => "object",
+            sys::napi_function => "function",
+            sys::napi_external => "external",
+            sys::napi_bigint => "bigint",
+            _ => "undefined",
+        }
+    }
+
+    pub fn get_number(&self, value: napi_value) -> f64 {
+        match self.state.get(value) {
+            Value::Number(n) => n,
+            _ => panic!("expected a number, found {}", self.describe(value)),
+        }
+    }
+
+    pub fn get_string(&self, value: napi_value) -> String {
+        match self.state.get(value) {
+            Value::String(s) => s,
+            _ => panic!("expected a string, found {}", self.describe(value)),
+        }
+    }
+
+    pub fn get_bigint(&self, value: napi_value) -> (bool, Vec<u64>) {
+        match self.state.get(value) {
+            Value::BigInt(negative, words) => (negative, words),
+            _ => panic!("expected a bigint, found {}", self.describe(value)),
+        }
+    }
+
+    pub fn get_bool(&self, value: napi_value) -> bool {
+        match self.state.get(value) {
+            Value::Boolean(b) => b,
+            _ => panic!("expected a boolean, found {}", self.describe(value)),
+        }
+    }
+
+    // 两个句柄是否指向同一个值（对象比较的是身份），相当于 Object.is
+    pub fn same_value(&self, left: napi_value, right: napi_value) -> bool {
+        self.state.get(left) == self.state.get(right)
+    }
+
+    // 调用一个函数，this 为 undefined。函数抛出异常时返回 Err(异常)
+    pub fn call_function(
+        &self,
+        function: napi_value,
+        args: &[napi_value],
+    ) -> Result<napi_value, napi_value> {
+        self.call_method(self.get_undefined(), function, args)
+    }
+
+    // 以 this 为接收者调用一个函数，相当于 function.call(this, ...args)
+    pub fn call_method(
+        &self,
+        this: napi_value,
+        function: napi_value,
+        args: &[napi_value],
+    ) -> Result<napi_value, napi_value> {
+        let result =
+            unsafe { napi::call(self.raw(), this, function, args.to_vec(), ptr::null_mut()) };
+        self.completion(result)
+    }
+
+    // 相当于 new function(...args)：this 是一个新的空对象，new.target 是 function。
+    // 函数返回对象时以它作为结果，否则结果是 this。模拟环境没有原型链
+    pub fn construct(
+        &self,
+        function: napi_value,
+        args: &[napi_value],
+    ) -> Result<napi_value, napi_value> {
+        let this = self.create_object();
+        let result = unsafe { napi::call(self.raw(), this, function, args.to_vec(), function) };
+        let result = self.completion(result)?;
+        Ok(match self.state.get(result) {
+            Value::Object(_) => result,
+            _ => this,
+        })
+    }
+
+    fn completion(
+        &self,
+        result: Result<napi_value, sys::napi_status>,
+    ) -> Result<napi_value, napi_value> {
+        if let Some(exception) = self.state.exception.take() {
+            return Err(self.state.alloc(exception));
+        }
+        Ok(result.expect("expected a function"))
+    }
+
+    pub fn promise_state(&self, promise: napi_value) -> PromiseState {
+        let id = self.state.object_id(promise);
+        let state = id.and_then(|id| {
+            self.state.with_object(id, |object| match &object.kind {
+                Kind::Promise(state) => Some(state.clone()),
+                _ => None,
+            })
+        });
+        match state {
+            Some(Promise::Pending) => PromiseState::Pending,
+            Some(Promise::Fulfilled(value)) => PromiseState::Fulfilled(self.state.alloc(value)),
+            Some(Promise::Rejected(value)) => PromiseState::Rejected(self.state.alloc(value)),
+            None => panic!("expected a promise, found {}", self.describe(promise)),
+        }
+    }
+
+    // 运行事件循环，直到没有正在执行的 async work 和被引用的 threadsafe function，
+    // 和 Node.js 进程在事件循环空闲时退出的条件一致
+    pub fn run(&self) {
+        self.state.wakeup.release();
+        loop {
+            let seen = self.state.wakeup.generation();
+            if self.state.tick() {
+                continue;
+            }
+            if !self.state.alive() {
+                return;
+            }
+            self.state.wakeup.wait(seen);
+        }
+    }
+
+    // 之后 queue 的 async work 在下一次 run() 之前都不会开始执行，
+    // 相当于线程池被占满，用于测试任务在排队期间被取消的情况
+    pub fn hold_async_work(&self) {
+        self.state.wakeup.hold();
+    }
+
+    // 相当于 N-API 的 handle scope：f 中创建的句柄在返回之后全部失效。
+    // 模拟环境没有 GC，循环调用大量函数时用它回收句柄占用的内存
+    pub fn handle_scope<R>(&self, f: impl FnOnce() -> R) -> R {
+        let mark = self.state.values.borrow().len();
+        let result = f();
+        self.state.values.borrow_mut().truncate(mark);
+        result
+    }
+
+    // 取出事件循环回调中未被处理的异常
+    pub fn take_uncaught(&self) -> Vec<napi_value> {
+        let uncaught = self.state.uncaught.take();
+        uncaught
+            .into_iter()
+            .map(|value| self.state.alloc(value))
+            .collect()
+    }
+
+    // 像开启了 V8 内存沙箱的宿主一样拒绝创建 external buffer
+    pub fn disallow_external_buffers(&self) {
+        self.state.external_buffers.set(false);
+    }
+
+    // 通过 napi_adjust_external_memory 报告的外部内存总量
+    pub fn external_memory(&self) -> i64 {
+        self.state.external_memory.get()
+    }
+
+    // 用于断言失败时的提示信息
+    pub fn describe(&self, value: napi_value) -> String {
+        match self.state.get(value) {
+            Value::Undefined => "undefined".to_string(),
+            Value::Null => "null".to_string(),
+            Value::Boolean(b) => b.to_string(),
+            Value::Number(n) => n.to_string(),
+            Value::String(s) => format!("{:?}", s),
+            Value::Symbol(_) => "Symbol()".to_string(),
+            Value::BigInt(negative, words) => format!("BigInt({}, {:?})", negative, words),
+            Value::Object(id) => self.state.with_object(id, |object| match &object.kind {
+                Kind::Error(name) => {
+                    let message = object.get(&Key::String("message".to_string()));
+                    format!("{}: {:?}", name, message.unwrap_or(Value::Undefined))
+                }
+                Kind::Function { .. } | Kind::Closure(_) => "[Function]".to_string(),
+                Kind::Array(_) => "[Array]".to_string(),
+                _ => format!("[Object with keys {:?}]", object.keys()),
+            }),
+        }
+    }
+}
+
+impl Default for Env {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for Env {
+    fn drop(&mut self) {
+        self.state.teardown();
+    }
+}
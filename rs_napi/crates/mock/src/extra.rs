@@ -0,0 +1,319 @@
+#![allow(clippy::missing_safety_doc)]
+
+use std::os::raw::{c_char, c_void};
+use std::slice;
+use sys::{
+    napi_callback, napi_env, napi_property_attributes, napi_property_descriptor, napi_ref,
+    napi_status, napi_value,
+};
+
+use crate::env::State;
+use crate::napi::{
+    self, c_string, get_property, throw_error, with_kind, write, NAPI_NUMBER_EXPECTED,
+};
+use crate::value::{Key, Kind, Value};
+
+// sys 中没有声明、但 napi-rs 编译出的模块会用到的函数，只在导出符号时才需要
+
+#[no_mangle]
+pub unsafe extern "C" fn napi_create_uint32(
+    env: napi_env,
+    value: u32,
+    result: *mut napi_value,
+) -> napi_status {
+    write(
+        result,
+        State::from_raw(env).alloc(Value::Number(value as f64)),
+    );
+    sys::napi_ok
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn napi_create_int32(
+    env: napi_env,
+    value: i32,
+    result: *mut napi_value,
+) -> napi_status {
+    write(
+        result,
+        State::from_raw(env).alloc(Value::Number(value as f64)),
+    );
+    sys::napi_ok
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn napi_create_int64(
+    env: napi_env,
+    value: i64,
+    result: *mut napi_value,
+) -> napi_status {
+    write(
+        result,
+        State::from_raw(env).alloc(Value::Number(value as f64)),
+    );
+    sys::napi_ok
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn napi_get_value_uint32(
+    env: napi_env,
+    value: napi_value,
+    result: *mut u32,
+) -> napi_status {
+    match State::from_raw(env).get(value) {
+        Value::Number(n) => {
+            write(result, n as u32);
+            sys::napi_ok
+        }
+        _ => NAPI_NUMBER_EXPECTED,
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn napi_get_value_int32(
+    env: napi_env,
+    value: napi_value,
+    result: *mut i32,
+) -> napi_status {
+    match State::from_raw(env).get(value) {
+        Value::Number(n) => {
+            write(result, n as i32);
+            sys::napi_ok
+        }
+        _ => NAPI_NUMBER_EXPECTED,
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn napi_get_named_property(
+    env: napi_env,
+    object: napi_value,
+    utf8name: *const c_char,
+    result: *mut napi_value,
+) -> napi_status {
+    let state = State::from_raw(env);
+    match get_property(state, object, Key::String(c_string(utf8name))) {
+        Ok(value) => {
+            write(result, state.alloc(value));
+            sys::napi_ok
+        }
+        Err(status) => status,
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn napi_is_error(
+    env: napi_env,
+    value: napi_value,
+    result: *mut bool,
+) -> napi_status {
+    let state = State::from_raw(env);
+    let error = with_kind(state, value, |kind| Some(matches!(kind, Kind::Error(_))));
+    write(result, error.unwrap_or(false));
+    sys::napi_ok
+}
+
+// 模拟环境中不能 new 一个函数，这里只创建构造函数本身：
+// static 属性定义在构造函数上，其他属性定义在它的 prototype 对象上
+#[no_mangle]
+#[allow(clippy::too_many_arguments)]
+pub unsafe extern "C" fn napi_define_class(
+    env: napi_env,
+    utf8name: *const c_char,
+    length: usize,
+    constructor: napi_callback,
+    data: *mut c_void,
+    property_count: usize,
+    properties: *const napi_property_descriptor,
+    result: *mut napi_value,
+) -> napi_status {
+    let mut class = std::ptr::null_mut();
+    let status = napi::napi_create_function(env, utf8name, length, constructor, data, &mut class);
+    if status != sys::napi_ok {
+        return status;
+    }
+    let properties = if property_count == 0 {
+        &[]
+    } else {
+        slice::from_raw_parts(properties, property_count)
+    };
+    let (statics, instance): (Vec<_>, Vec<_>) = properties.iter().partition(|property| {
+        property
+            .attributes
+            .contains(napi_property_attributes::napi_static)
+    });
+    let statics = statics.into_iter().copied().collect::<Vec<_>>();
+    let instance = instance.into_iter().copied().collect::<Vec<_>>();
+    let mut prototype = std::ptr::null_mut();
+    napi::napi_create_object(env, &mut prototype);
+    napi::napi_define_properties(env, class, statics.len(), statics.as_ptr());
+    napi::napi_define_properties(env, prototype, instance.len(), instance.as_ptr());
+    napi::napi_set_named_property(env, class, c"prototype".as_ptr(), prototype);
+    write(result, class);
+    sys::napi_ok
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn napi_throw_type_error(
+    env: napi_env,
+    code: *const c_char,
+    msg: *const c_char,
+) -> napi_status {
+    throw_error(env, "TypeError", code, msg)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn napi_throw_range_error(
+    env: napi_env,
+    code: *const c_char,
+    msg: *const c_char,
+) -> napi_status {
+    throw_error(env, "RangeError", code, msg)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn napi_add_env_cleanup_hook(
+    env: napi_env,
+    fun: Option<unsafe extern "C" fn(*mut c_void)>,
+    arg: *mut c_void,
+) -> napi_status {
+    match fun {
+        Some(fun) => {
+            let state = State::from_raw(env);
+            state.cleanup_hooks.borrow_mut().push((fun, arg as usize));
+            sys::napi_ok
+        }
+        None => napi::NAPI_INVALID_ARG,
+    }
+}
+
+// 没有 GC，和 napi_create_reference 一样忽略引用计数
+#[no_mangle]
+pub unsafe extern "C" fn napi_reference_unref(
+    _env: napi_env,
+    _reference: napi_ref,
+    result: *mut u32,
+) -> napi_status {
+    if !result.is_null() {
+        write(result, 0);
+    }
+    sys::napi_ok
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn napi_has_named_property(
+    env: napi_env,
+    object: napi_value,
+    utf8name: *const c_char,
+    result: *mut bool,
+) -> napi_status {
+    let state = State::from_raw(env);
+    let Some(id) = state.object_id(object) else {
+        return napi::NAPI_OBJECT_EXPECTED;
+    };
+    let key = Key::String(c_string(utf8name));
+    write(
+        result,
+        state.with_object(id, |object| object.get(&key)).is_some(),
+    );
+    sys::napi_ok
+}
+
+// 按 JS 的 String(value) 转换，对象只区分 Error 和其他对象
+#[no_mangle]
+pub unsafe extern "C" fn napi_coerce_to_string(
+    env: napi_env,
+    value: napi_value,
+    result: *mut napi_value,
+) -> napi_status {
+    let state = State::from_raw(env);
+    let string = match state.get(value) {
+        Value::Undefined => "undefined".to_string(),
+        Value::Null => "null".to_string(),
+        Value::Boolean(b) => b.to_string(),
+        Value::Number(n) if n.is_infinite() => {
+            if n > 0.0 { "Infinity" } else { "-Infinity" }.to_string()
+        }
+        Value::Number(n) => n.to_string(),
+        Value::String(s) => s,
+        Value::Symbol(_) => return napi::NAPI_STRING_EXPECTED,
+        Value::BigInt(negative, words) => {
+            let digits = bigint_digits(words);
+            if negative && digits != "0" {
+                format!("-{}", digits)
+            } else {
+                digits
+            }
+        }
+        Value::Object(id) => state.with_object(id, |object| match object.kind {
+            Kind::Error(name) => match object.get(&Key::String("message".to_string())) {
+                Some(Value::String(message)) if !message.is_empty() => {
+                    format!("{}: {}", name, message)
+                }
+                _ => name.to_string(),
+            },
+            _ => "[object Object]".to_string(),
+        }),
+    };
+    write(result, state.alloc(Value::String(string)));
+    sys::napi_ok
+}
+
+// BigInt 绝对值的十进制表示，每次除以 10^19 取出一段
+fn bigint_digits(mut words: Vec<u64>) -> String {
+    const BASE: u128 = 10_000_000_000_000_000_000;
+    let mut chunks = Vec::new();
+    while words.last() == Some(&0) {
+        words.pop();
+    }
+    while !words.is_empty() {
+        let mut rem = 0u128;
+        for word in words.iter_mut().rev() {
+            let current = (rem << 64) | *word as u128;
+            *word = (current / BASE) as u64;
+            rem = current % BASE;
+        }
+        chunks.push(rem as u64);
+        while words.last() == Some(&0) {
+            words.pop();
+        }
+    }
+    match chunks.split_last() {
+        None => "0".to_string(),
+        Some((first, rest)) => rest.iter().rev().fold(first.to_string(), |digits, chunk| {
+            format!("{}{:019}", digits, chunk)
+        }),
+    }
+}
+
+// 和事件循环的回调中没有被处理的异常一样记录下来，可以通过 Env::take_uncaught 取出
+#[no_mangle]
+pub unsafe extern "C" fn napi_fatal_exception(env: napi_env, err: napi_value) -> napi_status {
+    let state = State::from_raw(env);
+    let err = state.get(err);
+    state.uncaught.borrow_mut().push(err);
+    sys::napi_ok
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn napi_fatal_error(
+    location: *const c_char,
+    location_len: usize,
+    message: *const c_char,
+    message_len: usize,
+) -> ! {
+    let read = |s: *const c_char, len: usize| {
+        if s.is_null() {
+            String::new()
+        } else if len == usize::MAX {
+            c_string(s)
+        } else {
+            String::from_utf8_lossy(slice::from_raw_parts(s.cast(), len)).into_owned()
+        }
+    };
+    panic!(
+        "FATAL ERROR: {} {}",
+        read(location, location_len),
+        read(message, message_len)
+    );
+}
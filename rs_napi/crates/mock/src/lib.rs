@@ -0,0 +1,97 @@
+// 一个用 Rust 实现的、不依赖 Node.js 的 N-API 运行时，用于测试原生模块。
+// JS 值保存在内存中的对象表里，没有 GC 和原型链；async work 在新线程上执行，
+// 完成后和 threadsafe function 的调用一起由 Env::run 在当前线程上处理。
+// 模块需要开启 sys 的 dynamic-loading feature，并通过 sys::load_with(mock::symbol) 加载这里的实现；
+// 开启 export-symbols feature 后这些函数也以 napi_* 的名字导出，供 dlopen 加载的原生模块直接链接
+mod env;
+#[cfg(feature = "export-symbols")]
+mod extra;
+mod napi;
+mod task;
+mod value;
+
+pub use env::{Env, PromiseState};
+
+use std::os::raw::c_void;
+
+macro_rules! symbols {
+    ($($name:ident),* $(,)?) => {
+        // 按名字查找模拟实现的 napi_* 函数，传给 sys::load_with
+        pub fn symbol(name: &str) -> Option<*const c_void> {
+            match name {
+                $(stringify!($name) => Some(napi::$name as *const c_void),)*
+                _ => None,
+            }
+        }
+    };
+}
+
+symbols! {
+    napi_get_cb_info,
+    napi_get_value_double,
+    napi_create_double,
+    napi_module_register,
+    napi_define_properties,
+    napi_set_named_property,
+    napi_create_function,
+    napi_create_object,
+    napi_create_array_with_length,
+    napi_set_element,
+    napi_create_string_utf8,
+    napi_get_value_string_utf8,
+    napi_get_array_length,
+    napi_get_element,
+    napi_get_new_target,
+    napi_typeof,
+    napi_get_undefined,
+    napi_get_null,
+    napi_get_boolean,
+    napi_get_value_bool,
+    napi_throw_error,
+    napi_get_property_names,
+    napi_get_all_property_names,
+    napi_set_property,
+    napi_get_property,
+    napi_is_array,
+    napi_is_date,
+    napi_create_symbol,
+    napi_create_date,
+    napi_get_date_value,
+    napi_create_bigint_words,
+    napi_get_value_bigint_words,
+    napi_is_promise,
+    napi_throw,
+    napi_is_exception_pending,
+    napi_create_error,
+    napi_create_type_error,
+    napi_create_range_error,
+    node_api_create_syntax_error,
+    napi_adjust_external_memory,
+    napi_create_external,
+    napi_get_value_external,
+    napi_create_external_buffer,
+    napi_get_buffer_info,
+    napi_is_buffer,
+    napi_type_tag_object,
+    napi_check_object_type_tag,
+    napi_create_async_work,
+    napi_delete_async_work,
+    napi_queue_async_work,
+    napi_cancel_async_work,
+    napi_create_promise,
+    napi_resolve_deferred,
+    napi_reject_deferred,
+    napi_create_reference,
+    napi_delete_reference,
+    napi_get_reference_value,
+    napi_add_finalizer,
+    napi_call_function,
+    napi_get_and_clear_last_exception,
+    napi_create_threadsafe_function,
+    napi_call_threadsafe_function,
+    napi_acquire_threadsafe_function,
+    napi_release_threadsafe_function,
+    napi_ref_threadsafe_function,
+    napi_unref_threadsafe_function,
+    napi_get_global,
+}
@@ -0,0 +1,1234 @@
+#![allow(clippy::missing_safety_doc)]
+
+use std::ffi::CStr;
+use std::os::raw::{c_char, c_int, c_void};
+use std::ptr;
+use std::slice;
+use std::sync::{Arc, Mutex};
+use sys::{
+    napi_async_complete_callback, napi_async_execute_callback, napi_async_work, napi_callback,
+    napi_callback_info, napi_deferred, napi_env, napi_finalize, napi_module,
+    napi_property_attributes, napi_property_descriptor, napi_ref, napi_status,
+    napi_threadsafe_function, napi_threadsafe_function_call_js, napi_threadsafe_function_call_mode,
+    napi_threadsafe_function_release_mode, napi_type_tag, napi_value, napi_valuetype,
+};
+
+use crate::env::{Env, State};
+use crate::task::{SendPtr, Tsfn, TsfnState, Work};
+use crate::value::{Finalizer, Key, Kind, Promise, Value};
+
+// 和 js_native_api_types.h 中的 napi_status 一一对应，只列出用到的
+pub(crate) const NAPI_INVALID_ARG: napi_status = 1;
+pub(crate) const NAPI_OBJECT_EXPECTED: napi_status = 2;
+pub(crate) const NAPI_STRING_EXPECTED: napi_status = 3;
+const NAPI_FUNCTION_EXPECTED: napi_status = 5;
+pub(crate) const NAPI_NUMBER_EXPECTED: napi_status = 6;
+const NAPI_BOOLEAN_EXPECTED: napi_status = 7;
+const NAPI_ARRAY_EXPECTED: napi_status = 8;
+const NAPI_GENERIC_FAILURE: napi_status = 9;
+const NAPI_PENDING_EXCEPTION: napi_status = 10;
+pub(crate) const NAPI_CLOSING: napi_status = 16;
+const NAPI_BIGINT_EXPECTED: napi_status = 17;
+const NAPI_DATE_EXPECTED: napi_status = 18;
+
+// napi_callback_info 指向的结构，只在一次调用期间有效
+struct CallInfo {
+    this: napi_value,
+    args: Vec<napi_value>,
+    data: *mut c_void,
+    // 通过 new 调用时是被调用的函数，普通调用时为 NULL
+    new_target: napi_value,
+}
+
+pub(crate) unsafe fn write<T>(ptr: *mut T, value: T) {
+    if !ptr.is_null() {
+        *ptr = value;
+    }
+}
+
+pub(crate) unsafe fn c_string(s: *const c_char) -> String {
+    CStr::from_ptr(s).to_string_lossy().into_owned()
+}
+
+// 把任意值转换成属性名，和 JS 中 object[key] 的规则一致
+fn to_key(value: Value) -> Key {
+    match value {
+        Value::Symbol(id) => Key::Symbol(id),
+        Value::String(s) => Key::String(s),
+        Value::Number(n) if n.fract() == 0.0 && n.abs() < 1e15 => {
+            Key::String((n as i64).to_string())
+        }
+        Value::Number(n) => Key::String(n.to_string()),
+        Value::Boolean(b) => Key::String(b.to_string()),
+        Value::Null => Key::String("null".to_string()),
+        Value::Undefined => Key::String("undefined".to_string()),
+        Value::BigInt(..) | Value::Object(_) => Key::String("[object]".to_string()),
+    }
+}
+
+// 读取对象的属性，object 不是对象时返回 napi_object_expected
+pub(crate) fn get_property(
+    state: &State,
+    object: napi_value,
+    key: Key,
+) -> Result<Value, napi_status> {
+    let id = state.object_id(object).ok_or(NAPI_OBJECT_EXPECTED)?;
+    Ok(state
+        .with_object(id, |object| object.get(&key))
+        .unwrap_or(Value::Undefined))
+}
+
+fn set_property(state: &State, object: napi_value, key: Key, value: Value) -> napi_status {
+    match state.object_id(object) {
+        Some(id) => {
+            state.with_object(id, |object| object.set(key, value, true));
+            sys::napi_ok
+        }
+        None => NAPI_OBJECT_EXPECTED,
+    }
+}
+
+// 读取对象的 kind 中的数据，不是对象或者 kind 不匹配时返回 None
+pub(crate) fn with_kind<R>(
+    state: &State,
+    value: napi_value,
+    f: impl FnOnce(&mut Kind) -> Option<R>,
+) -> Option<R> {
+    let id = state.object_id(value)?;
+    state.with_object(id, |object| f(&mut object.kind))
+}
+
+#[cfg_attr(feature = "export-symbols", no_mangle)]
+pub unsafe extern "C" fn napi_get_cb_info(
+    env: napi_env,
+    cbinfo: napi_callback_info,
+    argc: *mut usize,
+    argv: *mut napi_value,
+    this_arg: *mut napi_value,
+    data: *mut *mut c_void,
+) -> napi_status {
+    let state = State::from_raw(env);
+    let info = &*cbinfo.cast_const().cast::<CallInfo>();
+    // argc 传入时是 argv 的容量，返回时是实际的参数个数，多出的位置填充 undefined
+    if !argc.is_null() && !argv.is_null() {
+        for index in 0..*argc {
+            *argv.add(index) = match info.args.get(index) {
+                Some(arg) => *arg,
+                None => state.alloc(Value::Undefined),
+            };
+        }
+    }
+    write(argc, info.args.len());
+    write(this_arg, info.this);
+    write(data, info.data);
+    sys::napi_ok
+}
+
+#[cfg_attr(feature = "export-symbols", no_mangle)]
+pub unsafe extern "C" fn napi_get_value_double(
+    env: napi_env,
+    value: napi_value,
+    result: *mut f64,
+) -> napi_status {
+    match State::from_raw(env).get(value) {
+        Value::Number(n) => {
+            write(result, n);
+            sys::napi_ok
+        }
+        _ => NAPI_NUMBER_EXPECTED,
+    }
+}
+
+#[cfg_attr(feature = "export-symbols", no_mangle)]
+pub unsafe extern "C" fn napi_create_double(
+    env: napi_env,
+    value: f64,
+    result: *mut napi_value,
+) -> napi_status {
+    write(result, State::from_raw(env).alloc(Value::Number(value)));
+    sys::napi_ok
+}
+
+// 模拟环境只支持通过 napi_register_module_v1 加载模块
+#[cfg_attr(feature = "export-symbols", no_mangle)]
+pub unsafe extern "C" fn napi_module_register(_mod: *mut napi_module) {}
+
+#[cfg_attr(feature = "export-symbols", no_mangle)]
+pub unsafe extern "C" fn napi_define_properties(
+    env: napi_env,
+    object: napi_value,
+    property_count: usize,
+    properties: *const napi_property_descriptor,
+) -> napi_status {
+    let state = State::from_raw(env);
+    let Some(id) = state.object_id(object) else {
+        return NAPI_OBJECT_EXPECTED;
+    };
+    let properties = if property_count == 0 {
+        &[]
+    } else {
+        slice::from_raw_parts(properties, property_count)
+    };
+    for property in properties {
+        let key = if property.utf8name.is_null() {
+            to_key(state.get(property.name))
+        } else {
+            Key::String(c_string(property.utf8name))
+        };
+        // 不支持 getter/setter，只支持值和方法
+        let value = match property.method {
+            Some(_) => state.create_function(
+                "",
+                Kind::Function {
+                    cb: property.method,
+                    data: property.data,
+                },
+            ),
+            None => state.get(property.value),
+        };
+        let enumerable = property
+            .attributes
+            .contains(napi_property_attributes::napi_enumerable);
+        // 方法和值一样由 napi_writable 决定是否可写
+        let writable = property
+            .attributes
+            .contains(napi_property_attributes::napi_writable);
+        state.with_object(id, |object| {
+            object.define(key, value, enumerable, writable, true)
+        });
+    }
+    sys::napi_ok
+}
+
+#[cfg_attr(feature = "export-symbols", no_mangle)]
+pub unsafe extern "C" fn napi_set_named_property(
+    env: napi_env,
+    object: napi_value,
+    utf8name: *const c_char,
+    value: napi_value,
+) -> napi_status {
+    let state = State::from_raw(env);
+    let value = state.get(value);
+    set_property(state, object, Key::String(c_string(utf8name)), value)
+}
+
+#[cfg_attr(feature = "export-symbols", no_mangle)]
+pub unsafe extern "C" fn napi_create_function(
+    env: napi_env,
+    utf8name: *const c_char,
+    length: usize,
+    cb: napi_callback,
+    data: *mut c_void,
+    result: *mut napi_value,
+) -> napi_status {
+    let state = State::from_raw(env);
+    let name = if utf8name.is_null() {
+        String::new()
+    } else if length == sys::NAPI_AUTO_LENGTH {
+        c_string(utf8name)
+    } else {
+        String::from_utf8_lossy(slice::from_raw_parts(utf8name.cast(), length)).into_owned()
+    };
+    let function = state.create_function(&name, Kind::Function { cb, data });
+    write(result, state.alloc(function));
+    sys::napi_ok
+}
+
+#[cfg_attr(feature = "export-symbols", no_mangle)]
+pub unsafe extern "C" fn napi_create_object(env: napi_env, result: *mut napi_value) -> napi_status {
+    let state = State::from_raw(env);
+    let object = state.new_object(Kind::Plain);
+    write(result, state.alloc(object));
+    sys::napi_ok
+}
+
+#[cfg_attr(feature = "export-symbols", no_mangle)]
+pub unsafe extern "C" fn napi_create_array_with_length(
+    env: napi_env,
+    length: usize,
+    result: *mut napi_value,
+) -> napi_status {
+    let state = State::from_raw(env);
+    let array = state.new_object(Kind::Array(vec![Value::Undefined; length]));
+    write(result, state.alloc(array));
+    sys::napi_ok
+}
+
+#[cfg_attr(feature = "export-symbols", no_mangle)]
+pub unsafe extern "C" fn napi_set_element(
+    env: napi_env,
+    object: napi_value,
+    index: u32,
+    value: napi_value,
+) -> napi_status {
+    let state = State::from_raw(env);
+    let value = state.get(value);
+    set_property(state, object, Key::String(index.to_string()), value)
+}
+
+#[cfg_attr(feature = "export-symbols", no_mangle)]
+pub unsafe extern "C" fn napi_create_string_utf8(
+    env: napi_env,
+    str_: *const c_char,
+    length: usize,
+    result: *mut napi_value,
+) -> napi_status {
+    let state = State::from_raw(env);
+    let value = if length == sys::NAPI_AUTO_LENGTH {
+        c_string(str_)
+    } else if length == 0 {
+        String::new()
+    } else {
+        String::from_utf8_lossy(slice::from_raw_parts(str_.cast(), length)).into_owned()
+    };
+    write(result, state.alloc(Value::String(value)));
+    sys::napi_ok
+}
+
+#[cfg_attr(feature = "export-symbols", no_mangle)]
+pub unsafe extern "C" fn napi_get_value_string_utf8(
+    env: napi_env,
+    value: napi_value,
+    buf: *mut c_char,
+    bufsize: usize,
+    result: *mut usize,
+) -> napi_status {
+    let Value::String(value) = State::from_raw(env).get(value) else {
+        return NAPI_STRING_EXPECTED;
+    };
+    // buf 为 NULL 时只返回字节长度，否则复制并截断到 bufsize - 1 个字节，以 null 结尾
+    if buf.is_null() {
+        write(result, value.len());
+        return sys::napi_ok;
+    }
+    if bufsize == 0 {
+        write(result, 0);
+        return sys::napi_ok;
+    }
+    let len = value.len().min(bufsize - 1);
+    ptr::copy_nonoverlapping(value.as_ptr(), buf.cast(), len);
+    *buf.add(len) = 0;
+    write(result, len);
+    sys::napi_ok
+}
+
+#[cfg_attr(feature = "export-symbols", no_mangle)]
+pub unsafe extern "C" fn napi_get_array_length(
+    env: napi_env,
+    value: napi_value,
+    result: *mut u32,
+) -> napi_status {
+    let state = State::from_raw(env);
+    match with_kind(state, value, |kind| match kind {
+        Kind::Array(elements) => Some(elements.len() as u32),
+        _ => None,
+    }) {
+        Some(len) => {
+            write(result, len);
+            sys::napi_ok
+        }
+        None => NAPI_ARRAY_EXPECTED,
+    }
+}
+
+#[cfg_attr(feature = "export-symbols", no_mangle)]
+pub unsafe extern "C" fn napi_get_element(
+    env: napi_env,
+    object: napi_value,
+    index: u32,
+    result: *mut napi_value,
+) -> napi_status {
+    let state = State::from_raw(env);
+    match get_property(state, object, Key::String(index.to_string())) {
+        Ok(value) => {
+            write(result, state.alloc(value));
+            sys::napi_ok
+        }
+        Err(status) => status,
+    }
+}
+
+// 只有通过 Env::construct 调用时才有 new.target
+#[cfg_attr(feature = "export-symbols", no_mangle)]
+pub unsafe extern "C" fn napi_get_new_target(
+    _env: napi_env,
+    cbinfo: napi_callback_info,
+    result: *mut napi_value,
+) -> napi_status {
+    let info = &*cbinfo.cast_const().cast::<CallInfo>();
+    write(result, info.new_target);
+    sys::napi_ok
+}
+
+#[cfg_attr(feature = "export-symbols", no_mangle)]
+pub unsafe extern "C" fn napi_typeof(
+    env: napi_env,
+    value: napi_value,
+    result: *mut napi_valuetype,
+) -> napi_status {
+    let state = State::from_raw(env);
+    let ty = match state.get(value) {
+        Value::Undefined => sys::napi_undefined,
+        Value::Null => sys::napi_null,
+        Value::Boolean(_) => sys::napi_boolean,
+        Value::Number(_) => sys::napi_number,
+        Value::String(_) => sys::napi_string,
+        Value::Symbol(_) => sys::napi_symbol,
+        Value::BigInt(..) => sys::napi_bigint,
+        Value::Object(id) => state.with_object(id, |object| match object.kind {
+            Kind::Function { .. } | Kind::Closure(_) => sys::napi_function,
+            Kind::External(_) => sys::napi_external,
+            _ => sys::napi_object,
+        }),
+    };
+    write(result, ty);
+    sys::napi_ok
+}
+
+#[cfg_attr(feature = "export-symbols", no_mangle)]
+pub unsafe extern "C" fn napi_get_undefined(env: napi_env, result: *mut napi_value) -> napi_status {
+    write(result, State::from_raw(env).alloc(Value::Undefined));
+    sys::napi_ok
+}
+
+#[cfg_attr(feature = "export-symbols", no_mangle)]
+pub unsafe extern "C" fn napi_get_null(env: napi_env, result: *mut napi_value) -> napi_status {
+    write(result, State::from_raw(env).alloc(Value::Null));
+    sys::napi_ok
+}
+
+#[cfg_attr(feature = "export-symbols", no_mangle)]
+pub unsafe extern "C" fn napi_get_boolean(
+    env: napi_env,
+    value: bool,
+    result: *mut napi_value,
+) -> napi_status {
+    write(result, State::from_raw(env).alloc(Value::Boolean(value)));
+    sys::napi_ok
+}
+
+#[cfg_attr(feature = "export-symbols", no_mangle)]
+pub unsafe extern "C" fn napi_get_value_bool(
+    env: napi_env,
+    value: napi_value,
+    result: *mut bool,
+) -> napi_status {
+    match State::from_raw(env).get(value) {
+        Value::Boolean(b) => {
+            write(result, b);
+            sys::napi_ok
+        }
+        _ => NAPI_BOOLEAN_EXPECTED,
+    }
+}
+
+// 创建一个 name 类型的错误并设置为当前的异常，code 可以为空指针
+pub(crate) unsafe fn throw_error(
+    env: napi_env,
+    name: &'static str,
+    code: *const c_char,
+    msg: *const c_char,
+) -> napi_status {
+    let state = State::from_raw(env);
+    let code = if code.is_null() {
+        Value::Undefined
+    } else {
+        Value::String(c_string(code))
+    };
+    let error = state.create_error(name, code, Value::String(c_string(msg)));
+    *state.exception.borrow_mut() = Some(error);
+    sys::napi_ok
+}
+
+#[cfg_attr(feature = "export-symbols", no_mangle)]
+pub unsafe extern "C" fn napi_throw_error(
+    env: napi_env,
+    code: *const c_char,
+    msg: *const c_char,
+) -> napi_status {
+    throw_error(env, "Error", code, msg)
+}
+
+#[cfg_attr(feature = "export-symbols", no_mangle)]
+pub unsafe extern "C" fn napi_get_property_names(
+    env: napi_env,
+    object: napi_value,
+    result: *mut napi_value,
+) -> napi_status {
+    let state = State::from_raw(env);
+    let Some(id) = state.object_id(object) else {
+        return NAPI_OBJECT_EXPECTED;
+    };
+    let keys = state.with_object(id, |object| object.keys());
+    let names = state.new_object(Kind::Array(keys.into_iter().map(Value::String).collect()));
+    write(result, state.alloc(names));
+    sys::napi_ok
+}
+
+// mock 中的对象没有原型链，key_mode 不影响结果；属性没有记录是否可配置，
+// 只支持 enumerable 过滤条件，writable 和 configurable 不起作用
+#[cfg_attr(feature = "export-symbols", no_mangle)]
+pub unsafe extern "C" fn napi_get_all_property_names(
+    env: napi_env,
+    object: napi_value,
+    _key_mode: sys::napi_key_collection_mode,
+    key_filter: sys::napi_key_filter,
+    key_conversion: sys::napi_key_conversion,
+    result: *mut napi_value,
+) -> napi_status {
+    let state = State::from_raw(env);
+    let Some(id) = state.object_id(object) else {
+        return NAPI_OBJECT_EXPECTED;
+    };
+    let enumerable_only = key_filter & sys::napi_key_enumerable != 0;
+    let keys = state.with_object(id, |object| object.own_keys(enumerable_only));
+    let names = keys
+        .into_iter()
+        .filter_map(|key| match key {
+            Key::String(_) if key_filter & sys::napi_key_skip_strings != 0 => None,
+            Key::Symbol(_) if key_filter & sys::napi_key_skip_symbols != 0 => None,
+            // 和 V8 一样，keep_numbers 时数组下标这类整数 key 以 number 的形式返回
+            Key::String(name) => match name.parse::<u32>() {
+                Ok(index) if key_conversion == sys::napi_key_keep_numbers => {
+                    Some(Value::Number(index as f64))
+                }
+                _ => Some(Value::String(name)),
+            },
+            Key::Symbol(id) => Some(Value::Symbol(id)),
+        })
+        .collect();
+    let names = state.new_object(Kind::Array(names));
+    write(result, state.alloc(names));
+    sys::napi_ok
+}
+
+#[cfg_attr(feature = "export-symbols", no_mangle)]
+pub unsafe extern "C" fn napi_set_property(
+    env: napi_env,
+    object: napi_value,
+    key: napi_value,
+    value: napi_value,
+) -> napi_status {
+    let state = State::from_raw(env);
+    let (key, value) = (to_key(state.get(key)), state.get(value));
+    set_property(state, object, key, value)
+}
+
+#[cfg_attr(feature = "export-symbols", no_mangle)]
+pub unsafe extern "C" fn napi_get_property(
+    env: napi_env,
+    object: napi_value,
+    key: napi_value,
+    result: *mut napi_value,
+) -> napi_status {
+    let state = State::from_raw(env);
+    match get_property(state, object, to_key(state.get(key))) {
+        Ok(value) => {
+            write(result, state.alloc(value));
+            sys::napi_ok
+        }
+        Err(status) => status,
+    }
+}
+
+#[cfg_attr(feature = "export-symbols", no_mangle)]
+pub unsafe extern "C" fn napi_is_array(
+    env: napi_env,
+    value: napi_value,
+    result: *mut bool,
+) -> napi_status {
+    let state = State::from_raw(env);
+    let is_array = with_kind(state, value, |kind| Some(matches!(kind, Kind::Array(_))));
+    write(result, is_array.unwrap_or(false));
+    sys::napi_ok
+}
+
+#[cfg_attr(feature = "export-symbols", no_mangle)]
+pub unsafe extern "C" fn napi_is_date(
+    env: napi_env,
+    value: napi_value,
+    is_date: *mut bool,
+) -> napi_status {
+    let state = State::from_raw(env);
+    let date = with_kind(state, value, |kind| Some(matches!(kind, Kind::Date(_))));
+    write(is_date, date.unwrap_or(false));
+    sys::napi_ok
+}
+
+#[cfg_attr(feature = "export-symbols", no_mangle)]
+pub unsafe extern "C" fn napi_create_symbol(
+    env: napi_env,
+    description: napi_value,
+    result: *mut napi_value,
+) -> napi_status {
+    let state = State::from_raw(env);
+    let description = match state.get(description) {
+        Value::String(s) => Some(s),
+        Value::Undefined => None,
+        _ => return NAPI_STRING_EXPECTED,
+    };
+    let id = {
+        let mut symbols = state.symbols.borrow_mut();
+        symbols.push(description);
+        symbols.len() - 1
+    };
+    write(result, state.alloc(Value::Symbol(id)));
+    sys::napi_ok
+}
+
+#[cfg_attr(feature = "export-symbols", no_mangle)]
+pub unsafe extern "C" fn napi_create_date(
+    env: napi_env,
+    time: f64,
+    result: *mut napi_value,
+) -> napi_status {
+    let state = State::from_raw(env);
+    let date = state.new_object(Kind::Date(time));
+    write(result, state.alloc(date));
+    sys::napi_ok
+}
+
+#[cfg_attr(feature = "export-symbols", no_mangle)]
+pub unsafe extern "C" fn napi_get_date_value(
+    env: napi_env,
+    value: napi_value,
+    result: *mut f64,
+) -> napi_status {
+    let state = State::from_raw(env);
+    match with_kind(state, value, |kind| match kind {
+        Kind::Date(time) => Some(*time),
+        _ => None,
+    }) {
+        Some(time) => {
+            write(result, time);
+            sys::napi_ok
+        }
+        None => NAPI_DATE_EXPECTED,
+    }
+}
+
+#[cfg_attr(feature = "export-symbols", no_mangle)]
+pub unsafe extern "C" fn napi_create_bigint_words(
+    env: napi_env,
+    sign_bit: c_int,
+    word_count: usize,
+    words: *const u64,
+    result: *mut napi_value,
+) -> napi_status {
+    let state = State::from_raw(env);
+    let mut words = if word_count == 0 {
+        Vec::new()
+    } else {
+        slice::from_raw_parts(words, word_count).to_vec()
+    };
+    // 和 V8 一样去掉高位的 0，0 没有符号
+    while words.last() == Some(&0) {
+        words.pop();
+    }
+    let negative = sign_bit != 0 && !words.is_empty();
+    write(result, state.alloc(Value::BigInt(negative, words)));
+    sys::napi_ok
+}
+
+#[cfg_attr(feature = "export-symbols", no_mangle)]
+pub unsafe extern "C" fn napi_get_value_bigint_words(
+    env: napi_env,
+    value: napi_value,
+    sign_bit: *mut c_int,
+    word_count: *mut usize,
+    words: *mut u64,
+) -> napi_status {
+    let Value::BigInt(negative, value) = State::from_raw(env).get(value) else {
+        return NAPI_BIGINT_EXPECTED;
+    };
+    // words 为 NULL 时只返回需要的个数
+    if !words.is_null() {
+        let count = value.len().min(*word_count);
+        ptr::copy_nonoverlapping(value.as_ptr(), words, count);
+        write(sign_bit, negative as c_int);
+    }
+    write(word_count, value.len());
+    sys::napi_ok
+}
+
+#[cfg_attr(feature = "export-symbols", no_mangle)]
+pub unsafe extern "C" fn napi_is_promise(
+    env: napi_env,
+    value: napi_value,
+    is_promise: *mut bool,
+) -> napi_status {
+    let state = State::from_raw(env);
+    let promise = with_kind(state, value, |kind| Some(matches!(kind, Kind::Promise(_))));
+    write(is_promise, promise.unwrap_or(false));
+    sys::napi_ok
+}
+
+#[cfg_attr(feature = "export-symbols", no_mangle)]
+pub unsafe extern "C" fn napi_throw(env: napi_env, error: napi_value) -> napi_status {
+    let state = State::from_raw(env);
+    *state.exception.borrow_mut() = Some(state.get(error));
+    sys::napi_ok
+}
+
+#[cfg_attr(feature = "export-symbols", no_mangle)]
+pub unsafe extern "C" fn napi_is_exception_pending(
+    env: napi_env,
+    result: *mut bool,
+) -> napi_status {
+    write(result, State::from_raw(env).exception.borrow().is_some());
+    sys::napi_ok
+}
+
+unsafe fn create_error(
+    env: napi_env,
+    name: &'static str,
+    code: napi_value,
+    msg: napi_value,
+    result: *mut napi_value,
+) -> napi_status {
+    let state = State::from_raw(env);
+    let message = state.get(msg);
+    if !matches!(message, Value::String(_)) {
+        return NAPI_STRING_EXPECTED;
+    }
+    let error = state.create_error(name, state.get(code), message);
+    write(result, state.alloc(error));
+    sys::napi_ok
+}
+
+#[cfg_attr(feature = "export-symbols", no_mangle)]
+pub unsafe extern "C" fn napi_create_error(
+    env: napi_env,
+    code: napi_value,
+    msg: napi_value,
+    result: *mut napi_value,
+) -> napi_status {
+    create_error(env, "Error", code, msg, result)
+}
+
+#[cfg_attr(feature = "export-symbols", no_mangle)]
+pub unsafe extern "C" fn napi_create_type_error(
+    env: napi_env,
+    code: napi_value,
+    msg: napi_value,
+    result: *mut napi_value,
+) -> napi_status {
+    create_error(env, "TypeError", code, msg, result)
+}
+
+#[cfg_attr(feature = "export-symbols", no_mangle)]
+pub unsafe extern "C" fn napi_create_range_error(
+    env: napi_env,
+    code: napi_value,
+    msg: napi_value,
+    result: *mut napi_value,
+) -> napi_status {
+    create_error(env, "RangeError", code, msg, result)
+}
+
+#[cfg_attr(feature = "export-symbols", no_mangle)]
+pub unsafe extern "C" fn node_api_create_syntax_error(
+    env: napi_env,
+    code: napi_value,
+    msg: napi_value,
+    result: *mut napi_value,
+) -> napi_status {
+    create_error(env, "SyntaxError", code, msg, result)
+}
+
+#[cfg_attr(feature = "export-symbols", no_mangle)]
+pub unsafe extern "C" fn napi_adjust_external_memory(
+    env: napi_env,
+    change_in_bytes: i64,
+    adjusted_value: *mut i64,
+) -> napi_status {
+    let state = State::from_raw(env);
+    let value = state.external_memory.get() + change_in_bytes;
+    state.external_memory.set(value);
+    write(adjusted_value, value);
+    sys::napi_ok
+}
+
+// 给对象添加一个在环境销毁时调用的 finalizer
+fn add_finalizer(
+    state: &State,
+    value: &Value,
+    cb: napi_finalize,
+    data: *mut c_void,
+    hint: *mut c_void,
+) {
+    if let (Value::Object(id), Some(_)) = (value, cb) {
+        state.with_object(*id, |object| {
+            object.finalizers.push(Finalizer { cb, data, hint })
+        });
+    }
+}
+
+#[cfg_attr(feature = "export-symbols", no_mangle)]
+pub unsafe extern "C" fn napi_create_external(
+    env: napi_env,
+    data: *mut c_void,
+    finalize_cb: napi_finalize,
+    finalize_hint: *mut c_void,
+    result: *mut napi_value,
+) -> napi_status {
+    let state = State::from_raw(env);
+    let external = state.new_object(Kind::External(data));
+    add_finalizer(state, &external, finalize_cb, data, finalize_hint);
+    write(result, state.alloc(external));
+    sys::napi_ok
+}
+
+#[cfg_attr(feature = "export-symbols", no_mangle)]
+pub unsafe extern "C" fn napi_get_value_external(
+    env: napi_env,
+    value: napi_value,
+    result: *mut *mut c_void,
+) -> napi_status {
+    let state = State::from_raw(env);
+    match with_kind(state, value, |kind| match kind {
+        Kind::External(data) => Some(*data),
+        _ => None,
+    }) {
+        Some(data) => {
+            write(result, data);
+            sys::napi_ok
+        }
+        None => NAPI_INVALID_ARG,
+    }
+}
+
+#[cfg_attr(feature = "export-symbols", no_mangle)]
+pub unsafe extern "C" fn napi_create_external_buffer(
+    env: napi_env,
+    length: usize,
+    data: *mut c_void,
+    finalize_cb: napi_finalize,
+    finalize_hint: *mut c_void,
+    result: *mut napi_value,
+) -> napi_status {
+    let state = State::from_raw(env);
+    if !state.external_buffers.get() {
+        return sys::napi_no_external_buffers_allowed;
+    }
+    let buffer = state.new_object(Kind::Buffer(data, length));
+    add_finalizer(state, &buffer, finalize_cb, data, finalize_hint);
+    write(result, state.alloc(buffer));
+    sys::napi_ok
+}
+
+#[cfg_attr(feature = "export-symbols", no_mangle)]
+pub unsafe extern "C" fn napi_get_buffer_info(
+    env: napi_env,
+    value: napi_value,
+    data: *mut *mut c_void,
+    length: *mut usize,
+) -> napi_status {
+    let state = State::from_raw(env);
+    match with_kind(state, value, |kind| match kind {
+        Kind::Buffer(data, len) => Some((*data, *len)),
+        _ => None,
+    }) {
+        Some((buffer, len)) => {
+            write(data, buffer);
+            write(length, len);
+            sys::napi_ok
+        }
+        None => NAPI_INVALID_ARG,
+    }
+}
+
+#[cfg_attr(feature = "export-symbols", no_mangle)]
+pub unsafe extern "C" fn napi_is_buffer(
+    env: napi_env,
+    value: napi_value,
+    result: *mut bool,
+) -> napi_status {
+    let state = State::from_raw(env);
+    let buffer = with_kind(state, value, |kind| Some(matches!(kind, Kind::Buffer(..))));
+    write(result, buffer.unwrap_or(false));
+    sys::napi_ok
+}
+
+#[cfg_attr(feature = "export-symbols", no_mangle)]
+pub unsafe extern "C" fn napi_type_tag_object(
+    env: napi_env,
+    value: napi_value,
+    type_tag: *const napi_type_tag,
+) -> napi_status {
+    let state = State::from_raw(env);
+    let Some(id) = state.object_id(value) else {
+        return NAPI_OBJECT_EXPECTED;
+    };
+    // 同一个对象只能被标记一次
+    state.with_object(id, |object| match object.tag {
+        Some(_) => NAPI_INVALID_ARG,
+        None => {
+            object.tag = Some(*type_tag);
+            sys::napi_ok
+        }
+    })
+}
+
+#[cfg_attr(feature = "export-symbols", no_mangle)]
+pub unsafe extern "C" fn napi_check_object_type_tag(
+    env: napi_env,
+    value: napi_value,
+    type_tag: *const napi_type_tag,
+    result: *mut bool,
+) -> napi_status {
+    let state = State::from_raw(env);
+    let Some(id) = state.object_id(value) else {
+        return NAPI_OBJECT_EXPECTED;
+    };
+    write(
+        result,
+        state.with_object(id, |object| object.tag == Some(*type_tag)),
+    );
+    sys::napi_ok
+}
+
+#[cfg_attr(feature = "export-symbols", no_mangle)]
+pub unsafe extern "C" fn napi_create_async_work(
+    env: napi_env,
+    _async_resource: napi_value,
+    _async_resource_name: napi_value,
+    execute: napi_async_execute_callback,
+    complete: napi_async_complete_callback,
+    data: *mut c_void,
+    result: *mut napi_async_work,
+) -> napi_status {
+    let state = State::from_raw(env);
+    let work = Work::new(env, execute, complete, data, state.wakeup.clone());
+    write(result, Box::into_raw(Box::new(work)).cast());
+    sys::napi_ok
+}
+
+#[cfg_attr(feature = "export-symbols", no_mangle)]
+pub unsafe extern "C" fn napi_delete_async_work(
+    _env: napi_env,
+    work: napi_async_work,
+) -> napi_status {
+    drop(Box::from_raw(work.cast::<Work>()));
+    sys::napi_ok
+}
+
+#[cfg_attr(feature = "export-symbols", no_mangle)]
+pub unsafe extern "C" fn napi_queue_async_work(
+    env: napi_env,
+    work: napi_async_work,
+) -> napi_status {
+    let state = State::from_raw(env);
+    state.works.set(state.works.get() + 1);
+    Work::queue(work.cast());
+    sys::napi_ok
+}
+
+#[cfg_attr(feature = "export-symbols", no_mangle)]
+pub unsafe extern "C" fn napi_cancel_async_work(
+    _env: napi_env,
+    work: napi_async_work,
+) -> napi_status {
+    if Work::cancel(work.cast()) {
+        sys::napi_ok
+    } else {
+        NAPI_GENERIC_FAILURE
+    }
+}
+
+#[cfg_attr(feature = "export-symbols", no_mangle)]
+pub unsafe extern "C" fn napi_create_promise(
+    env: napi_env,
+    deferred: *mut napi_deferred,
+    promise: *mut napi_value,
+) -> napi_status {
+    let state = State::from_raw(env);
+    let object = state.new_object(Kind::Promise(Promise::Pending));
+    let Value::Object(id) = object else {
+        unreachable!()
+    };
+    let index = {
+        let mut deferreds = state.deferreds.borrow_mut();
+        deferreds.push(Some(id));
+        deferreds.len()
+    };
+    write(deferred, index as napi_deferred);
+    write(promise, state.alloc(object));
+    sys::napi_ok
+}
+
+// 每个 deferred 只能兑现一次
+unsafe fn settle(
+    env: napi_env,
+    deferred: napi_deferred,
+    settled: impl FnOnce(Value) -> Promise,
+    value: napi_value,
+) -> napi_status {
+    let state = State::from_raw(env);
+    let Some(id) = state.deferreds.borrow_mut()[deferred as usize - 1].take() else {
+        return NAPI_INVALID_ARG;
+    };
+    let value = state.get(value);
+    state.with_object(id, |object| object.kind = Kind::Promise(settled(value)));
+    sys::napi_ok
+}
+
+#[cfg_attr(feature = "export-symbols", no_mangle)]
+pub unsafe extern "C" fn napi_resolve_deferred(
+    env: napi_env,
+    deferred: napi_deferred,
+    resolution: napi_value,
+) -> napi_status {
+    settle(env, deferred, Promise::Fulfilled, resolution)
+}
+
+#[cfg_attr(feature = "export-symbols", no_mangle)]
+pub unsafe extern "C" fn napi_reject_deferred(
+    env: napi_env,
+    deferred: napi_deferred,
+    rejection: napi_value,
+) -> napi_status {
+    settle(env, deferred, Promise::Rejected, rejection)
+}
+
+// 没有 GC，引用计数只用来区分强弱引用，对模拟环境没有影响
+#[cfg_attr(feature = "export-symbols", no_mangle)]
+pub unsafe extern "C" fn napi_create_reference(
+    env: napi_env,
+    value: napi_value,
+    _initial_refcount: u32,
+    result: *mut napi_ref,
+) -> napi_status {
+    let state = State::from_raw(env);
+    let value = state.get(value);
+    let index = {
+        let mut refs = state.refs.borrow_mut();
+        refs.push(Some(value));
+        refs.len()
+    };
+    write(result, index as napi_ref);
+    sys::napi_ok
+}
+
+#[cfg_attr(feature = "export-symbols", no_mangle)]
+pub unsafe extern "C" fn napi_delete_reference(env: napi_env, reference: napi_ref) -> napi_status {
+    let state = State::from_raw(env);
+    match state.refs.borrow_mut()[reference as usize - 1].take() {
+        Some(_) => sys::napi_ok,
+        None => NAPI_INVALID_ARG,
+    }
+}
+
+#[cfg_attr(feature = "export-symbols", no_mangle)]
+pub unsafe extern "C" fn napi_get_reference_value(
+    env: napi_env,
+    reference: napi_ref,
+    result: *mut napi_value,
+) -> napi_status {
+    let state = State::from_raw(env);
+    let value = state.refs.borrow()[reference as usize - 1].clone();
+    write(
+        result,
+        value.map_or(ptr::null_mut(), |value| state.alloc(value)),
+    );
+    sys::napi_ok
+}
+
+#[cfg_attr(feature = "export-symbols", no_mangle)]
+pub unsafe extern "C" fn napi_add_finalizer(
+    env: napi_env,
+    js_object: napi_value,
+    finalize_data: *mut c_void,
+    finalize_cb: napi_finalize,
+    finalize_hint: *mut c_void,
+    result: *mut napi_ref,
+) -> napi_status {
+    let state = State::from_raw(env);
+    let object = state.get(js_object);
+    if !matches!(object, Value::Object(_)) {
+        return NAPI_OBJECT_EXPECTED;
+    }
+    add_finalizer(state, &object, finalize_cb, finalize_data, finalize_hint);
+    if !result.is_null() {
+        napi_create_reference(env, js_object, 0, result);
+    }
+    sys::napi_ok
+}
+
+#[cfg_attr(feature = "export-symbols", no_mangle)]
+pub unsafe extern "C" fn napi_call_function(
+    env: napi_env,
+    recv: napi_value,
+    func: napi_value,
+    argc: usize,
+    argv: *const napi_value,
+    result: *mut napi_value,
+) -> napi_status {
+    let args = if argc == 0 {
+        Vec::new()
+    } else {
+        slice::from_raw_parts(argv, argc).to_vec()
+    };
+    match call(env, recv, func, args, ptr::null_mut()) {
+        Ok(value) => {
+            write(result, value);
+            sys::napi_ok
+        }
+        Err(status) => status,
+    }
+}
+
+// 以 recv 为 this 调用 func。new_target 不为 NULL 时相当于 new 调用，Closure 看不到 new.target
+pub(crate) unsafe fn call(
+    env: napi_env,
+    recv: napi_value,
+    func: napi_value,
+    args: Vec<napi_value>,
+    new_target: napi_value,
+) -> Result<napi_value, napi_status> {
+    let state = State::from_raw(env);
+    if state.exception.borrow().is_some() {
+        return Err(NAPI_PENDING_EXCEPTION);
+    }
+    // 先取出要调用的函数，调用期间不能持有对象表的借用
+    let callee = with_kind(state, func, |kind| match kind {
+        Kind::Function { cb, data } => Some((Some((*cb, *data)), None)),
+        Kind::Closure(closure) => Some((None, Some(closure.clone()))),
+        _ => None,
+    });
+    let value = match callee {
+        Some((Some((cb, data)), _)) => {
+            let mut info = CallInfo {
+                this: recv,
+                args,
+                data,
+                new_target,
+            };
+            match cb {
+                Some(cb) => cb(env, (&mut info as *mut CallInfo).cast()),
+                None => ptr::null_mut(),
+            }
+        }
+        Some((None, Some(closure))) => closure(&Env::borrow(env), &args),
+        _ => return Err(NAPI_FUNCTION_EXPECTED),
+    };
+    if state.exception.borrow().is_some() {
+        return Err(NAPI_PENDING_EXCEPTION);
+    }
+    if value.is_null() {
+        Ok(state.alloc(Value::Undefined))
+    } else {
+        Ok(value)
+    }
+}
+
+#[cfg_attr(feature = "export-symbols", no_mangle)]
+pub unsafe extern "C" fn napi_get_and_clear_last_exception(
+    env: napi_env,
+    result: *mut napi_value,
+) -> napi_status {
+    let state = State::from_raw(env);
+    let exception = state.exception.take().unwrap_or(Value::Undefined);
+    write(result, state.alloc(exception));
+    sys::napi_ok
+}
+
+#[allow(clippy::too_many_arguments)]
+#[cfg_attr(feature = "export-symbols", no_mangle)]
+pub unsafe extern "C" fn napi_create_threadsafe_function(
+    env: napi_env,
+    func: napi_value,
+    _async_resource: napi_value,
+    _async_resource_name: napi_value,
+    _max_queue_size: usize,
+    initial_thread_count: usize,
+    thread_finalize_data: *mut c_void,
+    thread_finalize_cb: napi_finalize,
+    context: *mut c_void,
+    call_js_cb: napi_threadsafe_function_call_js,
+    result: *mut napi_threadsafe_function,
+) -> napi_status {
+    let state = State::from_raw(env);
+    if func.is_null() && call_js_cb.is_none() {
+        return NAPI_INVALID_ARG;
+    }
+    // 持有 func 的引用，finalize 时删除
+    let func = (!func.is_null()).then(|| {
+        let mut reference = ptr::null_mut();
+        napi_create_reference(env, func, 1, &mut reference);
+        reference as usize - 1
+    });
+    let tsfn = Arc::new(Tsfn {
+        state: Mutex::new(TsfnState {
+            threads: initial_thread_count,
+            queue: Default::default(),
+            closing: false,
+            referenced: true,
+            finalized: false,
+        }),
+        wakeup: state.wakeup.clone(),
+        call_js: call_js_cb,
+        context: SendPtr(context),
+        finalize: thread_finalize_cb,
+        finalize_data: SendPtr(thread_finalize_data),
+        func,
+    });
+    write(result, Arc::as_ptr(&tsfn).cast_mut().cast());
+    state.tsfns.borrow_mut().push(tsfn);
+    sys::napi_ok
+}
+
+// 环境中的 tsfns 一直持有 Arc，所以指针在环境销毁之前都有效
+unsafe fn tsfn<'a>(func: napi_threadsafe_function) -> &'a Tsfn {
+    &*func.cast_const().cast::<Tsfn>()
+}
+
+// 队列不设上限，阻塞和非阻塞的调用行为一致
+#[cfg_attr(feature = "export-symbols", no_mangle)]
+pub unsafe extern "C" fn napi_call_threadsafe_function(
+    func: napi_threadsafe_function,
+    data: *mut c_void,
+    _is_blocking: napi_threadsafe_function_call_mode,
+) -> napi_status {
+    tsfn(func).call(data)
+}
+
+#[cfg_attr(feature = "export-symbols", no_mangle)]
+pub unsafe extern "C" fn napi_acquire_threadsafe_function(
+    func: napi_threadsafe_function,
+) -> napi_status {
+    tsfn(func).acquire()
+}
+
+#[cfg_attr(feature = "export-symbols", no_mangle)]
+pub unsafe extern "C" fn napi_release_threadsafe_function(
+    func: napi_threadsafe_function,
+    mode: napi_threadsafe_function_release_mode,
+) -> napi_status {
+    tsfn(func).release(mode == sys::napi_tsfn_abort)
+}
+
+#[cfg_attr(feature = "export-symbols", no_mangle)]
+pub unsafe extern "C" fn napi_ref_threadsafe_function(
+    _env: napi_env,
+    func: napi_threadsafe_function,
+) -> napi_status {
+    tsfn(func).state.lock().unwrap().referenced = true;
+    sys::napi_ok
+}
+
+#[cfg_attr(feature = "export-symbols", no_mangle)]
+pub unsafe extern "C" fn napi_unref_threadsafe_function(
+    _env: napi_env,
+    func: napi_threadsafe_function,
+) -> napi_status {
+    tsfn(func).state.lock().unwrap().referenced = false;
+    sys::napi_ok
+}
+
+#[cfg_attr(feature = "export-symbols", no_mangle)]
+pub unsafe extern "C" fn napi_get_global(env: napi_env, result: *mut napi_value) -> napi_status {
+    let state = State::from_raw(env);
+    write(result, state.alloc(Value::Object(state.global.get())));
+    sys::napi_ok
+}
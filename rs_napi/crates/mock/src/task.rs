@@ -0,0 +1,249 @@
+use crate::napi::{NAPI_CLOSING, NAPI_INVALID_ARG};
+use std::collections::VecDeque;
+use std::os::raw::c_void;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::Duration;
+use sys::{
+    napi_async_complete_callback, napi_async_execute_callback, napi_env, napi_finalize,
+    napi_status, napi_threadsafe_function_call_js,
+};
+
+// 其他线程唤醒事件循环用的通知。generation 每次通知加一，事件循环据此判断等待期间是否有新的事件
+#[derive(Default)]
+pub(crate) struct Wakeup {
+    state: Mutex<WakeupState>,
+    cond: Condvar,
+}
+
+#[derive(Default)]
+struct WakeupState {
+    generation: u64,
+    // 执行完成、等待回到主线程调用 complete 的 async work
+    completed: VecDeque<(SendPtr, napi_status)>,
+    // 为 true 时 queue 的 async work 停在队列中，直到事件循环开始运行
+    held: bool,
+}
+
+impl Wakeup {
+    pub(crate) fn generation(&self) -> u64 {
+        self.state.lock().unwrap().generation
+    }
+
+    pub(crate) fn notify(&self) {
+        self.state.lock().unwrap().generation += 1;
+        self.cond.notify_all();
+    }
+
+    fn complete(&self, work: *mut Work, status: napi_status) {
+        let mut state = self.state.lock().unwrap();
+        state.completed.push_back((SendPtr(work.cast()), status));
+        state.generation += 1;
+        self.cond.notify_all();
+    }
+
+    pub(crate) fn hold(&self) {
+        self.state.lock().unwrap().held = true;
+    }
+
+    pub(crate) fn release(&self) {
+        self.state.lock().unwrap().held = false;
+        self.cond.notify_all();
+    }
+
+    fn wait_released(&self) {
+        let state = self.state.lock().unwrap();
+        drop(self.cond.wait_while(state, |state| state.held).unwrap());
+    }
+
+    pub(crate) fn take_completed(&self) -> Option<(*mut Work, napi_status)> {
+        let mut state = self.state.lock().unwrap();
+        state
+            .completed
+            .pop_front()
+            .map(|(work, status)| (work.0.cast(), status))
+    }
+
+    // 等到 generation 不再等于 seen，超时说明测试在等一个永远不会发生的事件
+    pub(crate) fn wait(&self, seen: u64) {
+        let state = self.state.lock().unwrap();
+        let (_state, timeout) = self
+            .cond
+            .wait_timeout_while(state, Duration::from_secs(10), |state| {
+                state.generation == seen
+            })
+            .unwrap();
+        assert!(
+            !timeout.timed_out(),
+            "mock event loop made no progress for 10s"
+        );
+    }
+}
+
+// 在线程之间传递的裸指针，由使用者保证访问是安全的
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct SendPtr(pub(crate) *mut c_void);
+
+unsafe impl Send for SendPtr {}
+
+const QUEUED: u8 = 0;
+const RUNNING: u8 = 1;
+const CANCELLED: u8 = 2;
+
+// napi_async_work 指向的结构。queue 之后在新线程上执行 execute，
+// 完成或者被取消后由事件循环在主线程上调用 complete
+pub(crate) struct Work {
+    pub(crate) env: napi_env,
+    pub(crate) execute: napi_async_execute_callback,
+    pub(crate) complete: napi_async_complete_callback,
+    pub(crate) data: *mut c_void,
+    pub(crate) wakeup: Arc<Wakeup>,
+    state: Arc<AtomicU8>,
+}
+
+impl Work {
+    pub(crate) fn new(
+        env: napi_env,
+        execute: napi_async_execute_callback,
+        complete: napi_async_complete_callback,
+        data: *mut c_void,
+        wakeup: Arc<Wakeup>,
+    ) -> Self {
+        Work {
+            env,
+            execute,
+            complete,
+            data,
+            wakeup,
+            state: Arc::new(AtomicU8::new(QUEUED)),
+        }
+    }
+
+    pub(crate) fn queue(work: *mut Work) {
+        let (env, execute, data, wakeup, state) = unsafe {
+            let work = &*work;
+            (
+                SendPtr(work.env.cast()),
+                work.execute,
+                SendPtr(work.data),
+                work.wakeup.clone(),
+                work.state.clone(),
+            )
+        };
+        let work = SendPtr(work.cast());
+        thread::spawn(move || {
+            let (env, data, work) = (env, data, work);
+            wakeup.wait_released();
+            if state
+                .compare_exchange(QUEUED, RUNNING, Ordering::AcqRel, Ordering::Acquire)
+                .is_err()
+            {
+                return;
+            }
+            if let Some(execute) = execute {
+                unsafe { execute(env.0.cast(), data.0) };
+            }
+            wakeup.complete(work.0.cast(), sys::napi_ok);
+        });
+    }
+
+    // 只有还没开始执行的任务可以取消
+    pub(crate) fn cancel(work: *mut Work) -> bool {
+        let work_ref = unsafe { &*work };
+        let cancelled = work_ref
+            .state
+            .compare_exchange(QUEUED, CANCELLED, Ordering::AcqRel, Ordering::Acquire)
+            .is_ok();
+        if cancelled {
+            work_ref.wakeup.complete(work, sys::napi_cancelled);
+        }
+        cancelled
+    }
+}
+
+// napi_threadsafe_function 指向的结构，可以在任意线程上 call/acquire/release
+pub(crate) struct Tsfn {
+    pub(crate) state: Mutex<TsfnState>,
+    pub(crate) wakeup: Arc<Wakeup>,
+    pub(crate) call_js: napi_threadsafe_function_call_js,
+    pub(crate) context: SendPtr,
+    pub(crate) finalize: napi_finalize,
+    pub(crate) finalize_data: SendPtr,
+    // 创建时传入的 JS 函数在引用表中的位置
+    pub(crate) func: Option<usize>,
+}
+
+unsafe impl Sync for Tsfn {}
+unsafe impl Send for Tsfn {}
+
+pub(crate) struct TsfnState {
+    pub(crate) threads: usize,
+    pub(crate) queue: VecDeque<SendPtr>,
+    // release(abort) 之后或者环境销毁时不再接受新的调用
+    pub(crate) closing: bool,
+    pub(crate) referenced: bool,
+    pub(crate) finalized: bool,
+}
+
+impl Tsfn {
+    pub(crate) fn call(&self, data: *mut c_void) -> napi_status {
+        let mut state = self.state.lock().unwrap();
+        if state.closing || state.finalized {
+            return NAPI_CLOSING;
+        }
+        state.queue.push_back(SendPtr(data));
+        drop(state);
+        self.wakeup.notify();
+        sys::napi_ok
+    }
+
+    pub(crate) fn acquire(&self) -> napi_status {
+        let mut state = self.state.lock().unwrap();
+        if state.closing || state.finalized {
+            return NAPI_CLOSING;
+        }
+        state.threads += 1;
+        sys::napi_ok
+    }
+
+    pub(crate) fn release(&self, abort: bool) -> napi_status {
+        let mut state = self.state.lock().unwrap();
+        if state.threads == 0 {
+            return NAPI_INVALID_ARG;
+        }
+        state.threads -= 1;
+        if abort {
+            state.closing = true;
+        }
+        drop(state);
+        self.wakeup.notify();
+        sys::napi_ok
+    }
+
+    pub(crate) fn pop(&self) -> Option<*mut c_void> {
+        self.state
+            .lock()
+            .unwrap()
+            .queue
+            .pop_front()
+            .map(|data| data.0)
+    }
+
+    // 所有线程都 release 并且队列已经清空，可以调用 finalize 了
+    pub(crate) fn take_finished(&self) -> bool {
+        let mut state = self.state.lock().unwrap();
+        let finished =
+            !state.finalized && state.queue.is_empty() && (state.threads == 0 || state.closing);
+        if finished {
+            state.finalized = true;
+        }
+        finished
+    }
+
+    // 还没有 finalize 并且被引用，会让事件循环继续运行
+    pub(crate) fn keeps_alive(&self) -> bool {
+        let state = self.state.lock().unwrap();
+        !state.finalized && state.referenced
+    }
+}
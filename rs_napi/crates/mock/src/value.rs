@@ -0,0 +1,182 @@
+use std::os::raw::c_void;
+use std::rc::Rc;
+use sys::{napi_callback, napi_finalize, napi_type_tag, napi_value};
+
+use crate::env::Env;
+
+// 堆上的一个 JS 值。对象、函数等引用类型只保存对象表中的下标，复制 Value 不会复制对象
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Value {
+    Undefined,
+    Null,
+    Boolean(bool),
+    Number(f64),
+    String(String),
+    Symbol(usize),
+    BigInt(bool, Vec<u64>),
+    Object(usize),
+}
+
+// 属性名：字符串或者 Symbol
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Key {
+    String(String),
+    Symbol(usize),
+}
+
+// 测试中用 Rust 闭包实现的 JS 函数
+pub(crate) type Closure = Rc<dyn Fn(&Env, &[napi_value]) -> napi_value>;
+
+pub(crate) enum Kind {
+    Plain,
+    Array(Vec<Value>),
+    // N-API 创建的原生函数
+    Function {
+        cb: napi_callback,
+        data: *mut c_void,
+    },
+    Closure(Closure),
+    Date(f64),
+    External(*mut c_void),
+    Buffer(*mut c_void, usize),
+    Promise(Promise),
+    // 没有原型链，err.name 在没有自己的 name 属性时返回这里的名字
+    Error(&'static str),
+}
+
+#[derive(Clone, Debug)]
+pub(crate) enum Promise {
+    Pending,
+    Fulfilled(Value),
+    Rejected(Value),
+}
+
+pub(crate) struct Property {
+    pub(crate) key: Key,
+    pub(crate) value: Value,
+    pub(crate) enumerable: bool,
+    // 不可写的属性只能通过 napi_define_properties 创建，之后的赋值被忽略
+    pub(crate) writable: bool,
+}
+
+pub(crate) struct Finalizer {
+    pub(crate) cb: napi_finalize,
+    pub(crate) data: *mut c_void,
+    pub(crate) hint: *mut c_void,
+}
+
+pub(crate) struct Object {
+    pub(crate) kind: Kind,
+    // 按插入顺序保存，和 JS 中字符串 key 的枚举顺序一致
+    pub(crate) props: Vec<Property>,
+    pub(crate) tag: Option<napi_type_tag>,
+    // 没有 GC，所有的 finalizer 都在环境销毁时调用
+    pub(crate) finalizers: Vec<Finalizer>,
+}
+
+impl Object {
+    pub(crate) fn new(kind: Kind) -> Self {
+        Object {
+            kind,
+            props: Vec::new(),
+            tag: None,
+            finalizers: Vec::new(),
+        }
+    }
+
+    pub(crate) fn get(&self, key: &Key) -> Option<Value> {
+        if let (Kind::Array(elements), Key::String(name)) = (&self.kind, key) {
+            if name == "length" {
+                return Some(Value::Number(elements.len() as f64));
+            }
+            if let Ok(index) = name.parse::<usize>() {
+                return elements.get(index).cloned();
+            }
+        }
+        let own = self
+            .props
+            .iter()
+            .find(|prop| &prop.key == key)
+            .map(|prop| prop.value.clone());
+        match (&self.kind, key) {
+            (Kind::Error(name), Key::String(key)) if own.is_none() && key == "name" => {
+                Some(Value::String(name.to_string()))
+            }
+            _ => own,
+        }
+    }
+
+    // 和非严格模式下的赋值一样，给不可写的属性赋值不会生效，也不会报错
+    pub(crate) fn set(&mut self, key: Key, value: Value, enumerable: bool) {
+        self.define(key, value, enumerable, true, false);
+    }
+
+    // redefine 为 true 时相当于 Object.defineProperty，可以覆盖不可写的属性
+    pub(crate) fn define(
+        &mut self,
+        key: Key,
+        value: Value,
+        enumerable: bool,
+        writable: bool,
+        redefine: bool,
+    ) {
+        if let (Kind::Array(elements), Key::String(name)) = (&mut self.kind, &key) {
+            if let Ok(index) = name.parse::<usize>() {
+                if index >= elements.len() {
+                    elements.resize(index + 1, Value::Undefined);
+                }
+                elements[index] = value;
+                return;
+            }
+        }
+        match self.props.iter_mut().find(|prop| prop.key == key) {
+            Some(prop) if redefine => {
+                prop.value = value;
+                prop.enumerable = enumerable;
+                prop.writable = writable;
+            }
+            Some(prop) if prop.writable => prop.value = value,
+            Some(_) => {}
+            None => self.props.push(Property {
+                key,
+                value,
+                enumerable,
+                writable,
+            }),
+        }
+    }
+
+    // 对象自身的 key，数组的下标排在前面，然后是按插入顺序排列的其他属性
+    pub(crate) fn own_keys(&self, enumerable_only: bool) -> Vec<Key> {
+        let indices = match &self.kind {
+            Kind::Array(elements) => (0..elements.len())
+                .map(|i| Key::String(i.to_string()))
+                .collect(),
+            _ => Vec::new(),
+        };
+        indices
+            .into_iter()
+            .chain(
+                self.props
+                    .iter()
+                    .filter(|prop| prop.enumerable || !enumerable_only)
+                    .map(|prop| prop.key.clone()),
+            )
+            .collect()
+    }
+
+    // 可枚举的字符串 key，数组的下标排在前面
+    pub(crate) fn keys(&self) -> Vec<String> {
+        let indices = match &self.kind {
+            Kind::Array(elements) => (0..elements.len()).map(|i| i.to_string()).collect(),
+            _ => Vec::new(),
+        };
+        indices
+            .into_iter()
+            .chain(self.props.iter().filter_map(|prop| match &prop.key {
+                Key::String(name) if prop.enumerable => Some(name.clone()),
+                _ => None,
+            }))
+            .collect()
+    }
+}
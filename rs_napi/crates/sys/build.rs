@@ -0,0 +1,13 @@
+use std::env;
+
+fn main() {
+    // 动态加载模式下 napi_* 符号在运行时解析，不需要在链接阶段绑定
+    if env::var_os("CARGO_FEATURE_DYNAMIC_LOADING").is_some() {
+        return;
+    }
+    // 告诉 cargo 在编译阶段需要链接到动态链接库 libace_napi.z.so。这个库只存在于 OpenHarmony 的 SDK 中，
+    // 其他宿主（Node.js、mock 运行时）在加载模块时提供 napi_* 符号，链接它只会让构建失败
+    if env::var("CARGO_CFG_TARGET_ENV").as_deref() == Ok("ohos") {
+        println!("cargo:rustc-link-lib=dylib=ace_napi.z");
+    }
+}
@@ -0,0 +1,109 @@
+use std::error::Error;
+use std::ffi::OsStr;
+use std::fmt;
+use std::os::raw::c_void;
+use std::sync::OnceLock;
+
+use libloading::Library;
+
+use crate::Functions;
+
+// 运行时解析得到的函数表，只会被初始化一次
+static FUNCTIONS: OnceLock<Functions> = OnceLock::new();
+
+#[derive(Debug)]
+pub enum LoadError {
+    // 打开动态库失败
+    Library(libloading::Error),
+    // 宿主没有提供的 napi_* 符号
+    MissingSymbols(Vec<&'static str>),
+    // 函数表已经初始化过，不能再替换
+    AlreadyLoaded,
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadError::Library(err) => write!(f, "failed to open N-API library: {}", err),
+            LoadError::MissingSymbols(missing) => {
+                write!(f, "missing N-API symbols: {}", missing.join(", "))
+            }
+            LoadError::AlreadyLoaded => write!(f, "N-API symbols have already been loaded"),
+        }
+    }
+}
+
+impl Error for LoadError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            LoadError::Library(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+/// 通过 resolve 查找每一个 napi_* 符号并填充函数表。
+///
+/// # Safety
+///
+/// resolve 返回的指针必须指向签名与 N-API 声明一致的函数。
+pub unsafe fn load_with(
+    mut resolve: impl FnMut(&str) -> Option<*const c_void>,
+) -> Result<(), LoadError> {
+    let functions = Functions::resolve(&mut resolve)?;
+    FUNCTIONS
+        .set(functions)
+        .map_err(|_| LoadError::AlreadyLoaded)
+}
+
+/// 从指定路径的动态库中加载 N-API 符号。
+///
+/// # Safety
+///
+/// 打开动态库会执行其中的初始化代码，并且库中的符号必须与 N-API 声明一致。
+pub unsafe fn load_from(path: impl AsRef<OsStr>) -> Result<(), LoadError> {
+    let library = Library::new(path).map_err(LoadError::Library)?;
+    load_library(library)
+}
+
+/// 从当前进程（例如 node 可执行文件）中加载 N-API 符号。
+pub fn load_from_host() -> Result<(), LoadError> {
+    unsafe { load_library(host_library()?) }
+}
+
+unsafe fn load_library(library: Library) -> Result<(), LoadError> {
+    load_with(|name| {
+        library
+            .get::<*const c_void>(name.as_bytes())
+            .ok()
+            .map(|symbol| *symbol)
+    })?;
+    // 函数表中的指针在整个进程生命周期内有效，所以动态库永远不能被卸载
+    std::mem::forget(library);
+    Ok(())
+}
+
+#[cfg(unix)]
+fn host_library() -> Result<Library, LoadError> {
+    Ok(libloading::os::unix::Library::this().into())
+}
+
+#[cfg(windows)]
+fn host_library() -> Result<Library, LoadError> {
+    libloading::os::windows::Library::this()
+        .map(Into::into)
+        .map_err(LoadError::Library)
+}
+
+// 没有显式加载时，默认从当前进程中查找符号，找不到时直接 panic 并列出缺失的符号
+pub(crate) fn functions() -> &'static Functions {
+    if let Some(functions) = FUNCTIONS.get() {
+        return functions;
+    }
+    if let Err(err) = load_from_host() {
+        if !matches!(err, LoadError::AlreadyLoaded) {
+            panic!("{}", err);
+        }
+    }
+    FUNCTIONS.get().unwrap()
+}
@@ -1,4 +1,5 @@
 #![allow(non_camel_case_types)]
+#![allow(non_upper_case_globals)]
 
 use std::os::raw::{c_char, c_int, c_uint, c_void};
 
@@ -20,6 +21,47 @@ pub type napi_env = *mut napi_env__;
 
 pub type napi_status = i32;
 
+pub const napi_ok: napi_status = 0;
+// async work 在开始执行之前被 napi_cancel_async_work 取消
+pub const napi_cancelled: napi_status = 11;
+// 宿主不允许 Buffer 直接使用外部内存，例如开启了 V8 内存沙箱的 Electron
+pub const napi_no_external_buffers_allowed: napi_status = 22;
+
+// 传给 napi_create_string_utf8 等函数，表示字符串以 null 结尾、由 N-API 自行计算长度
+pub const NAPI_AUTO_LENGTH: usize = usize::MAX;
+
+// napi_typeof 返回的 JS 值类型
+pub type napi_valuetype = i32;
+
+pub const napi_undefined: napi_valuetype = 0;
+pub const napi_null: napi_valuetype = 1;
+pub const napi_boolean: napi_valuetype = 2;
+pub const napi_number: napi_valuetype = 3;
+pub const napi_string: napi_valuetype = 4;
+pub const napi_symbol: napi_valuetype = 5;
+pub const napi_object: napi_valuetype = 6;
+pub const napi_function: napi_valuetype = 7;
+pub const napi_external: napi_valuetype = 8;
+pub const napi_bigint: napi_valuetype = 9;
+
+// napi_get_all_property_names 的参数，与 node_api_types.h 中的定义一一对应
+pub type napi_key_collection_mode = i32;
+pub const napi_key_include_prototypes: napi_key_collection_mode = 0;
+pub const napi_key_own_only: napi_key_collection_mode = 1;
+
+// 可以按位组合的过滤条件
+pub type napi_key_filter = i32;
+pub const napi_key_all_properties: napi_key_filter = 0;
+pub const napi_key_writable: napi_key_filter = 1;
+pub const napi_key_enumerable: napi_key_filter = 1 << 1;
+pub const napi_key_configurable: napi_key_filter = 1 << 2;
+pub const napi_key_skip_strings: napi_key_filter = 1 << 3;
+pub const napi_key_skip_symbols: napi_key_filter = 1 << 4;
+
+pub type napi_key_conversion = i32;
+pub const napi_key_keep_numbers: napi_key_conversion = 0;
+pub const napi_key_numbers_to_strings: napi_key_conversion = 1;
+
 #[repr(C)]
 #[derive(Copy, Clone)]
 pub struct napi_callback_info__ {
@@ -30,7 +72,93 @@ pub type napi_callback_info = *mut napi_callback_info__;
 pub type napi_callback =
     Option<unsafe extern "C" fn(env: napi_env, info: napi_callback_info) -> napi_value>;
 
-pub type napi_property_attributes = i32;
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct napi_ref__ {
+    _unused: [u8; 0],
+}
+pub type napi_ref = *mut napi_ref__;
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct napi_deferred__ {
+    _unused: [u8; 0],
+}
+pub type napi_deferred = *mut napi_deferred__;
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct napi_async_work__ {
+    _unused: [u8; 0],
+}
+pub type napi_async_work = *mut napi_async_work__;
+
+// 在 libuv 线程池中执行，不能调用任何 N-API 函数
+pub type napi_async_execute_callback =
+    Option<unsafe extern "C" fn(env: napi_env, data: *mut c_void)>;
+// 回到 JS 主线程后调用，status 为 napi_cancelled 时表示 execute 没有执行
+pub type napi_async_complete_callback =
+    Option<unsafe extern "C" fn(env: napi_env, status: napi_status, data: *mut c_void)>;
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct napi_threadsafe_function__ {
+    _unused: [u8; 0],
+}
+pub type napi_threadsafe_function = *mut napi_threadsafe_function__;
+
+// 在 JS 主线程上处理其他线程通过 napi_call_threadsafe_function 发来的 data，
+// 环境正在销毁时 env 和 js_callback 都是 NULL，只需要释放 data
+pub type napi_threadsafe_function_call_js = Option<
+    unsafe extern "C" fn(
+        env: napi_env,
+        js_callback: napi_value,
+        context: *mut c_void,
+        data: *mut c_void,
+    ),
+>;
+
+pub type napi_threadsafe_function_release_mode = i32;
+pub const napi_tsfn_release: napi_threadsafe_function_release_mode = 0;
+pub const napi_tsfn_abort: napi_threadsafe_function_release_mode = 1;
+
+pub type napi_threadsafe_function_call_mode = i32;
+pub const napi_tsfn_nonblocking: napi_threadsafe_function_call_mode = 0;
+pub const napi_tsfn_blocking: napi_threadsafe_function_call_mode = 1;
+
+// 用来标记对象来源的 128 位标签，见 napi_type_tag_object
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct napi_type_tag {
+    pub lower: u64,
+    pub upper: u64,
+}
+
+// 被 GC 回收的 external 值、external buffer 等在释放时调用的回调
+pub type napi_finalize = Option<
+    unsafe extern "C" fn(env: napi_env, finalize_data: *mut c_void, finalize_hint: *mut c_void),
+>;
+
+bitflags::bitflags! {
+    // 属性描述符的标志位，与 node_api_types.h 中的 napi_property_attributes 一一对应
+    #[repr(transparent)]
+    #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+    pub struct napi_property_attributes: i32 {
+        // 只读、不可枚举、不可配置
+        const napi_default = 0;
+        const napi_writable = 1;
+        const napi_enumerable = 1 << 1;
+        const napi_configurable = 1 << 2;
+        // 用于 napi_define_class，表示定义在类本身而不是原型上的静态属性
+        const napi_static = 1 << 10;
+        // 与类的方法默认行为一致：可写、不可枚举、可配置
+        const napi_default_method = Self::napi_writable.bits() | Self::napi_configurable.bits();
+        // 与 JS 中 obj.key = value 的默认行为一致：可写、可枚举、可配置
+        const napi_default_jsproperty = Self::napi_writable.bits()
+            | Self::napi_enumerable.bits()
+            | Self::napi_configurable.bits();
+    }
+}
 
 pub type napi_addon_register_func =
     Option<unsafe extern "C" fn(env: napi_env, exports: napi_value) -> napi_value>;
@@ -60,7 +188,69 @@ pub struct napi_module {
     pub reserved: [*mut c_void; 4usize],
 }
 
-extern "C" {
+// 是否通过 dlopen/dlsym 在运行时解析 napi_* 符号，而不是在链接阶段绑定
+pub const DYNAMIC_LOADING: bool = cfg!(feature = "dynamic-loading");
+
+#[cfg(feature = "dynamic-loading")]
+mod dynamic;
+
+#[cfg(feature = "dynamic-loading")]
+pub use dynamic::{load_from, load_from_host, load_with, LoadError};
+
+// 默认情况下，所有 N-API 函数都以 extern "C" 的方式声明，由链接器绑定到宿主提供的符号
+#[cfg(not(feature = "dynamic-loading"))]
+macro_rules! napi_functions {
+    ($(pub fn $name:ident($($arg:ident: $ty:ty),* $(,)?) $(-> $ret:ty)?;)*) => {
+        extern "C" {
+            $(pub fn $name($($arg: $ty),*) $(-> $ret)?;)*
+        }
+    };
+}
+
+// 开启 dynamic-loading 后，同名函数变成对函数表的转发，函数表在运行时通过 dlsym 填充
+#[cfg(feature = "dynamic-loading")]
+macro_rules! napi_functions {
+    ($(pub fn $name:ident($($arg:ident: $ty:ty),* $(,)?) $(-> $ret:ty)?;)*) => {
+        pub(crate) struct Functions {
+            $($name: unsafe extern "C" fn($($ty),*) $(-> $ret)?,)*
+        }
+
+        impl Functions {
+            // 逐个解析符号，所有找不到的符号会被收集起来一起报告，而不是遇到第一个就失败
+            pub(crate) unsafe fn resolve(
+                resolve: &mut dyn FnMut(&str) -> Option<*const c_void>,
+            ) -> Result<Functions, LoadError> {
+                let mut missing = Vec::new();
+                $(
+                    let $name = resolve(stringify!($name)).filter(|ptr| !ptr.is_null());
+                    if $name.is_none() {
+                        missing.push(stringify!($name));
+                    }
+                )*
+                if !missing.is_empty() {
+                    return Err(LoadError::MissingSymbols(missing));
+                }
+                Ok(Functions {
+                    $($name: std::mem::transmute::<*const c_void, unsafe extern "C" fn($($ty),*) $(-> $ret)?>(
+                        $name.unwrap(),
+                    ),)*
+                })
+            }
+        }
+
+        $(
+            /// # Safety
+            ///
+            /// 与同名的 N-API C 函数要求一致。
+            #[allow(clippy::too_many_arguments)]
+            pub unsafe fn $name($($arg: $ty),*) $(-> $ret)? {
+                (dynamic::functions().$name)($($arg),*)
+            }
+        )*
+    };
+}
+
+napi_functions! {
     pub fn napi_get_cb_info(
         env: napi_env,
         cbinfo: napi_callback_info,
@@ -69,8 +259,7 @@ extern "C" {
         this_arg: *mut napi_value,
         data: *mut *mut c_void,
     ) -> napi_status;
-    pub fn napi_get_value_double(env: napi_env, value: napi_value, result: *mut f64)
-        -> napi_status;
+    pub fn napi_get_value_double(env: napi_env, value: napi_value, result: *mut f64) -> napi_status;
     pub fn napi_create_double(env: napi_env, value: f64, result: *mut napi_value) -> napi_status;
     pub fn napi_module_register(mod_: *mut napi_module);
     pub fn napi_define_properties(
@@ -93,4 +282,256 @@ extern "C" {
         data: *mut c_void,
         result: *mut napi_value,
     ) -> napi_status;
+    pub fn napi_create_object(env: napi_env, result: *mut napi_value) -> napi_status;
+    pub fn napi_create_array_with_length(
+        env: napi_env,
+        length: usize,
+        result: *mut napi_value,
+    ) -> napi_status;
+    pub fn napi_set_element(
+        env: napi_env,
+        object: napi_value,
+        index: u32,
+        value: napi_value,
+    ) -> napi_status;
+    pub fn napi_create_string_utf8(
+        env: napi_env,
+        str_: *const c_char,
+        length: usize,
+        result: *mut napi_value,
+    ) -> napi_status;
+    pub fn napi_get_value_string_utf8(
+        env: napi_env,
+        value: napi_value,
+        buf: *mut c_char,
+        bufsize: usize,
+        result: *mut usize,
+    ) -> napi_status;
+    pub fn napi_get_array_length(env: napi_env, value: napi_value, result: *mut u32)
+        -> napi_status;
+    pub fn napi_get_element(
+        env: napi_env,
+        object: napi_value,
+        index: u32,
+        result: *mut napi_value,
+    ) -> napi_status;
+    pub fn napi_get_new_target(
+        env: napi_env,
+        cbinfo: napi_callback_info,
+        result: *mut napi_value,
+    ) -> napi_status;
+    pub fn napi_typeof(env: napi_env, value: napi_value, result: *mut napi_valuetype)
+        -> napi_status;
+    pub fn napi_get_undefined(env: napi_env, result: *mut napi_value) -> napi_status;
+    pub fn napi_get_null(env: napi_env, result: *mut napi_value) -> napi_status;
+    pub fn napi_get_boolean(env: napi_env, value: bool, result: *mut napi_value) -> napi_status;
+    pub fn napi_get_value_bool(env: napi_env, value: napi_value, result: *mut bool)
+        -> napi_status;
+    pub fn napi_throw_error(env: napi_env, code: *const c_char, msg: *const c_char)
+        -> napi_status;
+    pub fn napi_get_property_names(
+        env: napi_env,
+        object: napi_value,
+        result: *mut napi_value,
+    ) -> napi_status;
+    pub fn napi_get_all_property_names(
+        env: napi_env,
+        object: napi_value,
+        key_mode: napi_key_collection_mode,
+        key_filter: napi_key_filter,
+        key_conversion: napi_key_conversion,
+        result: *mut napi_value,
+    ) -> napi_status;
+    pub fn napi_set_property(
+        env: napi_env,
+        object: napi_value,
+        key: napi_value,
+        value: napi_value,
+    ) -> napi_status;
+    pub fn napi_get_property(
+        env: napi_env,
+        object: napi_value,
+        key: napi_value,
+        result: *mut napi_value,
+    ) -> napi_status;
+    pub fn napi_is_array(env: napi_env, value: napi_value, result: *mut bool) -> napi_status;
+    pub fn napi_is_date(env: napi_env, value: napi_value, is_date: *mut bool) -> napi_status;
+    pub fn napi_create_symbol(
+        env: napi_env,
+        description: napi_value,
+        result: *mut napi_value,
+    ) -> napi_status;
+    pub fn napi_create_date(env: napi_env, time: f64, result: *mut napi_value) -> napi_status;
+    pub fn napi_get_date_value(env: napi_env, value: napi_value, result: *mut f64)
+        -> napi_status;
+    pub fn napi_create_bigint_words(
+        env: napi_env,
+        sign_bit: c_int,
+        word_count: usize,
+        words: *const u64,
+        result: *mut napi_value,
+    ) -> napi_status;
+    pub fn napi_get_value_bigint_words(
+        env: napi_env,
+        value: napi_value,
+        sign_bit: *mut c_int,
+        word_count: *mut usize,
+        words: *mut u64,
+    ) -> napi_status;
+    pub fn napi_is_promise(env: napi_env, value: napi_value, is_promise: *mut bool)
+        -> napi_status;
+    pub fn napi_throw(env: napi_env, error: napi_value) -> napi_status;
+    pub fn napi_is_exception_pending(env: napi_env, result: *mut bool) -> napi_status;
+    pub fn napi_create_error(
+        env: napi_env,
+        code: napi_value,
+        msg: napi_value,
+        result: *mut napi_value,
+    ) -> napi_status;
+    pub fn napi_create_type_error(
+        env: napi_env,
+        code: napi_value,
+        msg: napi_value,
+        result: *mut napi_value,
+    ) -> napi_status;
+    pub fn napi_create_range_error(
+        env: napi_env,
+        code: napi_value,
+        msg: napi_value,
+        result: *mut napi_value,
+    ) -> napi_status;
+    pub fn node_api_create_syntax_error(
+        env: napi_env,
+        code: napi_value,
+        msg: napi_value,
+        result: *mut napi_value,
+    ) -> napi_status;
+    pub fn napi_adjust_external_memory(
+        env: napi_env,
+        change_in_bytes: i64,
+        adjusted_value: *mut i64,
+    ) -> napi_status;
+    pub fn napi_create_external(
+        env: napi_env,
+        data: *mut c_void,
+        finalize_cb: napi_finalize,
+        finalize_hint: *mut c_void,
+        result: *mut napi_value,
+    ) -> napi_status;
+    pub fn napi_get_value_external(
+        env: napi_env,
+        value: napi_value,
+        result: *mut *mut c_void,
+    ) -> napi_status;
+    pub fn napi_create_external_buffer(
+        env: napi_env,
+        length: usize,
+        data: *mut c_void,
+        finalize_cb: napi_finalize,
+        finalize_hint: *mut c_void,
+        result: *mut napi_value,
+    ) -> napi_status;
+    pub fn napi_get_buffer_info(
+        env: napi_env,
+        value: napi_value,
+        data: *mut *mut c_void,
+        length: *mut usize,
+    ) -> napi_status;
+    pub fn napi_is_buffer(env: napi_env, value: napi_value, result: *mut bool) -> napi_status;
+    pub fn napi_type_tag_object(
+        env: napi_env,
+        value: napi_value,
+        type_tag: *const napi_type_tag,
+    ) -> napi_status;
+    pub fn napi_check_object_type_tag(
+        env: napi_env,
+        value: napi_value,
+        type_tag: *const napi_type_tag,
+        result: *mut bool,
+    ) -> napi_status;
+    pub fn napi_create_async_work(
+        env: napi_env,
+        async_resource: napi_value,
+        async_resource_name: napi_value,
+        execute: napi_async_execute_callback,
+        complete: napi_async_complete_callback,
+        data: *mut c_void,
+        result: *mut napi_async_work,
+    ) -> napi_status;
+    pub fn napi_delete_async_work(env: napi_env, work: napi_async_work) -> napi_status;
+    pub fn napi_queue_async_work(env: napi_env, work: napi_async_work) -> napi_status;
+    pub fn napi_cancel_async_work(env: napi_env, work: napi_async_work) -> napi_status;
+    pub fn napi_create_promise(
+        env: napi_env,
+        deferred: *mut napi_deferred,
+        promise: *mut napi_value,
+    ) -> napi_status;
+    pub fn napi_resolve_deferred(
+        env: napi_env,
+        deferred: napi_deferred,
+        resolution: napi_value,
+    ) -> napi_status;
+    pub fn napi_reject_deferred(
+        env: napi_env,
+        deferred: napi_deferred,
+        rejection: napi_value,
+    ) -> napi_status;
+    pub fn napi_create_reference(
+        env: napi_env,
+        value: napi_value,
+        initial_refcount: u32,
+        result: *mut napi_ref,
+    ) -> napi_status;
+    pub fn napi_delete_reference(env: napi_env, reference: napi_ref) -> napi_status;
+    pub fn napi_get_reference_value(
+        env: napi_env,
+        reference: napi_ref,
+        result: *mut napi_value,
+    ) -> napi_status;
+    pub fn napi_add_finalizer(
+        env: napi_env,
+        js_object: napi_value,
+        finalize_data: *mut c_void,
+        finalize_cb: napi_finalize,
+        finalize_hint: *mut c_void,
+        result: *mut napi_ref,
+    ) -> napi_status;
+    pub fn napi_call_function(
+        env: napi_env,
+        recv: napi_value,
+        func: napi_value,
+        argc: usize,
+        argv: *const napi_value,
+        result: *mut napi_value,
+    ) -> napi_status;
+    pub fn napi_get_and_clear_last_exception(env: napi_env, result: *mut napi_value)
+        -> napi_status;
+    pub fn napi_create_threadsafe_function(
+        env: napi_env,
+        func: napi_value,
+        async_resource: napi_value,
+        async_resource_name: napi_value,
+        max_queue_size: usize,
+        initial_thread_count: usize,
+        thread_finalize_data: *mut c_void,
+        thread_finalize_cb: napi_finalize,
+        context: *mut c_void,
+        call_js_cb: napi_threadsafe_function_call_js,
+        result: *mut napi_threadsafe_function,
+    ) -> napi_status;
+    pub fn napi_call_threadsafe_function(
+        func: napi_threadsafe_function,
+        data: *mut c_void,
+        is_blocking: napi_threadsafe_function_call_mode,
+    ) -> napi_status;
+    pub fn napi_acquire_threadsafe_function(func: napi_threadsafe_function) -> napi_status;
+    pub fn napi_release_threadsafe_function(
+        func: napi_threadsafe_function,
+        mode: napi_threadsafe_function_release_mode,
+    ) -> napi_status;
+    pub fn napi_ref_threadsafe_function(env: napi_env, func: napi_threadsafe_function)
+        -> napi_status;
+    pub fn napi_unref_threadsafe_function(env: napi_env, func: napi_threadsafe_function)
+        -> napi_status;
+    pub fn napi_get_global(env: napi_env, result: *mut napi_value) -> napi_status;
 }
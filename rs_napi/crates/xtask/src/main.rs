@@ -0,0 +1,111 @@
+// 工作区的构建任务，通过 `cargo xtask <task>` 运行：
+//   gen-dts [--check] [cargo build 参数...]  重新编译 apisecond，把 #[api] 宏输出的声明合并成 index.d.ts，
+//                                            --check 时只比较，index.d.ts 过期时以非 0 状态退出
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::{self, Command};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// 和 backend 中 typedef.rs 使用的环境变量相同
+const TYPE_DEF_DIR: &str = "API_TYPE_DEF_DIR";
+
+const HEADER: &str = "/* auto-generated by `cargo xtask gen-dts` */\n/* eslint-disable */\n\n";
+
+const FOOTER: &str = "
+/** 每个导出函数的文档注释、参数和返回值描述 */
+export const __meta: Record<string, { doc: string; params: { name: string; type: string; optional: boolean; rest: boolean }[]; returns: string }>;
+/** 模块注册的所有导出，按名称排序 */
+export const __registry: { name: string; kind: 'function' | 'async' | 'iterator' | 'value'; arity: number }[];
+";
+
+fn usage() -> ! {
+    eprintln!("usage: cargo xtask gen-dts [--check] [cargo build args...]");
+    process::exit(2);
+}
+
+fn main() {
+    let mut args = env::args().skip(1);
+    match args.next().as_deref() {
+        Some("gen-dts") => gen_dts(args.collect()),
+        _ => usage(),
+    }
+}
+
+fn workspace_root() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .ancestors()
+        .nth(2)
+        .expect("xtask is in crates/xtask")
+        .to_path_buf()
+}
+
+fn gen_dts(mut args: Vec<String>) {
+    let check = match args.iter().position(|arg| arg == "--check") {
+        Some(index) => {
+            args.remove(index);
+            true
+        }
+        None => false,
+    };
+    let output = workspace_root().join("crates/apisecond/index.d.ts");
+    let dts = collect_type_defs(&args).unwrap_or_else(|err| {
+        eprintln!("failed to collect type definitions: {}", err);
+        process::exit(1);
+    });
+    if check {
+        if fs::read_to_string(&output).ok().as_deref() != Some(dts.as_str()) {
+            eprintln!(
+                "{} is out of date, run `cargo xtask gen-dts` to update it",
+                output.display()
+            );
+            process::exit(1);
+        }
+        return;
+    }
+    fs::write(&output, dts).unwrap_or_else(|err| {
+        eprintln!("failed to write {}: {}", output.display(), err);
+        process::exit(1);
+    });
+}
+
+// 在一个新的临时目录中收集声明：目录的路径每次都不同，apisecond 的 build.rs 通过
+// rerun-if-env-changed 保证 crate 被重新编译，每个 #[api] 都会重新写出自己的声明
+fn collect_type_defs(args: &[String]) -> io::Result<String> {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_nanos());
+    let dir = env::temp_dir().join(format!("apisecond-dts-{}-{}", process::id(), nanos));
+    fs::create_dir_all(&dir)?;
+    let result = build_with_type_defs(&dir, args);
+    fs::remove_dir_all(&dir)?;
+    result
+}
+
+fn build_with_type_defs(dir: &Path, args: &[String]) -> io::Result<String> {
+    let cargo = env::var_os("CARGO").unwrap_or_else(|| "cargo".into());
+    let status = Command::new(cargo)
+        .current_dir(workspace_root())
+        .args(["build", "-p", "apisecond"])
+        .args(args)
+        .env(TYPE_DEF_DIR, dir)
+        .status()?;
+    if !status.success() {
+        return Err(io::Error::other(format!("cargo build failed: {}", status)));
+    }
+
+    // 按文件名排序，生成的内容不依赖宏展开的顺序
+    let mut files = fs::read_dir(dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<io::Result<Vec<_>>>()?;
+    files.retain(|path| path.extension().is_some_and(|ext| ext == "ts"));
+    files.sort();
+
+    let mut dts = HEADER.to_string();
+    for file in files {
+        dts.push_str(&fs::read_to_string(file)?);
+    }
+    dts.push_str(FOOTER);
+    Ok(dts)
+}